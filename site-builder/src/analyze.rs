@@ -0,0 +1,268 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Analysis of the sizes of the resources of a site directory, to guide its optimization.
+
+use std::{collections::BTreeMap, path::Path};
+
+use anyhow::Result;
+
+use crate::{
+    publish::DEFAULT_WS_RESOURCES_FILE,
+    site::resource::{infer_content_type, FileLister, Mount},
+};
+
+/// The size from which a compressible resource is suggested to be minified: 32 KiB.
+const MINIFY_THRESHOLD: u64 = 32 * 1024;
+
+/// The maximum number of paths listed in a suggestion.
+const SUGGESTED_PATHS: usize = 5;
+
+/// A file of the site directory, as it would be published.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct AnalyzedResource {
+    pub path: String,
+    pub content_type: String,
+    pub size: u64,
+}
+
+/// The breakdown of the sizes of the resources of a site.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct SizeReport {
+    /// The number of resources and their total size, by content type, in decreasing size order.
+    pub by_type: Vec<(String, usize, u64)>,
+    /// The largest resources, in decreasing size order.
+    pub largest: Vec<AnalyzedResource>,
+    /// The resources with a compressible content type, in decreasing size order.
+    ///
+    /// The resources are always stored with the `identity` encoding, so all of them are stored
+    /// uncompressed.
+    pub compressible: Vec<AnalyzedResource>,
+    /// The resources larger than the threshold, in decreasing size order.
+    pub large: Vec<AnalyzedResource>,
+    pub total_size: u64,
+    pub total_count: usize,
+}
+
+/// Lists the files of the directory with their size and inferred content type.
+///
+/// The content types are inferred from the extensions and contents only, ignoring the ones set in
+/// `ws-resources.json`, which is itself not a resource.
+pub(crate) fn read_directory(
+    directory: &Path,
+    file_lister: FileLister,
+) -> Result<Vec<AnalyzedResource>> {
+    let mount = Mount::root(directory.to_owned());
+    let ws_resources_path = directory.join(DEFAULT_WS_RESOURCES_FILE);
+    file_lister
+        .iter_dir(directory)?
+        .into_iter()
+        .filter(|full_path| *full_path != ws_resources_path)
+        .map(|full_path| {
+            Ok(AnalyzedResource {
+                path: mount.resource_path(&full_path)?,
+                content_type: infer_content_type(&full_path).to_string(),
+                size: std::fs::metadata(&full_path)?.len(),
+            })
+        })
+        .collect()
+}
+
+impl SizeReport {
+    /// Computes the report, with at most `top` of the largest resources.
+    pub fn new(mut resources: Vec<AnalyzedResource>, top: usize, large_threshold: u64) -> Self {
+        resources.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.path.cmp(&b.path)));
+        let mut by_type: BTreeMap<&str, (usize, u64)> = BTreeMap::new();
+        for resource in resources.iter() {
+            let entry = by_type
+                .entry(media_type(&resource.content_type))
+                .or_default();
+            entry.0 += 1;
+            entry.1 += resource.size;
+        }
+        let mut by_type = by_type
+            .into_iter()
+            .map(|(content_type, (count, size))| (content_type.to_owned(), count, size))
+            .collect::<Vec<_>>();
+        by_type.sort_by(|a, b| b.2.cmp(&a.2).then_with(|| a.0.cmp(&b.0)));
+        Self {
+            by_type,
+            largest: resources.iter().take(top).cloned().collect(),
+            compressible: resources
+                .iter()
+                .filter(|resource| is_compressible(&resource.content_type))
+                .cloned()
+                .collect(),
+            large: resources
+                .iter()
+                .filter(|resource| resource.size > large_threshold)
+                .cloned()
+                .collect(),
+            total_size: resources.iter().map(|resource| resource.size).sum(),
+            total_count: resources.len(),
+        }
+    }
+
+    /// Prints the report, followed by the suggested actions.
+    pub fn print(&self) {
+        println!(
+            "Total: {} resources, {} bytes",
+            self.total_count, self.total_size
+        );
+        println!("\nBy content type:");
+        for (content_type, count, size) in self.by_type.iter() {
+            println!(
+                "  {content_type:<40} {count:>6} resources {size:>12} bytes ({:.1}%)",
+                percentage(*size, self.total_size)
+            );
+        }
+        println!("\nLargest resources:");
+        for resource in self.largest.iter() {
+            println!(
+                "  {:<60} {:>12} bytes  {}",
+                resource.path, resource.size, resource.content_type
+            );
+        }
+        let compressible_size: u64 = self.compressible.iter().map(|resource| resource.size).sum();
+        println!(
+            "\nCompressible resources stored uncompressed: {} ({compressible_size} bytes)",
+            self.compressible.len()
+        );
+
+        let suggestions = self.suggestions();
+        if !suggestions.is_empty() {
+            println!("\nSuggestions:");
+            for suggestion in suggestions {
+                println!("  - {suggestion}");
+            }
+        }
+    }
+
+    /// Returns the actions that would reduce the size of the site the most.
+    fn suggestions(&self) -> Vec<String> {
+        let mut suggestions = vec![];
+        // Only the compressible resources of a significant size are worth minifying.
+        let to_minify = self
+            .compressible
+            .iter()
+            .filter(|resource| resource.size >= MINIFY_THRESHOLD)
+            .map(|resource| resource.path.as_str())
+            .take(SUGGESTED_PATHS)
+            .collect::<Vec<_>>();
+        if !to_minify.is_empty() {
+            suggestions.push(format!(
+                "minify the largest text resources at build time, as the portal serves them as \
+                stored: {}",
+                to_minify.join(", ")
+            ));
+        }
+        if !self.large.is_empty() {
+            suggestions.push(format!(
+                "split the resources that load slowly when served by the portal into smaller \
+                files: {}",
+                self.large
+                    .iter()
+                    .map(|resource| resource.path.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ));
+        }
+        if let Some((content_type, _, size)) = self.by_type.first() {
+            if content_type.starts_with("image/")
+                && !content_type.ends_with("webp")
+                && !content_type.ends_with("avif")
+                && percentage(*size, self.total_size) >= 50.0
+            {
+                suggestions.push(format!(
+                    "{content_type} makes up most of the site; converting the images to WebP or \
+                    AVIF usually makes them much smaller"
+                ));
+            }
+        }
+        suggestions
+    }
+}
+
+/// Returns the media type of the content type, without parameters like `charset`.
+fn media_type(content_type: &str) -> &str {
+    content_type
+        .split(';')
+        .next()
+        .unwrap_or(content_type)
+        .trim()
+}
+
+/// Returns `true` if resources of the content type usually compress well.
+fn is_compressible(content_type: &str) -> bool {
+    let media_type = media_type(content_type);
+    media_type.starts_with("text/")
+        || media_type.ends_with("+json")
+        || media_type.ends_with("+xml")
+        || matches!(
+            media_type,
+            "application/json"
+                | "application/javascript"
+                | "application/xml"
+                | "application/wasm"
+                | "image/svg+xml"
+        )
+}
+
+fn percentage(part: u64, total: u64) -> f64 {
+    if total == 0 {
+        return 0.0;
+    }
+    part as f64 * 100.0 / total as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn resource(path: &str, content_type: &str, size: u64) -> AnalyzedResource {
+        AnalyzedResource {
+            path: path.to_owned(),
+            content_type: content_type.to_owned(),
+            size,
+        }
+    }
+
+    #[test]
+    fn test_size_report() {
+        let report = SizeReport::new(
+            vec![
+                resource("/index.html", "text/html; charset=utf-8", 100_000),
+                resource("/app.js", "text/javascript", 300),
+                resource("/logo.png", "image/png", 500_000),
+                resource("/photo.png", "image/png", 700_000),
+            ],
+            2,
+            600_000,
+        );
+        assert_eq!(report.total_count, 4);
+        assert_eq!(report.total_size, 1_300_300);
+        assert_eq!(
+            report.by_type,
+            vec![
+                ("image/png".to_owned(), 2, 1_200_000),
+                ("text/html".to_owned(), 1, 100_000),
+                ("text/javascript".to_owned(), 1, 300),
+            ]
+        );
+        assert_eq!(
+            report
+                .largest
+                .iter()
+                .map(|resource| resource.path.as_str())
+                .collect::<Vec<_>>(),
+            vec!["/photo.png", "/logo.png"]
+        );
+        assert_eq!(report.compressible.len(), 2);
+        assert_eq!(
+            report.large,
+            vec![resource("/photo.png", "image/png", 700_000)]
+        );
+        // Minify the HTML page, split the photo, and convert the PNG images.
+        assert_eq!(report.suggestions().len(), 3);
+    }
+}
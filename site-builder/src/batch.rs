@@ -0,0 +1,178 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Execution of operations over many sites, or over many networks.
+
+use std::num::NonZeroUsize;
+
+use anyhow::{bail, Result};
+use futures::{stream, Future, StreamExt};
+use sui_types::base_types::ObjectID;
+
+use crate::{display, profiles::Network};
+
+/// Runs the operation on each of the sites, on at most `max_concurrent` sites at a time.
+///
+/// The sites are started in order, so they are processed in order if `max_concurrent` is 1.
+/// If `keep_going` is set, failures on a site are reported and the operation continues with the
+/// next site; an error is returned at the end if any of the sites failed. Otherwise, the first
+/// failure is returned immediately, and the operations still running are dropped.
+pub(crate) async fn for_each_site<F, Fut>(
+    sites: &[ObjectID],
+    keep_going: bool,
+    max_concurrent: NonZeroUsize,
+    mut operation: F,
+) -> Result<()>
+where
+    F: FnMut(ObjectID) -> Fut,
+    Fut: Future<Output = Result<()>>,
+{
+    let mut failures = vec![];
+    let mut results = stream::iter(sites)
+        .map(|site| {
+            let operation = operation(*site);
+            async move { (*site, operation.await) }
+        })
+        .buffer_unordered(max_concurrent.get());
+    while let Some((site, result)) = results.next().await {
+        match result {
+            Ok(()) => (),
+            Err(error) if keep_going => {
+                display::error(format!("Error for site {site}: {error:#}"));
+                failures.push((site, error));
+            }
+            Err(error) => return Err(error),
+        }
+    }
+
+    if sites.len() > 1 {
+        display::header("Batch summary");
+        for site in sites {
+            let status = if failures.iter().any(|(failed, _)| failed == site) {
+                "failed"
+            } else {
+                "ok"
+            };
+            println!("  - {site}: {status}");
+        }
+    }
+    if !failures.is_empty() {
+        bail!("{} of {} sites failed", failures.len(), sites.len());
+    }
+    Ok(())
+}
+
+/// Runs the operation on each of the networks in turn, and prints the site of each network.
+///
+/// The operation returns the object ID of the site, if one was published. If `keep_going` is set,
+/// failures on a network are reported and the operation continues with the next network; an error
+/// is returned at the end if any of the networks failed. Otherwise, the first failure is returned
+/// immediately.
+pub(crate) async fn for_each_network<F, Fut>(
+    networks: &[Network],
+    keep_going: bool,
+    mut operation: F,
+) -> Result<()>
+where
+    F: FnMut(Network) -> Fut,
+    Fut: Future<Output = Result<Option<ObjectID>>>,
+{
+    let name = |network: &Network| format!("{network:?}").to_lowercase();
+    let mut results = vec![];
+    for network in networks {
+        display::header(format!("Publishing on {}", name(network)));
+        match operation(*network).await {
+            Ok(site) => results.push((network, Some(site))),
+            Err(error) if keep_going => {
+                display::error(format!("Error on {}: {error:#}", name(network)));
+                results.push((network, None));
+            }
+            Err(error) => return Err(error),
+        }
+    }
+
+    display::header("Network summary");
+    for (network, result) in results.iter() {
+        let (status, site) = match result {
+            Some(Some(site)) => ("ok", site.to_string()),
+            Some(None) => ("ok", "no site published".to_owned()),
+            None => ("failed", "-".to_owned()),
+        };
+        println!("  - {:<10} {status:<7} {site}", name(network));
+    }
+    let n_failures = results
+        .iter()
+        .filter(|(_, result)| result.is_none())
+        .count();
+    if n_failures > 0 {
+        bail!("{n_failures} of {} networks failed", networks.len());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use anyhow::anyhow;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_for_each_site() {
+        let sites = (0..6).map(|_| ObjectID::random()).collect::<Vec<_>>();
+        let running = AtomicUsize::new(0);
+        let max_running = AtomicUsize::new(0);
+        let result = for_each_site(&sites, true, NonZeroUsize::new(2).unwrap(), |site| {
+            let running = &running;
+            let max_running = &max_running;
+            let fails = site == sites[1] || site == sites[4];
+            async move {
+                let now_running = running.fetch_add(1, Ordering::SeqCst) + 1;
+                max_running.fetch_max(now_running, Ordering::SeqCst);
+                tokio::task::yield_now().await;
+                running.fetch_sub(1, Ordering::SeqCst);
+                if fails {
+                    Err(anyhow!("failed"))
+                } else {
+                    Ok(())
+                }
+            }
+        })
+        .await;
+        assert_eq!(result.unwrap_err().to_string(), "2 of 6 sites failed");
+        assert_eq!(max_running.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_for_each_network() {
+        let networks = [Network::Testnet, Network::Mainnet, Network::Devnet];
+        let mut published = vec![];
+        let result = for_each_network(&networks, true, |network| {
+            published.push(network);
+            async move {
+                match network {
+                    Network::Mainnet => Err(anyhow!("failed")),
+                    _ => Ok(Some(ObjectID::ZERO)),
+                }
+            }
+        })
+        .await;
+        assert_eq!(result.unwrap_err().to_string(), "1 of 3 networks failed");
+        assert_eq!(published, networks);
+
+        let mut published = vec![];
+        let result = for_each_network(&networks, false, |network| {
+            published.push(network);
+            async move {
+                match network {
+                    Network::Mainnet => Err(anyhow!("failed")),
+                    _ => Ok(None),
+                }
+            }
+        })
+        .await;
+        assert_eq!(result.unwrap_err().to_string(), "failed");
+        assert_eq!(published, networks[..2]);
+    }
+}
@@ -0,0 +1,249 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Printing of the effective configuration, and of where each of its values comes from.
+
+use std::{fmt::Display, path::PathBuf};
+
+use crate::{
+    default,
+    util::{wallet_config_path, SUI_CONFIG_DIR_ENV},
+    Config,
+    GeneralArgs,
+};
+
+/// Where a value of the effective configuration comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ValueSource {
+    Default,
+    Profile,
+    Config,
+    Env,
+    Flag,
+}
+
+impl Display for ValueSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let source = match self {
+            ValueSource::Default => "default",
+            ValueSource::Profile => "network profile",
+            ValueSource::Config => "config file",
+            ValueSource::Env => "environment",
+            ValueSource::Flag => "CLI flag",
+        };
+        write!(f, "{source}")
+    }
+}
+
+/// Returns the source of an optional value, given the values in the CLI and in the config file.
+///
+/// A value in the config file that is equal to the serde default is reported as a default.
+fn source<T: PartialEq>(flag: &Option<T>, file: &Option<T>, default: &Option<T>) -> ValueSource {
+    if flag.is_some() {
+        ValueSource::Flag
+    } else if file.is_some() && file != default {
+        ValueSource::Config
+    } else {
+        ValueSource::Default
+    }
+}
+
+/// Returns the source of a boolean flag, given the values in the CLI and in the config file.
+fn flag_source(flag: bool, file: bool) -> ValueSource {
+    if flag {
+        ValueSource::Flag
+    } else if file {
+        ValueSource::Config
+    } else {
+        ValueSource::Default
+    }
+}
+
+fn or_unset<T: Display>(value: &Option<T>) -> String {
+    value
+        .as_ref()
+        .map_or_else(|| "(unset)".to_owned(), |value| value.to_string())
+}
+
+fn or_unset_path(value: &Option<PathBuf>) -> String {
+    or_unset(&value.as_ref().map(|path| path.display()))
+}
+
+/// Prints the effective configuration, annotating each value with its source.
+///
+/// `file_config` is the configuration as read from the config file, and `cli` the arguments
+/// specified on the command line; `config` is the result of merging them and applying the network
+/// profile. No network calls are made.
+pub(crate) fn print_effective_config(config: &Config, file_config: &Config, cli: &GeneralArgs) {
+    let general = &config.general;
+    let file = &file_config.general;
+    let print = |name: &str, value: String, source: ValueSource| {
+        println!("{name:<26} {value:<70} ({source})");
+    };
+    let profile = general.network.map(|network| network.profile());
+
+    print(
+        "network:",
+        or_unset(
+            &general
+                .network
+                .map(|network| format!("{network:?}").to_lowercase()),
+        ),
+        source(&cli.networks.first().copied(), &file.network, &None),
+    );
+    let rpc_source = match source(&cli.rpc_url, &file.rpc_url, &None) {
+        ValueSource::Default if profile.is_some() => ValueSource::Profile,
+        source => source,
+    };
+    print("rpc url:", or_unset(&general.rpc_url), rpc_source);
+    let network_package = general
+        .network
+        .and_then(|network| file_config.networks.get(&network))
+        .and_then(|network_config| network_config.package);
+    let package_source = match (network_package, &profile, file_config.package) {
+        (Some(_), _, _) => ValueSource::Config,
+        (None, Some(profile), _) if profile.package.is_some() => ValueSource::Profile,
+        (None, _, Some(_)) => ValueSource::Config,
+        _ => ValueSource::Default,
+    };
+    print("package:", or_unset(&config.package), package_source);
+    let portal_source = if file_config.portal != default::default_portal() {
+        ValueSource::Config
+    } else {
+        ValueSource::Default
+    };
+    print("portal:", config.portal.clone(), portal_source);
+    print(
+        "portal url template:",
+        or_unset(&config.portal_url_template),
+        source(&None, &file_config.portal_url_template, &None),
+    );
+
+    let sui_config_dir = std::env::var_os(SUI_CONFIG_DIR_ENV);
+    let wallet_source = match source(&cli.wallet, &file.wallet, &None) {
+        ValueSource::Default if sui_config_dir.is_some() => ValueSource::Env,
+        source => source,
+    };
+    let wallet = wallet_config_path(&general.wallet)
+        .map(|path| path.display().to_string())
+        .unwrap_or_else(|_| "(not found)".to_owned());
+    print("wallet:", wallet, wallet_source);
+    let (signer, signer_source) = match (&general.signer_command, &general.keyring_service) {
+        (Some(command), _) => (
+            format!("signer command `{command}`"),
+            source(&cli.signer_command, &file.signer_command, &None),
+        ),
+        (None, Some(service)) => (
+            format!(
+                "OS secret store, service `{service}`, account `{}`",
+                general
+                    .keyring_account
+                    .as_deref()
+                    .unwrap_or("(active address)")
+            ),
+            source(&cli.keyring_service, &file.keyring_service, &None),
+        ),
+        (None, None) => ("wallet keystore".to_owned(), ValueSource::Default),
+    };
+    print("signer:", signer, signer_source);
+    print(
+        "proxy:",
+        or_unset(&general.proxy),
+        source(&cli.proxy, &file.proxy, &None),
+    );
+    print(
+        "retryable errors:",
+        general
+            .retryable_errors
+            .as_ref()
+            .map_or("(built-in)".to_owned(), |errors| errors.join(", ")),
+        source(&cli.retryable_errors, &file.retryable_errors, &None),
+    );
+
+    print(
+        "walrus binary:",
+        config.walrus_binary(),
+        source(
+            &cli.walrus_binary,
+            &file.walrus_binary,
+            &default::walrus_binary(),
+        ),
+    );
+    print(
+        "walrus config:",
+        or_unset_path(&general.walrus_config),
+        source(&cli.walrus_config, &file.walrus_config, &None),
+    );
+    print(
+        "gas budget:",
+        config.gas_budget().to_string(),
+        source(&cli.gas_budget, &file.gas_budget, &default::gas_budget()),
+    );
+    print(
+        "max budget:",
+        or_unset(&general.max_budget),
+        source(&cli.max_budget, &file.max_budget, &None),
+    );
+    print(
+        "deadline (s):",
+        or_unset(&general.deadline),
+        source(&cli.deadline, &file.deadline, &None),
+    );
+    print(
+        "batch upload threshold:",
+        or_unset(&general.batch_upload_threshold),
+        source(
+            &cli.batch_upload_threshold,
+            &file.batch_upload_threshold,
+            &None,
+        ),
+    );
+    print(
+        "audit log:",
+        or_unset_path(&general.audit_log),
+        source(&cli.audit_log, &file.audit_log, &None),
+    );
+    print(
+        "label:",
+        or_unset(&general.label),
+        source(&cli.label, &file.label, &None),
+    );
+    print(
+        "confirm local execution:",
+        (!general.no_confirm_local_execution).to_string(),
+        flag_source(
+            cli.no_confirm_local_execution,
+            file.no_confirm_local_execution,
+        ),
+    );
+    print(
+        "package version check:",
+        (!general.skip_package_version_check).to_string(),
+        flag_source(
+            cli.skip_package_version_check,
+            file.skip_package_version_check,
+        ),
+    );
+    print(
+        "strict:",
+        general.strict.to_string(),
+        flag_source(cli.strict, file.strict),
+    );
+    println!(
+        "\nThe number of epochs is set per command; see the `--help` of `publish` and `update`."
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_source() {
+        assert_eq!(source(&Some(1), &Some(2), &None), ValueSource::Flag);
+        assert_eq!(source(&None, &Some(2), &None), ValueSource::Config);
+        assert_eq!(source(&None, &Some(2), &Some(2)), ValueSource::Default);
+        assert_eq!(source::<u64>(&None, &None, &None), ValueSource::Default);
+        assert_eq!(flag_source(false, true), ValueSource::Config);
+    }
+}
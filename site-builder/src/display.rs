@@ -4,6 +4,10 @@
 use std::{
     fmt::Display,
     io::{stderr, stdout},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
 };
 
 use crossterm::{
@@ -12,8 +16,31 @@ use crossterm::{
     terminal::{Clear, ClearType},
 };
 
+/// Whether the progress output to stdout is suppressed.
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+/// Suppresses the headers and the progress of the actions on stdout.
+///
+/// Errors and warnings are still printed, as they go to stderr.
+pub fn set_quiet() {
+    QUIET.store(true, Ordering::Relaxed);
+}
+
+/// Whether the warnings of the checks of the site are errors.
+static STRICT: AtomicBool = AtomicBool::new(false);
+
+/// Makes [`check_warning`] fail instead of displaying the warning.
+pub fn set_strict() {
+    STRICT.store(true, Ordering::Relaxed);
+}
+
+/// Returns `true` if the output to stdout is enabled.
+fn stdout_enabled() -> bool {
+    cfg!(not(test)) && !QUIET.load(Ordering::Relaxed)
+}
+
 pub fn header<S: Display>(message: S) {
-    if cfg!(not(test)) {
+    if stdout_enabled() {
         crossterm::execute!(
             stdout(),
             PrintStyledContent(format!("\n{message}\n").green().bold()),
@@ -32,14 +59,47 @@ pub fn error<S: Display>(message: S) {
     }
 }
 
-pub fn action<S: Display>(message: S) {
+pub fn warning<S: Display>(message: S) {
     if cfg!(not(test)) {
+        crossterm::execute!(
+            stderr(),
+            PrintStyledContent(format!("Warning: {message}\n").yellow()),
+        )
+        .unwrap();
+    }
+}
+
+/// Displays a warning of a check of the site, or fails with it in strict mode.
+///
+/// The warnings about the execution, which do not depend on the site, use [`warning`] instead.
+pub fn check_warning<S: Display>(message: S) -> anyhow::Result<()> {
+    if STRICT.load(Ordering::Relaxed) {
+        anyhow::bail!("{message}\n(warnings are errors with --strict)");
+    }
+    warning(message);
+    Ok(())
+}
+
+/// The last action started, to report the progress in case of interruption.
+static LAST_ACTION: Mutex<Option<String>> = Mutex::new(None);
+
+/// Returns the message of the last action started.
+pub fn last_action() -> Option<String> {
+    LAST_ACTION
+        .lock()
+        .expect("the lock is not poisoned")
+        .clone()
+}
+
+pub fn action<S: Display>(message: S) {
+    *LAST_ACTION.lock().expect("the lock is not poisoned") = Some(message.to_string());
+    if stdout_enabled() {
         crossterm::execute!(stdout(), Print(format!("{message} ... ")), SavePosition).unwrap();
     }
 }
 
 pub fn done() {
-    if cfg!(not(test)) {
+    if stdout_enabled() {
         crossterm::execute!(
             stdout(),
             RestorePosition,
@@ -49,3 +109,15 @@ pub fn done() {
         .unwrap();
     }
 }
+
+pub fn failed() {
+    if stdout_enabled() {
+        crossterm::execute!(
+            stdout(),
+            RestorePosition,
+            Clear(ClearType::UntilNewLine),
+            Print(format!("[{}]\n", "Failed".red()))
+        )
+        .unwrap();
+    }
+}
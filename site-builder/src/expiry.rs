@@ -0,0 +1,108 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Checks of the storage lifetime of the blobs of a site, for monitoring.
+
+use std::collections::BTreeMap;
+
+use anyhow::{Context, Result};
+
+use crate::{
+    site::SiteData,
+    walrus::{output::Epoch, types::BlobId, Walrus},
+};
+
+/// The exit code of `check-expiry` if a blob expires within the threshold.
+///
+/// Distinct from the exit code of the other errors, e.g., if the site cannot be read.
+pub(crate) const EXPIRING_EXIT_CODE: i32 = 2;
+
+/// The storage lifetime of the blob of a resource.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct BlobExpiry {
+    pub path: String,
+    pub blob_id: BlobId,
+    /// The epoch until which the blob is certified (exclusive), or `None` if the blob is not
+    /// certified, e.g., because it already expired or it is deletable.
+    pub certified_until: Option<Epoch>,
+}
+
+/// Reads the storage lifetime of the blobs of all the resources of the site.
+///
+/// The status of each blob is read once, even if it is shared by several resources.
+pub(crate) async fn read_expiries(walrus: &Walrus, site: &SiteData) -> Result<Vec<BlobExpiry>> {
+    let mut statuses = BTreeMap::new();
+    let mut expiries = vec![];
+    for resource in site.resources().inner.iter() {
+        let blob_id = resource.info.blob_id;
+        let certified_until = match statuses.get(&blob_id) {
+            Some(certified_until) => *certified_until,
+            None => {
+                let certified_until = walrus
+                    .blob_status(blob_id)
+                    .await
+                    .context(format!("could not get the status of the blob {blob_id}"))?
+                    .status
+                    .certified_until();
+                statuses.insert(blob_id, certified_until);
+                certified_until
+            }
+        };
+        expiries.push(BlobExpiry {
+            path: resource.info.path.clone(),
+            blob_id,
+            certified_until,
+        });
+    }
+    Ok(expiries)
+}
+
+/// Returns the blobs that are not certified until after `until_epoch`, the soonest expiring first.
+pub(crate) fn expiring(expiries: &[BlobExpiry], until_epoch: Epoch) -> Vec<&BlobExpiry> {
+    let mut expiring = expiries
+        .iter()
+        .filter(|expiry| {
+            expiry
+                .certified_until
+                .map_or(true, |certified_until| certified_until <= until_epoch)
+        })
+        .collect::<Vec<_>>();
+    // The blobs that are not certified come first.
+    expiring.sort_by(|a, b| {
+        a.certified_until
+            .cmp(&b.certified_until)
+            .then_with(|| a.path.cmp(&b.path))
+    });
+    expiring
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expiry(path: &str, certified_until: Option<Epoch>) -> BlobExpiry {
+        BlobExpiry {
+            path: path.to_owned(),
+            blob_id: BlobId([0; BlobId::LENGTH]),
+            certified_until,
+        }
+    }
+
+    #[test]
+    fn test_expiring() {
+        let expiries = vec![
+            expiry("/index.html", Some(20)),
+            expiry("/style.css", Some(15)),
+            expiry("/app.js", Some(12)),
+            expiry("/gone.png", None),
+        ];
+        let paths = |until_epoch| {
+            expiring(&expiries, until_epoch)
+                .into_iter()
+                .map(|expiry| expiry.path.as_str())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(paths(15), vec!["/gone.png", "/app.js", "/style.css"]);
+        assert_eq!(paths(10), vec!["/gone.png"]);
+    }
+}
@@ -0,0 +1,105 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! The revision of the sources of a site, read from the git repository containing them.
+
+use std::{
+    fmt,
+    path::Path,
+    process::{Command, Stdio},
+};
+
+use anyhow::{ensure, Context, Result};
+use serde::Serialize;
+
+/// The state of the git worktree from which a site is published.
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct GitMetadata {
+    /// The full SHA of the commit checked out.
+    pub git_commit: String,
+    /// The branch checked out, or `None` if the HEAD is detached.
+    pub git_branch: Option<String>,
+    /// Whether the worktree has changes not committed, including untracked files.
+    pub git_dirty: bool,
+}
+
+impl GitMetadata {
+    /// Reads the metadata of the git repository containing the directory.
+    ///
+    /// Fails if `git` cannot be run, or if the directory is not in a repository with commits.
+    pub fn read(directory: &Path) -> Result<Self> {
+        let git_commit = run_git(directory, &["rev-parse", "HEAD"])?.context(format!(
+            "{} is not in a git repository with commits",
+            directory.display()
+        ))?;
+        // Exits with an error, and prints nothing, if the HEAD is detached.
+        let git_branch = run_git(directory, &["symbolic-ref", "--short", "-q", "HEAD"])?;
+        let git_dirty = run_git(directory, &["status", "--porcelain"])?
+            .context("could not read the status of the git worktree")?;
+        Ok(Self {
+            git_commit,
+            git_branch,
+            git_dirty: !git_dirty.is_empty(),
+        })
+    }
+
+    /// Fails if the worktree has changes not committed.
+    pub fn ensure_clean(&self) -> Result<()> {
+        ensure!(
+            !self.git_dirty,
+            "the git worktree has changes not committed; commit or stash them, or publish \
+            without `--require-clean`"
+        );
+        Ok(())
+    }
+}
+
+impl fmt::Display for GitMetadata {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.git_commit)?;
+        match (&self.git_branch, self.git_dirty) {
+            (Some(branch), true) => write!(f, " ({branch}, dirty)"),
+            (Some(branch), false) => write!(f, " ({branch})"),
+            (None, true) => write!(f, " (detached, dirty)"),
+            (None, false) => write!(f, " (detached)"),
+        }
+    }
+}
+
+/// Runs git in the directory, and returns its trimmed standard output, or `None` if git exited
+/// with an error.
+fn run_git(directory: &Path, args: &[&str]) -> Result<Option<String>> {
+    tracing::debug!(?directory, ?args, "running git");
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(directory)
+        .args(args)
+        .stdin(Stdio::null())
+        .output()
+        .context("could not run git to read the revision of the site")?;
+    if !output.status.success() {
+        return Ok(None);
+    }
+    Ok(Some(String::from_utf8(output.stdout)?.trim().to_owned()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display() {
+        let mut metadata = GitMetadata {
+            git_commit: "0123abc".to_owned(),
+            git_branch: Some("main".to_owned()),
+            git_dirty: false,
+        };
+        assert_eq!(metadata.to_string(), "0123abc (main)");
+        assert!(metadata.ensure_clean().is_ok());
+        metadata.git_branch = None;
+        metadata.git_dirty = true;
+        assert_eq!(metadata.to_string(), "0123abc (detached, dirty)");
+        assert!(metadata.ensure_clean().is_err());
+    }
+}
@@ -0,0 +1,202 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Human-readable descriptions of the HTTP headers stored with the resources.
+
+use crate::types::HttpHeaders;
+
+/// The headers known to the site builder, without a specific description.
+const KNOWN_HEADERS: [(&str, &str); 6] = [
+    (
+        "accept-ranges",
+        "ignored by the portal, which always serves the whole resource",
+    ),
+    ("allow", "the HTTP methods declared as allowed"),
+    ("content-length", "the size of the content, in bytes"),
+    (
+        "cross-origin-embedder-policy",
+        "required for cross-origin isolation",
+    ),
+    (
+        "cross-origin-opener-policy",
+        "required for cross-origin isolation",
+    ),
+    ("etag", "the tag derived from the content, for revalidation"),
+];
+
+/// A row of the table of the headers of a resource.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct HeaderRow {
+    pub name: String,
+    pub value: String,
+    /// The meaning of the header, or `None` if the header is not known to the site builder.
+    pub note: Option<String>,
+}
+
+impl HeaderRow {
+    fn new(name: &str, value: &str) -> Self {
+        Self {
+            name: name.to_owned(),
+            value: value.to_owned(),
+            note: describe(&name.to_lowercase(), value),
+        }
+    }
+}
+
+/// Returns the rows of the headers, in name order.
+pub(crate) fn header_rows(headers: &HttpHeaders) -> Vec<HeaderRow> {
+    headers
+        .0
+        .iter()
+        .map(|(name, value)| HeaderRow::new(name, value))
+        .collect()
+}
+
+/// Formats the rows as a table with aligned columns; the unknown headers are flagged.
+pub(crate) fn to_table(rows: &[HeaderRow]) -> String {
+    let name_width = rows
+        .iter()
+        .map(|row| row.name.len())
+        .chain(["HEADER".len()])
+        .max()
+        .unwrap_or_default();
+    let value_width = rows
+        .iter()
+        .map(|row| row.value.len())
+        .chain(["VALUE".len()])
+        .max()
+        .unwrap_or_default();
+    let mut table = format!(
+        "{:<name_width$}  {:<value_width$}  NOTE\n",
+        "HEADER", "VALUE"
+    );
+    for row in rows {
+        let note = row
+            .note
+            .clone()
+            .unwrap_or_else(|| "unrecognized: not set by the site builder".to_owned());
+        table.push_str(
+            format!(
+                "{:<name_width$}  {:<value_width$}  {note}",
+                row.name, row.value
+            )
+            .trim_end(),
+        );
+        table.push('\n');
+    }
+    table
+}
+
+/// Returns the meaning of the header with the lowercase name, if it is known.
+fn describe(name: &str, value: &str) -> Option<String> {
+    match name {
+        "content-type" => Some(describe_content_type(value)),
+        "content-encoding" => Some(describe_content_encoding(value)),
+        "cache-control" => Some(describe_cache_control(value)),
+        "content-security-policy" => Some("the sources the page can load".to_owned()),
+        "content-security-policy-report-only" => {
+            Some("the sources the page can load, only reported if violated".to_owned())
+        }
+        _ => KNOWN_HEADERS
+            .iter()
+            .find(|(known, _)| *known == name)
+            .map(|(_, note)| (*note).to_owned()),
+    }
+}
+
+fn describe_content_type(value: &str) -> String {
+    let (essence, parameters) = value.split_once(';').unwrap_or((value, ""));
+    let essence = essence.trim().to_lowercase();
+    let kind = match essence.as_str() {
+        "text/html" => "an HTML page".to_owned(),
+        "text/css" => "a stylesheet".to_owned(),
+        "text/javascript" | "application/javascript" => "a script".to_owned(),
+        "application/json" => "JSON data".to_owned(),
+        "application/wasm" => "a WebAssembly module".to_owned(),
+        "application/octet-stream" => "binary data, usually downloaded by the browser".to_owned(),
+        _ => match essence.split_once('/') {
+            Some(("image", _)) => "an image".to_owned(),
+            Some(("font", _)) => "a font".to_owned(),
+            Some(("audio" | "video", _)) => "streamable media".to_owned(),
+            _ => format!("the media type {essence}"),
+        },
+    };
+    let charset = parameters
+        .split(';')
+        .filter_map(|parameter| parameter.trim().split_once('='))
+        .find(|(key, _)| key.eq_ignore_ascii_case("charset"))
+        .map(|(_, charset)| format!(", in {charset}"))
+        .unwrap_or_default();
+    format!("served as {kind}{charset}")
+}
+
+fn describe_content_encoding(value: &str) -> String {
+    match value.trim().to_lowercase().as_str() {
+        "identity" => "the content is stored uncompressed".to_owned(),
+        "gzip" | "br" | "deflate" | "zstd" => format!(
+            "the content is stored compressed with {value}, and decompressed by the browser"
+        ),
+        _ => format!("the unknown encoding {value}, which browsers may not decode"),
+    }
+}
+
+fn describe_cache_control(value: &str) -> String {
+    let directives: Vec<String> = value
+        .split(',')
+        .map(|directive| directive.trim().to_lowercase())
+        .filter(|directive| !directive.is_empty())
+        .map(|directive| match directive.split_once('=') {
+            Some(("max-age", seconds)) => format!("cached for {seconds} seconds"),
+            Some(("s-maxage", seconds)) => format!("cached by shared caches for {seconds} seconds"),
+            _ => match directive.as_str() {
+                "no-cache" => "revalidated on every request".to_owned(),
+                "no-store" => "never cached".to_owned(),
+                "immutable" => "never revalidated while fresh".to_owned(),
+                "public" => "cacheable by shared caches".to_owned(),
+                "private" => "only cacheable by the browser".to_owned(),
+                "must-revalidate" => "revalidated once stale".to_owned(),
+                _ => format!("`{directive}`"),
+            },
+        })
+        .collect();
+    directives.join("; ")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+
+    #[test]
+    fn test_header_rows() {
+        let headers = HttpHeaders(BTreeMap::from([
+            (
+                "cache-control".to_owned(),
+                "public, max-age=3600".to_owned(),
+            ),
+            ("content-encoding".to_owned(), "identity".to_owned()),
+            (
+                "content-type".to_owned(),
+                "text/html; charset=utf-8".to_owned(),
+            ),
+            ("x-custom".to_owned(), "1".to_owned()),
+        ]));
+        let rows = header_rows(&headers);
+        let notes: Vec<_> = rows.iter().map(|row| row.note.as_deref()).collect();
+        assert_eq!(
+            notes,
+            vec![
+                Some("cacheable by shared caches; cached for 3600 seconds"),
+                Some("the content is stored uncompressed"),
+                Some("served as an HTML page, in utf-8"),
+                None,
+            ]
+        );
+        assert_eq!(
+            to_table(&rows[3..]),
+            "HEADER    VALUE  NOTE\n\
+            x-custom  1      unrecognized: not set by the site builder\n"
+        );
+    }
+}
@@ -0,0 +1,161 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Extraction of the files of a site from a container image, without running the image.
+
+use std::{
+    path::{Component, Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+use anyhow::{anyhow, ensure, Context, Result};
+
+use crate::display;
+
+/// Copies the directory `image_path` of the image into `directory`, with the container CLI.
+///
+/// The image is pulled if needed, and a container is created from it, but never started; the
+/// container is removed afterwards. The directory must be empty or missing. The extracted files
+/// are then sanitized: the symbolic links that point outside of the directory, and the special
+/// files, e.g., devices, are removed.
+pub(crate) fn extract(cli: &str, image: &str, image_path: &str, directory: &Path) -> Result<()> {
+    ensure!(
+        image_path.starts_with('/'),
+        "the path in the image {image_path} must be absolute"
+    );
+    ensure!(
+        !directory.exists() || std::fs::read_dir(directory)?.next().is_none(),
+        "the directory {} must be empty or missing to extract the image into it",
+        directory.display()
+    );
+    std::fs::create_dir_all(directory)?;
+
+    display::action(format!("Extracting {image_path} from the image {image}"));
+    // The command is never run, but it is required to create a container from an image without
+    // a default command.
+    let container = run(cli, &["create", image, "/walrus-sites-extract"])?;
+    let source = format!("{container}:{}/.", image_path.trim_end_matches('/'));
+    let copied = run(
+        cli,
+        &[
+            "cp",
+            &source,
+            directory
+                .to_str()
+                .ok_or_else(|| anyhow!("the directory {} is not UTF-8", directory.display()))?,
+        ],
+    );
+    if let Err(error) = run(cli, &["rm", &container]) {
+        display::warning(format!(
+            "could not remove the container {container}: {error:#}"
+        ));
+    }
+    copied?;
+    display::done();
+
+    let removed = sanitize(directory)?;
+    if !removed.is_empty() {
+        display::check_warning(format!(
+            "removed {} files of the image that are links outside of the site or special \
+            files:\n{}",
+            removed.len(),
+            removed
+                .iter()
+                .map(|path| format!("  - {}", path.display()))
+                .collect::<Vec<_>>()
+                .join("\n")
+        ))?;
+    }
+    Ok(())
+}
+
+/// Runs the container CLI, and returns its trimmed standard output.
+fn run(cli: &str, args: &[&str]) -> Result<String> {
+    tracing::debug!(cli, ?args, "running the container CLI");
+    let output = Command::new(cli)
+        .args(args)
+        .stdin(Stdio::null())
+        .output()
+        .context(format!("could not run `{cli}`; is it installed?"))?;
+    ensure!(
+        output.status.success(),
+        "`{cli} {}` failed: {}",
+        args.join(" "),
+        String::from_utf8_lossy(&output.stderr).trim()
+    );
+    Ok(String::from_utf8(output.stdout)?.trim().to_owned())
+}
+
+/// Removes the symbolic links that point outside of the directory and the special files, and
+/// returns their paths.
+fn sanitize(directory: &Path) -> Result<Vec<PathBuf>> {
+    let mut removed = vec![];
+    let mut pending = vec![directory.to_owned()];
+    while let Some(current) = pending.pop() {
+        for entry in std::fs::read_dir(&current)? {
+            let path = entry?.path();
+            let file_type = std::fs::symlink_metadata(&path)?.file_type();
+            let keep = if file_type.is_dir() {
+                pending.push(path.clone());
+                true
+            } else if file_type.is_symlink() {
+                let target = std::fs::read_link(&path)?;
+                !escapes(directory, &path, &target)
+            } else {
+                file_type.is_file()
+            };
+            if !keep {
+                std::fs::remove_file(&path)?;
+                removed.push(path);
+            }
+        }
+    }
+    removed.sort();
+    Ok(removed)
+}
+
+/// Returns `true` if the symbolic link at `link`, with the given target, points outside of the
+/// directory.
+///
+/// The absolute targets refer to the root of the image, not to the site, so they always escape.
+/// The check is lexical, so that a link is never followed.
+fn escapes(directory: &Path, link: &Path, target: &Path) -> bool {
+    if target.is_absolute() {
+        return true;
+    }
+    let Ok(relative) = link.strip_prefix(directory) else {
+        return true;
+    };
+    // The depth of the directory containing the link, relative to the site directory.
+    let mut depth = relative.components().count() - 1;
+    for component in target.components() {
+        match component {
+            Component::ParentDir if depth == 0 => return true,
+            Component::ParentDir => depth -= 1,
+            Component::Normal(_) => depth += 1,
+            _ => (),
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escapes() {
+        let directory = Path::new("/tmp/site");
+        let link = Path::new("/tmp/site/assets/logo.png");
+        assert!(!escapes(directory, link, Path::new("images/logo.png")));
+        assert!(!escapes(directory, link, Path::new("../index.html")));
+        assert!(!escapes(directory, link, Path::new("./a/../../b.png")));
+        assert!(escapes(directory, link, Path::new("../../etc/passwd")));
+        assert!(escapes(directory, link, Path::new("/etc/passwd")));
+        assert!(escapes(
+            directory,
+            Path::new("/tmp/site/index.html"),
+            Path::new("../site/index.html")
+        ));
+    }
+}
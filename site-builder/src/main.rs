@@ -1,27 +1,85 @@
 // Copyright (c) Mysten Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
+mod analyze;
+mod batch;
+mod config_check;
 mod display;
+mod expiry;
+mod git;
+mod headers;
+mod image;
+mod manifest;
+mod metrics;
 mod preprocessor;
+mod probe;
+mod profiles;
 mod publish;
+mod redirect;
+mod rpc_relay;
+mod serve;
+mod signer;
 mod site;
+mod sitemap;
 mod summary;
 mod types;
 mod util;
 mod walrus;
-use std::path::PathBuf;
+mod webhook;
+use std::{
+    collections::BTreeMap,
+    ffi::OsString,
+    future::Future,
+    num::NonZeroUsize,
+    path::PathBuf,
+    time::Duration,
+};
 
-use anyhow::{Context, Result};
+use anyhow::{anyhow, bail, ensure, Context, Result};
 use clap::{Parser, Subcommand};
 use futures::TryFutureExt;
 use publish::{ContinuousEditing, PublishOptions, SiteEditor, WhenWalrusUpload};
 use serde::Deserialize;
-use site::{manager::SiteIdentifier, RemoteSiteFactory};
-use sui_types::base_types::ObjectID;
+use site::{
+    lockfile::DEFAULT_LOCKFILE,
+    manager::SiteIdentifier,
+    resource::{FileLister, SymlinkPolicy},
+    snapshot::DEFAULT_SNAPSHOT_DIR,
+    RemoteSiteFactory,
+};
+use sui_sdk::{
+    rpc_types::{
+        SuiTransactionBlockResponseOptions,
+        SuiTransactionBlockResponseQuery,
+        TransactionFilter,
+    },
+    wallet_context::WalletContext,
+};
+use sui_types::{
+    base_types::{ObjectID, SequenceNumber, SuiAddress},
+    digests::TransactionDigest,
+    gas_coin::MIST_PER_SUI,
+};
 
 use crate::{
+    manifest::SiteManifest,
     preprocessor::Preprocessor,
-    util::{id_to_base36, load_wallet_context},
+    profiles::Network,
+    serve::LocalSite,
+    sitemap::SitemapFormat,
+    summary::{DeployRecord, Summarizable},
+    util::{
+        bench_base36,
+        get_owned_sites,
+        handle_pagination,
+        id_to_base36,
+        load_wallet_context,
+        portal_url,
+        sui_balance,
+        validate_portal_url_template,
+        verify_base36,
+        wallet_config_path,
+    },
 };
 
 // Define the `GIT_REVISION` and `VERSION` consts.
@@ -42,6 +100,26 @@ struct Args {
 #[derive(Parser, Clone, Debug, Deserialize)]
 #[clap(rename_all = "kebab-case")]
 pub(crate) struct GeneralArgs {
+    /// The networks to operate on, which select the defaults for each network.
+    ///
+    /// The RPC URL is taken from the bundled profile of the network unless it is specified
+    /// explicitly, and the Walrus Sites package unless it is set in the `networks` section of the
+    /// config. The Sui env of the wallet with the name of the network as alias, e.g., `testnet`,
+    /// is used if there is one, and the command fails if the env is on the chain of another
+    /// network. With several networks, e.g., `testnet,mainnet`, the site is published on each of
+    /// them in turn; only `publish` supports several networks.
+    /// Can be specified as a CLI argument, or as a single `network` in the config.
+    #[clap(
+        long = "network",
+        value_enum,
+        value_delimiter = ',',
+        value_name = "NETWORK"
+    )]
+    #[serde(skip)]
+    networks: Vec<Network>,
+    /// The network to operate on, from the config or the single network given with `--network`.
+    #[clap(skip)]
+    network: Option<Network>,
     /// The URL or the RPC endpoint to connect the client to.
     ///
     /// Can be specified as a CLI argument or in the config.
@@ -49,6 +127,8 @@ pub(crate) struct GeneralArgs {
     rpc_url: Option<String>,
     /// The path to the Sui Wallet config.
     ///
+    /// If not set, the `client.yaml` in the directory set with `SUI_CONFIG_DIR` is used, then the
+    /// one in the local folder, and finally the one in `~/.sui/sui_config`.
     /// Can be specified as a CLI argument or in the config.
     #[clap(long)]
     wallet: Option<PathBuf>,
@@ -72,16 +152,157 @@ pub(crate) struct GeneralArgs {
     #[clap(short, long)]
     #[serde(default = "default::gas_budget")]
     gas_budget: Option<u64>,
+    /// Do not require the full node to confirm the local execution of transactions.
+    ///
+    /// Some full nodes execute the transactions successfully, but never confirm the local
+    /// execution. With this flag, only the status of the transaction effects is checked.
+    /// Can be specified as a CLI argument or in the config.
+    #[clap(long, action)]
+    #[serde(default)]
+    no_confirm_local_execution: bool,
+    /// Only warn, instead of failing, if the Walrus Sites package on chain is newer than the
+    /// version supported by the site builder.
+    ///
+    /// Can be specified as a CLI argument or in the config.
+    #[clap(long, action)]
+    #[serde(default)]
+    skip_package_version_check: bool,
+    /// A label to tag the transactions submitted by the site builder, e.g., a ticket ID.
+    ///
+    /// The label is recorded in the audit log and in the summary.
+    #[clap(long)]
+    label: Option<String>,
+    /// The path to a file to which an audit log of the submitted transactions is appended.
+    ///
+    /// Each line of the file is a JSON object with the label, digest, timestamp, signer, and
+    /// operation of a transaction.
+    /// Can be specified as a CLI argument or in the config.
+    #[clap(long)]
+    audit_log: Option<PathBuf>,
+    /// The maximum total gas, in MIST, that can be spent by all the transactions of a command.
+    ///
+    /// Before each transaction, its gas cost is estimated; the command is aborted if the estimate,
+    /// plus the gas already spent, exceeds this value. By default, there is no cap.
+    /// Can be specified as a CLI argument or in the config.
+    #[clap(long)]
+    max_budget: Option<u64>,
+    /// The maximum time, in seconds, for the whole command to complete.
+    ///
+    /// When the deadline expires, the command is interrupted and exits with an error. The
+    /// operations on Sui are atomic, and the blobs already stored on Walrus are not uploaded
+    /// again: running the command again resumes the publishing. With several networks, the
+    /// deadline applies to the publish on each network.
+    /// Can be specified as a CLI argument or in the config.
+    #[clap(long)]
+    deadline: Option<u64>,
+    /// Override the scope of the intent with which transactions are signed.
+    ///
+    /// Only useful for testing and alternative chains; by default, transactions are signed with
+    /// the Sui transaction intent (scope `0`).
+    /// Can be specified as a CLI argument or in the config.
+    #[clap(long, hide = true)]
+    intent_scope: Option<u8>,
+    /// Store the resources up to this size, in bytes, on Walrus with a single batched command.
+    ///
+    /// If the Walrus CLI does not support batched uploads, the resources are stored individually;
+    /// the resources that the batch fails to store are also stored individually, with retries.
+    /// By default, all resources are stored individually.
+    /// Can be specified as a CLI argument or in the config.
+    #[clap(long)]
+    batch_upload_threshold: Option<u64>,
+    /// Also print the gas report after the execution as a JSON object.
+    ///
+    /// The report contains the gas budget, the gas used, the gas estimate if any, and a suggested
+    /// gas budget for the next runs.
+    /// Can be specified as a CLI argument or in the config.
+    #[clap(long, action)]
+    #[serde(default)]
+    json_gas_report: bool,
+    /// Sign the transactions with this external command, instead of the wallet keystore.
+    ///
+    /// The command receives a line with the base64-encoded BCS intent message to sign on stdin,
+    /// and must print the base64-encoded Sui signature (flag, signature, and public key, as in
+    /// `sui keytool sign`) on stdout. The signature must be for the active address of the wallet.
+    /// Can be specified as a CLI argument or in the config.
+    #[clap(long)]
+    signer_command: Option<String>,
+    /// Sign the transactions with the private key stored in the OS secret store under this
+    /// service name, instead of using the keystore of the wallet.
+    ///
+    /// The key is read from the Keychain on macOS, the Credential Manager on Windows, or the
+    /// Secret Service on Linux, and must be encoded as exported by `sui keytool export`. It must
+    /// be the key of the active address of the wallet. Can be specified as a CLI argument or in
+    /// the config.
+    #[clap(long, conflicts_with = "signer_command")]
+    keyring_service: Option<String>,
+    /// The account name of the key in the OS secret store.
+    ///
+    /// Defaults to the active address of the wallet.
+    #[clap(long, requires = "keyring_service")]
+    keyring_account: Option<String>,
+    /// The URL of the HTTP or SOCKS proxy for the Walrus CLI and the webhooks, e.g.,
+    /// `http://proxy.corp:3128` or `socks5://proxy.corp:1080`.
+    ///
+    /// Overrides the `HTTP_PROXY`, `HTTPS_PROXY`, and `ALL_PROXY` environment variables, which
+    /// are honored otherwise; `NO_PROXY` is always honored. The requests of the Sui RPC client,
+    /// which does not support proxies, go through a local relay that forwards them to the proxy.
+    /// Can be specified as a CLI argument or in the config.
+    #[clap(long)]
+    proxy: Option<String>,
+    /// A substring of the errors of the transactions to treat as transient, and therefore to
+    /// retry, e.g., `connection reset` for a flaky RPC endpoint. Can be repeated.
+    ///
+    /// By default, only the transactions rejected because the version of their gas coin was stale
+    /// are retried, with the latest version of the coin. The transactions failing with a
+    /// configured error are resubmitted unchanged, with the same digest, so they are never
+    /// executed twice. Both are retried at most 3 times in total.
+    /// Can be specified as a CLI argument or in the config.
+    #[clap(long = "retryable-error", value_name = "SUBSTRING")]
+    retryable_errors: Option<Vec<String>>,
+    /// Fail on the warnings of the checks of the site, e.g., dangling links, oversized resources,
+    /// or skipped files, instead of only displaying them.
+    ///
+    /// Meant for automated pipelines, so that a questionable deploy is never published. Warnings
+    /// about the execution, e.g., a retried upload or a failed webhook, are not affected.
+    /// Can be specified as a CLI argument or in the config.
+    #[clap(long, action)]
+    #[serde(default)]
+    strict: bool,
+    /// Do not ask for confirmation before changing a site on Mainnet.
+    ///
+    /// Without this flag, the operations that change a site on Mainnet require an interactive
+    /// confirmation. Can only be specified as a CLI argument.
+    #[clap(long, action)]
+    #[serde(skip)]
+    yes: bool,
 }
 
 impl Default for GeneralArgs {
     fn default() -> Self {
         Self {
+            networks: vec![],
+            network: None,
             rpc_url: None,
             wallet: None,
             walrus_binary: default::walrus_binary(),
             walrus_config: None,
             gas_budget: default::gas_budget(),
+            no_confirm_local_execution: false,
+            skip_package_version_check: false,
+            label: None,
+            audit_log: None,
+            max_budget: None,
+            deadline: None,
+            intent_scope: None,
+            batch_upload_threshold: None,
+            json_gas_report: false,
+            signer_command: None,
+            keyring_service: None,
+            keyring_account: None,
+            proxy: None,
+            retryable_errors: None,
+            strict: false,
+            yes: false,
         }
     }
 }
@@ -110,12 +331,32 @@ impl GeneralArgs {
         merge_fields!(
             self,
             other,
+            network,
             rpc_url,
             wallet,
             walrus_binary,
             walrus_config,
             gas_budget,
+            label,
+            audit_log,
+            max_budget,
+            deadline,
+            intent_scope,
+            batch_upload_threshold,
+            signer_command,
+            keyring_service,
+            keyring_account,
+            proxy,
+            retryable_errors,
         );
+        if let [network] = other.networks.as_slice() {
+            self.network = Some(*network);
+        }
+        self.no_confirm_local_execution |= other.no_confirm_local_execution;
+        self.skip_package_version_check |= other.skip_package_version_check;
+        self.json_gas_report |= other.json_gas_report;
+        self.strict |= other.strict;
+        self.yes |= other.yes;
     }
 }
 
@@ -129,6 +370,37 @@ enum Commands {
         /// The name of the site.
         #[clap(short, long, default_value = "test site")]
         site_name: String,
+        /// Update the site with the same name owned by the active address, if there is one,
+        /// instead of creating a new site.
+        ///
+        /// Repeated publishes of the same site then keep the same object ID, and therefore the same
+        /// subdomain on the portal. Fails if the address owns multiple sites with the name.
+        #[clap(long, action)]
+        reuse_named_site: bool,
+        /// With several networks, continue with the next networks if the publish fails on one,
+        /// and report all errors at the end.
+        #[clap(long, action)]
+        keep_going: bool,
+    },
+    /// Publish a new site that redirects all its paths to the target URL, e.g., to move the
+    /// visitors of a site to its new object.
+    ///
+    /// The site has a single page, which redirects with a `refresh` meta tag; the portal cannot
+    /// send redirect responses. Its sources are generated in a temporary directory.
+    PublishRedirect {
+        /// The HTTP or HTTPS URL to redirect to.
+        target_url: String,
+        /// The name of the site.
+        #[clap(short, long, default_value = "redirect")]
+        site_name: String,
+        /// The number of epochs for which to save the page on Walrus.
+        #[clap(long, default_value_t = 1)]
+        epochs: u64,
+        /// Append the path, query, and fragment of the request to the target URL.
+        ///
+        /// This needs JavaScript in the browser; without it, all paths redirect to the target URL.
+        #[clap(long, action)]
+        keep_path: bool,
     },
     /// Update an existing site.
     Update {
@@ -145,6 +417,275 @@ enum Commands {
         #[clap(long, action)]
         force: bool,
     },
+    /// Make the site at the given object ID match the directory exactly, whatever its state.
+    ///
+    /// Meant to recover from a publish that failed midway. The resources missing from the site or
+    /// different from the local files are added, and the resources without a local file are
+    /// removed. Unlike `update`, the blobs of the unchanged resources are also checked, and
+    /// uploaded again if they are no longer stored on Walrus for the requested epochs.
+    Repair {
+        #[clap(flatten)]
+        publish_options: PublishOptions,
+        /// The object ID of the site to repair.
+        object_id: ObjectID,
+    },
+    /// Apply a plan computed with `publish --dry-run --dry-run-file` or `update --dry-run
+    /// --dry-run-file`.
+    ///
+    /// The plan is only executed if the site on chain has not changed since it was computed.
+    Apply {
+        /// The path to the plan file.
+        plan: PathBuf,
+    },
+    /// Move a resource of a site to a new path, without re-uploading the blob.
+    Mv {
+        /// The object ID of the site.
+        site_object: ObjectID,
+        /// The current path of the resource.
+        old_path: String,
+        /// The new path of the resource.
+        new_path: String,
+        /// Move all the resources in a subtree.
+        ///
+        /// Both paths must end with `*`, e.g., `/old/* /new/*`; all the resources starting with
+        /// the old prefix are moved under the new prefix.
+        #[clap(long, action)]
+        glob: bool,
+    },
+    /// Update the metadata of the site at the given object ID, without touching its resources.
+    ///
+    /// The Walrus Sites package currently only stores the name of the site as metadata.
+    SetMetadata {
+        /// The object ID of the site.
+        site_object: ObjectID,
+        /// The new name of the site.
+        #[clap(long)]
+        name: String,
+    },
+    /// Remove all the resources and routes from the site at the given object ID.
+    ///
+    /// The resources are removed in batches over multiple transactions. If the command is
+    /// interrupted, run it again to continue from the remaining resources.
+    Destroy {
+        /// The object ID of the site.
+        site_object: ObjectID,
+    },
+    /// Restore the site at the given object ID to its most recent snapshot.
+    ///
+    /// The snapshots are saved by `publish` and `update` with `--snapshot`. Only the Sui object
+    /// is restored, so the blobs of the snapshot must still be stored on Walrus.
+    Rollback {
+        /// The object ID of the site.
+        site_object: ObjectID,
+        /// The directory in which the snapshots are saved.
+        #[clap(long, value_name = "DIR", default_value = DEFAULT_SNAPSHOT_DIR)]
+        snapshot_dir: PathBuf,
+    },
+    /// Download the site at the given object ID, and serve it locally as the portal would.
+    ///
+    /// The resources are served with their headers, and the routes are honored.
+    Serve {
+        /// The object ID of the site.
+        site_object: ObjectID,
+        /// The local port on which to serve the site.
+        #[clap(long, default_value_t = 8000)]
+        port: u16,
+        /// Only warn, instead of failing, if the content of a resource downloaded from Walrus
+        /// does not match the hash stored on chain.
+        #[clap(long, action)]
+        lenient: bool,
+        /// The directory to which the resources are downloaded before being served.
+        ///
+        /// Defaults to a new private directory in the temporary directory of the system, removed
+        /// when the server stops.
+        #[clap(long, value_name = "DIR")]
+        download_dir: Option<PathBuf>,
+        /// The maximum number of blobs downloaded at the same time.
+        #[clap(long, default_value_t = 8)]
+        concurrency: usize,
+    },
+    /// Request every resource of the site at the given object ID through the portal, and list
+    /// them from the slowest to serve.
+    ///
+    /// The time to first byte is the time until the headers of the response are received. Fails
+    /// if any resource is not served successfully.
+    Probe {
+        /// The object ID of the site.
+        site_object: ObjectID,
+        /// The maximum number of requests in flight.
+        #[clap(long, default_value_t = 8)]
+        concurrency: usize,
+        /// The timeout of each request, in seconds.
+        #[clap(long, value_name = "SECONDS", default_value_t = 30)]
+        timeout: u64,
+        /// Print the results as JSON instead of a table.
+        #[clap(long, action)]
+        json: bool,
+    },
+    /// List the object IDs of the sites owned by an address, with their URLs on the portal.
+    ListSites {
+        /// The owner of the sites. Defaults to the active address of the wallet.
+        #[clap(long)]
+        address: Option<SuiAddress>,
+    },
+    /// Check the `ws-resources.json` file against the files in the site directory, without
+    /// publishing.
+    ///
+    /// Reports all the headers, content types, routes, and error pages that refer to missing
+    /// files, or that are never used by the portal. Fails if any error is found.
+    Validate {
+        /// The directory containing the site sources.
+        directory: PathBuf,
+        /// The path to the Walrus sites resources file.
+        ///
+        /// By default, the file is expected to be named `ws-resources.json` and located in the
+        /// root of the site directory.
+        #[clap(long)]
+        ws_resources: Option<PathBuf>,
+        /// Also validate the `ws-resources.json` files in the subdirectories, merged into the one
+        /// at the root.
+        #[clap(long, action)]
+        nested_ws_resources: bool,
+        /// Include dotfiles and dot-directories (e.g., `.well-known/`) in the site.
+        #[clap(long, action)]
+        include_dotfiles: bool,
+        /// How to handle the symbolic links in the directory.
+        #[clap(long, value_enum, default_value_t = SymlinkPolicy::Skip)]
+        symlinks: SymlinkPolicy,
+    },
+    /// Show where the bytes of the site directory are, and how to make the site smaller.
+    ///
+    /// Prints the total size of the resources by content type, the largest resources, and the
+    /// compressible resources, which are always stored uncompressed. No network calls are made.
+    Analyze {
+        /// The directory containing the site sources.
+        directory: PathBuf,
+        /// The number of the largest resources to show.
+        #[clap(long, default_value_t = 10)]
+        top: usize,
+        /// The size in bytes above which a resource is suggested to be split.
+        #[clap(long, default_value_t = publish::DEFAULT_LARGE_RESOURCE_THRESHOLD)]
+        large_resource_threshold: usize,
+        /// Include dotfiles and dot-directories (e.g., `.well-known/`) in the site.
+        #[clap(long, action)]
+        include_dotfiles: bool,
+        /// How to handle the symbolic links in the directory.
+        #[clap(long, value_enum, default_value_t = SymlinkPolicy::Skip)]
+        symlinks: SymlinkPolicy,
+    },
+    /// Compare the site directory with a lockfile, without any network access.
+    ///
+    /// Prints the resources that publishing the directory would add, change, or remove relative
+    /// to the lockfile. Only the contents are compared, as needed to tell if a commit changes the
+    /// deployed site; no wallet, Sui RPC, or Walrus CLI is needed.
+    ///
+    /// The resources are read with the same options as the publish, e.g., `--base-href` or
+    /// `--tag`. The lockfile is `walrus.lock` in the current directory, unless set with
+    /// `--lockfile`.
+    Plan {
+        #[clap(flatten)]
+        publish_options: PublishOptions,
+        /// Exit with an error if any resource differs from the lockfile.
+        #[clap(long, action)]
+        exit_code: bool,
+    },
+    /// Check that the resources of the site at the given object ID match a lockfile.
+    ///
+    /// Prints the resources whose blob ID or content on chain differs from the lockfile, and the
+    /// resources that are only on chain or only in the lockfile, e.g., after a deploy that did not
+    /// update the lockfile. Exits with an error if any resource drifted.
+    LockfileVerify {
+        /// The object ID of the site.
+        site_object: ObjectID,
+        /// The lockfile to compare with.
+        #[clap(long, default_value = DEFAULT_LOCKFILE)]
+        lockfile: PathBuf,
+    },
+    /// Show the address, network, and configuration that the other commands would use.
+    ///
+    /// This is a read-only command, useful to check the setup before publishing.
+    Whoami,
+    /// Show the effects of the transaction with the given digest.
+    Tx {
+        /// The digest of the transaction.
+        digest: TransactionDigest,
+    },
+    /// Show the history of the transactions that changed the site at the given object ID.
+    ///
+    /// For each transaction, newest first, prints the digest, the timestamp in milliseconds since
+    /// the Unix epoch, the sender, the net gas used, and the status. The history is read from
+    /// chain, and is therefore complete regardless of who updated the site.
+    History {
+        /// The object ID of the site.
+        site_object: ObjectID,
+    },
+    /// Check that the blobs of the site at the given object ID are stored for long enough.
+    ///
+    /// Prints the resources whose blobs expire within the given number of epochs, or are no
+    /// longer stored, the soonest expiring first. Exits with code 2 if there is any, and with code
+    /// 1 on other errors, to be run on a schedule by monitoring tools.
+    CheckExpiry {
+        /// The object ID of the site.
+        site_object: ObjectID,
+        /// The number of epochs from the current one within which an expiry is reported.
+        #[clap(long, default_value_t = 5)]
+        warn_epochs: u64,
+        /// The maximum number of expiring resources to print.
+        #[clap(long, default_value_t = 20)]
+        top: usize,
+    },
+    /// Print the number of resources of the site at the given object ID.
+    ///
+    /// Only the names of the dynamic fields are fetched, which makes this a quick check.
+    Count {
+        /// The object ID of the site.
+        site_object: ObjectID,
+    },
+    /// List the dynamic fields of the site at the given object ID.
+    ///
+    /// The fields unknown to the site builder, e.g., added by a newer version of the contract, are
+    /// listed as well.
+    Fields {
+        /// The object ID of the site.
+        site_object: ObjectID,
+        /// Print the Move types of the name and the value of each field, without interpreting
+        /// them.
+        #[clap(long, action)]
+        raw: bool,
+        /// Only list the resources whose path starts with this prefix, e.g., `/assets/`.
+        #[clap(long, value_name = "PATH")]
+        prefix: Option<String>,
+    },
+    /// Show the headers stored with the resource at the given path of the site, as a table.
+    ///
+    /// Only the dynamic field of the resource is fetched. The meaning of the known headers is
+    /// explained, and the headers the site builder does not recognize are flagged.
+    Headers {
+        /// The object ID of the site.
+        site_object: ObjectID,
+        /// The path of the resource, e.g., `/index.html`.
+        path: String,
+    },
+    /// Print the Merkle root of the resources of the site at the given object ID.
+    ///
+    /// The root commits to the paths and the contents of all the resources, and can be compared
+    /// with the resource root printed after a publish or an update.
+    Root {
+        /// The object ID of the site.
+        site_object: ObjectID,
+    },
+    /// Export a portable JSON manifest of the resources of the site.
+    ///
+    /// The manifest lists the path, content type, size, SHA-256 digest, and blob ID of every
+    /// resource, to mirror or verify the site on other content-addressed systems. The format is
+    /// versioned with the `manifest_version` field.
+    ExportManifest {
+        /// The object ID of the site.
+        site_object: ObjectID,
+        /// Write the manifest to this file, instead of stdout.
+        #[clap(long, short)]
+        output: Option<PathBuf>,
+    },
     /// Convert an object ID in hex format to the equivalent Base36 format.
     ///
     /// This command may be useful to browse a site, given it object ID.
@@ -152,12 +693,58 @@ enum Commands {
         /// The object id (in hex format) to convert
         object_id: ObjectID,
     },
-    /// Show the pages composing the site at the given object ID.
-    Sitemap { object: ObjectID },
+    /// Check that a Base36 subdomain corresponds to the given object ID.
+    VerifySubdomain {
+        /// The object id (in hex format) of the site.
+        object_id: ObjectID,
+        /// The Base36 subdomain to check.
+        base36: String,
+    },
+    /// Show the pages composing the sites at the given object IDs.
+    Sitemap {
+        /// The object IDs of the sites.
+        #[clap(required = true)]
+        objects: Vec<ObjectID>,
+        /// Continue with the next sites if one of them fails, and report all errors at the end.
+        #[clap(long, action)]
+        keep_going: bool,
+        /// The maximum number of sites processed at the same time.
+        ///
+        /// Bounds the load on the RPC when many sites are given; the sites are processed one
+        /// after the other by default. The output of each site is still printed in one piece.
+        #[clap(long, value_name = "N", default_value = "1")]
+        max_concurrent_sites: NonZeroUsize,
+        /// The format of the sitemap.
+        ///
+        /// The XML sitemap only contains the HTML pages, prefixed with the portal URL of the site;
+        /// the JSON map contains the blob IDs of all the resources.
+        #[clap(long, value_enum, default_value_t = SitemapFormat::List)]
+        format: SitemapFormat,
+        /// Only show the resources whose path starts with this prefix, e.g., `/assets/`.
+        ///
+        /// The other resources are not fetched. All the dynamic fields of the sites are still
+        /// listed, as they are not ordered by path.
+        #[clap(long, value_name = "PATH")]
+        prefix: Option<String>,
+    },
     /// Preprocess the directory, creating and linking index files.
     /// This command allows to publish directories as sites. Warning: Rewrites all `index.html`
     /// files.
     ListDirectory { path: PathBuf },
+    /// Encode random object IDs to base36 and decode them back, reporting the throughput.
+    ///
+    /// A development tool, to check the speed and the correctness of the subdomain encoding.
+    #[clap(hide = true)]
+    BenchBase36 {
+        /// The number of object IDs to encode.
+        #[clap(long, default_value_t = 100_000)]
+        count: usize,
+    },
+    /// Print the effective configuration, and where each of its values comes from.
+    ///
+    /// The values are taken from the CLI flags, the config file, the network profile, the
+    /// environment, or the defaults. No network calls are made.
+    ConfigCheck,
 }
 
 /// The configuration for the site builder.
@@ -165,11 +752,43 @@ enum Commands {
 pub(crate) struct Config {
     #[serde(default = "default::default_portal")]
     pub portal: String,
-    pub package: ObjectID,
+    /// The template of the URLs of the sites on the portal, e.g., `https://gw.example/{b36}`.
+    ///
+    /// `{b36}` is replaced by the Base36 subdomain of the site, and `{id}` by its object ID. If
+    /// not set, sites are served at `https://{b36}.<portal>`.
+    #[serde(default)]
+    pub portal_url_template: Option<String>,
+    #[serde(default)]
+    package: Option<ObjectID>,
+    /// The initial shared versions of shared sites, used instead of querying the full node.
+    ///
+    /// The initial shared version of an object never changes, so pinning it allows building the
+    /// same transaction bytes offline.
+    #[serde(default)]
+    pub shared_object_versions: BTreeMap<ObjectID, SequenceNumber>,
+    /// The values specific to each network, used when the network is selected, e.g., the wallet
+    /// with the keys for Mainnet.
+    #[serde(default)]
+    pub networks: BTreeMap<Network, NetworkConfig>,
     #[serde(default)]
     pub general: GeneralArgs,
 }
 
+/// The configuration specific to a network, which takes precedence over the general one.
+#[derive(Deserialize, Debug, Clone, Default)]
+pub(crate) struct NetworkConfig {
+    #[serde(default)]
+    pub portal: Option<String>,
+    #[serde(default)]
+    pub package: Option<ObjectID>,
+    #[serde(default)]
+    pub rpc_url: Option<String>,
+    #[serde(default)]
+    pub wallet: Option<PathBuf>,
+    #[serde(default)]
+    pub walrus_config: Option<PathBuf>,
+}
+
 impl Config {
     /// Merges the other [`GeneralArgs`] (taken from the CLI) with the `general` in the struct.
     ///
@@ -178,6 +797,95 @@ impl Config {
         self.general.merge(other_general);
     }
 
+    /// Applies the values of the `networks` section for the network, and selects the network.
+    pub fn apply_network_config(&mut self, network: Network) {
+        self.general.network = Some(network);
+        let Some(network_config) = self.networks.get(&network).cloned() else {
+            return;
+        };
+        tracing::debug!(
+            ?network,
+            ?network_config,
+            "applying the config of the network"
+        );
+        if let Some(portal) = network_config.portal {
+            self.portal = portal;
+        }
+        self.package = network_config.package.or(self.package);
+        let general = &mut self.general;
+        general.rpc_url = network_config.rpc_url.or(general.rpc_url.take());
+        general.wallet = network_config.wallet.or(general.wallet.take());
+        general.walrus_config = network_config
+            .walrus_config
+            .or(general.walrus_config.take());
+    }
+
+    /// Fills the values that are not specified with the defaults of the selected network.
+    pub fn apply_network_profile(&mut self) {
+        let Some(network) = self.general.network else {
+            return;
+        };
+        let profile = network.profile();
+        tracing::debug!(?network, ?profile, "applying the network profile");
+        self.general
+            .rpc_url
+            .get_or_insert_with(|| profile.rpc_url.to_owned());
+        // The package of the profile takes precedence over the top-level one, which is usually
+        // the package of another network; only the package of the network config overrides it.
+        if !self
+            .networks
+            .get(&network)
+            .is_some_and(|network_config| network_config.package.is_some())
+        {
+            self.package = profile.package.or(self.package);
+        }
+    }
+
+    /// Loads the wallet context, relaying its RPC through the proxy if one is set.
+    ///
+    /// If a network is selected, the env of the wallet with the name of the network as alias is
+    /// used instead of the active one, if there is one, and loading fails if the Sui RPC of the env
+    /// is on another chain.
+    pub async fn load_wallet(&self) -> Result<WalletContext> {
+        let mut wallet = self.load_network_wallet()?;
+        self.connect_wallet(&mut wallet).await?;
+        Ok(wallet)
+    }
+
+    /// Loads the wallet context, with the env of the selected network active, if there is one.
+    fn load_network_wallet(&self) -> Result<WalletContext> {
+        let mut wallet = load_wallet_context(&self.general.wallet)?;
+        if let Some(network) = self.general.network {
+            let alias = format!("{network:?}").to_lowercase();
+            if wallet.config.envs.iter().any(|env| env.alias == alias) {
+                wallet.config.active_env = Some(alias);
+            }
+        }
+        Ok(wallet)
+    }
+
+    /// Relays the Sui RPC of the wallet through the proxy, if one is set, and checks that it is
+    /// on the chain of the selected network.
+    async fn connect_wallet(&self, wallet: &mut WalletContext) -> Result<()> {
+        if let Some(proxy) = self.general.proxy.as_deref() {
+            rpc_relay::relay_wallet_rpc(wallet, proxy).await?;
+        }
+        if let Some(network) = self.general.network {
+            check_chain(wallet, network).await?;
+        }
+        Ok(())
+    }
+
+    /// Returns the URL of the site on the portal.
+    pub fn site_url(&self, site_id: &ObjectID) -> Result<String> {
+        portal_url(&self.portal, self.portal_url_template.as_deref(), site_id)
+    }
+
+    pub fn package(&self) -> ObjectID {
+        self.package
+            .expect("checked when loading the config => package exists")
+    }
+
     pub fn walrus_binary(&self) -> String {
         self.general
             .walrus_binary
@@ -207,11 +915,19 @@ mod default {
 }
 
 async fn run() -> Result<()> {
-    tracing_subscriber::fmt::init();
+    let args = Args::parse();
+    if args.command.prints_url_only() {
+        // Only the URL of the site goes to stdout.
+        tracing_subscriber::fmt()
+            .with_writer(std::io::stderr)
+            .init();
+        display::set_quiet();
+    } else {
+        tracing_subscriber::fmt::init();
+    }
     tracing::info!("initializing site builder");
 
-    let args = Args::parse();
-    let mut config: Config = std::fs::read_to_string(&args.config)
+    let config: Config = std::fs::read_to_string(&args.config)
         .context(format!(
             "unable to read config {:?}; consider using the --config flag to point to the config",
             args.config
@@ -220,16 +936,236 @@ async fn run() -> Result<()> {
             serde_yaml::from_str(&s)
                 .context(format!("unable to parse yaml in file {:?}", args.config))
         })?;
+    if let Some(template) = config.portal_url_template.as_ref() {
+        validate_portal_url_template(template)?;
+    }
+    if config.general.strict || args.general.strict {
+        display::set_strict();
+    }
+    if args.general.networks.len() > 1 {
+        return publish_on_networks(args.command, config, &args.general).await;
+    }
+    let file_config = config.clone();
+    let config = resolve_config(config, &args.general);
+    if matches!(args.command, Commands::ConfigCheck) {
+        config_check::print_effective_config(&config, &file_config, &args.general);
+        return Ok(());
+    }
+    check_package(&config)?;
+    tracing::info!(?config, "configuration loaded");
+
+    with_deadline(config.general.deadline, run_command(args.command, config)).await
+}
+
+/// Returns the site to publish to, given its name: the site with the name owned by the active
+/// address if there is one, or a new site otherwise.
+async fn named_site_identifier(config: &Config, site_name: String) -> Result<SiteIdentifier> {
+    let mut wallet = config.load_wallet().await?;
+    let owner = wallet.active_address()?;
+    let existing = RemoteSiteFactory::new(&wallet.get_client().await?, config.package())
+        .find_owned_site(owner, &site_name)
+        .await?;
+    Ok(match existing {
+        Some(site_id) => {
+            tracing::info!(%site_id, site_name, "updating the existing site with the same name");
+            SiteIdentifier::ExistingSite(site_id)
+        }
+        None => SiteIdentifier::NewSite(site_name),
+    })
+}
+
+/// Publishes the site, and returns its object ID, unless nothing was published.
+async fn publish(
+    config: Config,
+    publish_options: PublishOptions,
+    site_name: String,
+    reuse_named_site: bool,
+) -> Result<Option<ObjectID>> {
+    let site_id = if reuse_named_site {
+        named_site_identifier(&config, site_name).await?
+    } else {
+        SiteIdentifier::NewSite(site_name)
+    };
+    SiteEditor::new(
+        publish_options,
+        site_id,
+        config,
+        ContinuousEditing::Once,
+        WhenWalrusUpload::Modified,
+    )
+    .run()
+    .await
+}
+
+/// Publishes the site on each of the networks in turn, with the configuration of each network.
+///
+/// The object IDs of the sites differ across networks, so only `publish` supports several
+/// networks; `--reuse-named-site` keeps the sites of all the networks in sync.
+async fn publish_on_networks(
+    command: Commands,
+    file_config: Config,
+    general: &GeneralArgs,
+) -> Result<()> {
+    let Commands::Publish {
+        publish_options,
+        site_name,
+        reuse_named_site,
+        keep_going,
+    } = command
+    else {
+        bail!(
+            "several networks can only be given to `publish`, as the object IDs of the sites \
+            differ across networks"
+        );
+    };
+    let network_config = |network: Network| {
+        let general = GeneralArgs {
+            networks: vec![network],
+            ..general.clone()
+        };
+        resolve_config(file_config.clone(), &general)
+    };
+    // All the networks are checked before publishing on the first one.
+    for network in general.networks.iter() {
+        check_network_config(&file_config, *network, &network_config(*network)).await?;
+    }
+    batch::for_each_network(&general.networks, keep_going, |network| {
+        let config = network_config(network);
+        let publish_options = publish_options.clone();
+        let site_name = site_name.clone();
+        async move {
+            tracing::info!(?network, ?config, "configuration loaded");
+            with_deadline(
+                config.general.deadline,
+                publish(config, publish_options, site_name, reuse_named_site),
+            )
+            .await
+        }
+    })
+    .await
+}
+
+/// Merges the config file with the CLI args, and completes it with the values of the network.
+///
+/// The CLI args take precedence over the `networks` section of the config for the network, which
+/// takes precedence over the general config, and then over the network profile.
+fn resolve_config(mut config: Config, general: &GeneralArgs) -> Config {
+    if let Some(network) = general.networks.first().copied().or(config.general.network) {
+        config.apply_network_config(network);
+    }
     // Merge the configs and the CLI args. Serde default ensures that the `walrus_binary` and
     // `gas_budget` exist.
-    config.merge(&args.general);
-    tracing::info!(?config, "configuration loaded");
+    config.merge(general);
+    config.apply_network_profile();
+    config
+}
 
-    match args.command {
+/// Checks that the network has its own package and wallet, and that the wallet is on its chain.
+///
+/// Otherwise, the networks would share the top-level package and the active env of the wallet,
+/// and the site would be published several times on the same chain.
+async fn check_network_config(
+    file_config: &Config,
+    network: Network,
+    config: &Config,
+) -> Result<()> {
+    let name = format!("{network:?}").to_lowercase();
+    let network_config = file_config.networks.get(&network);
+    ensure!(
+        network_config.is_some_and(|network_config| network_config.package.is_some())
+            || network.profile().package.is_some(),
+        "no Walrus Sites package for {name}; set `networks.{name}.package` in the config, as the \
+        top-level package is used for all the networks"
+    );
+    check_package(config)?;
+    let mut wallet = config.load_network_wallet()?;
+    ensure!(
+        network_config.is_some_and(|network_config| network_config.wallet.is_some())
+            || wallet.config.active_env.as_deref() == Some(name.as_str()),
+        "no wallet for {name}; set `networks.{name}.wallet` in the config, or add an env with the \
+        alias `{name}` to the wallet with `sui client new-env`"
+    );
+    config.connect_wallet(&mut wallet).await
+}
+
+/// Checks that the Sui RPC of the active env of the wallet is on the chain of the network.
+async fn check_chain(wallet: &WalletContext, network: Network) -> Result<()> {
+    let chain_id = wallet
+        .get_client()
+        .await?
+        .read_api()
+        .get_chain_identifier()
+        .await?;
+    let alias = &wallet.config.get_active_env()?.alias;
+    tracing::debug!(?network, alias, %chain_id, "checking the chain of the wallet");
+    let name = format!("{network:?}").to_lowercase();
+    ensure!(
+        network.matches_chain(&chain_id),
+        "the Sui env `{alias}` of the wallet is on the chain {chain_id}, not on {name}; add an env \
+        for the network with `sui client new-env --alias {name} --rpc {}`, or select the wallet \
+        of the network with `--wallet`",
+        network.profile().rpc_url
+    );
+    Ok(())
+}
+
+fn check_package(config: &Config) -> Result<()> {
+    ensure!(
+        config.package.is_some(),
+        "no Walrus Sites package specified; set `package` in the config, or select a network \
+        with a bundled package with `--network`"
+    );
+    Ok(())
+}
+
+/// Runs the future, failing if it does not complete within the deadline in seconds, if set.
+async fn with_deadline<T>(
+    deadline: Option<u64>,
+    future: impl Future<Output = Result<T>>,
+) -> Result<T> {
+    let Some(deadline) = deadline.map(Duration::from_secs) else {
+        return future.await;
+    };
+    tokio::time::timeout(deadline, future).await.map_err(|_| {
+        let progress = display::last_action()
+            .map(|action| format!("; the last action started was: {action}"))
+            .unwrap_or_default();
+        anyhow!(
+            "the command did not complete within the deadline of {} seconds{progress}",
+            deadline.as_secs()
+        )
+    })?
+}
+
+/// Runs the command with the given configuration.
+async fn run_command(command: Commands, config: Config) -> Result<()> {
+    match command {
         Commands::Publish {
             publish_options,
             site_name,
+            reuse_named_site,
+            ..
+        } => {
+            publish(config, publish_options, site_name, reuse_named_site).await?;
+        }
+        Commands::PublishRedirect {
+            target_url,
+            site_name,
+            epochs,
+            keep_path,
         } => {
+            // The directory is removed when dropped, after the publish.
+            let directory = tempfile::Builder::new()
+                .prefix("walrus-sites-redirect-")
+                .tempdir()
+                .context("could not create the temporary directory of the redirect site")?;
+            redirect::write_redirect_site(directory.path(), &target_url, keep_path)?;
+            let publish_options = PublishOptions::try_parse_from([
+                OsString::from("publish-redirect"),
+                directory.path().into(),
+                "--epochs".into(),
+                epochs.to_string().into(),
+            ])?;
             SiteEditor::new(
                 publish_options,
                 SiteIdentifier::NewSite(site_name),
@@ -238,7 +1174,7 @@ async fn run() -> Result<()> {
                 WhenWalrusUpload::Modified,
             )
             .run()
-            .await?
+            .await?;
         }
         Commands::Update {
             publish_options,
@@ -254,29 +1190,487 @@ async fn run() -> Result<()> {
                 WhenWalrusUpload::from_force_flag(force),
             )
             .run()
-            .await?
+            .await?;
+        }
+        Commands::Repair {
+            publish_options,
+            object_id,
+        } => {
+            SiteEditor::new(
+                publish_options,
+                SiteIdentifier::ExistingSite(object_id),
+                config,
+                ContinuousEditing::Once,
+                WhenWalrusUpload::Missing,
+            )
+            .run()
+            .await?;
         }
         // Add a path to be watched. All files and directories at that path and
         // below will be monitored for changes.
-        Commands::Sitemap { object } => {
-            let wallet = load_wallet_context(&config.general.wallet)?;
-            let all_dynamic_fields = RemoteSiteFactory::new(&wallet.get_client().await?, object)
-                .get_existing_resources()
+        Commands::Sitemap {
+            objects,
+            keep_going,
+            max_concurrent_sites,
+            format,
+            prefix,
+        } => {
+            let wallet = config.load_wallet().await?;
+            let client = wallet.get_client().await?;
+            let prefix = prefix.as_deref().unwrap_or_default();
+            batch::for_each_site(&objects, keep_going, max_concurrent_sites, |object| {
+                let client = &client;
+                let config = &config;
+                async move {
+                    let remote_site = RemoteSiteFactory::new(client, config.package());
+                    match format {
+                        SitemapFormat::List => {
+                            let all_dynamic_fields = RemoteSiteFactory::new(client, object)
+                                .get_existing_resources()
+                                .await?;
+                            println!("Pages in site at object id: {}", object);
+                            for (name, id) in all_dynamic_fields
+                                .into_iter()
+                                .filter(|(name, _)| name.starts_with(prefix))
+                            {
+                                println!("  - {:<40} {:?}", name, id);
+                            }
+                        }
+                        SitemapFormat::Xml => {
+                            let site = remote_site.get_prefix_from_chain(object, prefix).await?;
+                            let site_url = config.site_url(&object)?;
+                            print!("{}", sitemap::to_xml(&site, &site_url));
+                        }
+                        SitemapFormat::Json => {
+                            let site = remote_site.get_prefix_from_chain(object, prefix).await?;
+                            println!("{}", sitemap::to_json(&site)?);
+                        }
+                    }
+                    Ok(())
+                }
+            })
+            .await?
+        }
+        Commands::Apply { plan } => publish::apply_plan(config, &plan).await?,
+        Commands::Mv {
+            site_object,
+            old_path,
+            new_path,
+            glob,
+        } => publish::move_resources(config, site_object, &old_path, &new_path, glob).await?,
+        Commands::SetMetadata { site_object, name } => {
+            publish::set_metadata(config, site_object, &name).await?
+        }
+        Commands::Destroy { site_object } => publish::destroy_site(config, site_object).await?,
+        Commands::Rollback {
+            site_object,
+            snapshot_dir,
+        } => publish::rollback(config, site_object, &snapshot_dir).await?,
+        Commands::Serve {
+            site_object,
+            port,
+            lenient,
+            download_dir,
+            concurrency,
+        } => {
+            let wallet = config.load_wallet().await?;
+            let site = RemoteSiteFactory::new(&wallet.get_client().await?, config.package())
+                .get_from_chain(site_object)
+                .await?;
+            // Without a download directory, the resources are downloaded to a private temporary
+            // directory, removed when dropped.
+            let temp_dir;
+            let download_dir = match &download_dir {
+                Some(download_dir) => download_dir.as_path(),
+                None => {
+                    temp_dir = tempfile::Builder::new()
+                        .prefix("walrus-sites-serve-")
+                        .tempdir()
+                        .context("could not create the temporary download directory")?;
+                    temp_dir.path()
+                }
+            };
+            let local_site = LocalSite::download(
+                &site,
+                &publish::load_walrus(&config),
+                lenient,
+                download_dir,
+                concurrency,
+            )
+            .await?;
+            local_site.serve(port).await?;
+        }
+        Commands::Probe {
+            site_object,
+            concurrency,
+            timeout,
+            json,
+        } => {
+            let wallet = config.load_wallet().await?;
+            let site = RemoteSiteFactory::new(&wallet.get_client().await?, config.package())
+                .get_from_chain(site_object)
+                .await?;
+            let site_url = config.site_url(&site_object)?;
+            display::action(format!(
+                "Probing {} resources at {site_url}",
+                site.resources().inner.len()
+            ));
+            let results = probe::probe_site(
+                &site,
+                &site_url,
+                concurrency,
+                Duration::from_secs(timeout),
+                config.general.proxy.as_deref(),
+            )
+            .await?;
+            display::done();
+            if json {
+                println!("{}", serde_json::to_string_pretty(&results)?);
+            } else {
+                print!("{}", probe::to_table(&results));
+            }
+            let unreachable = results
+                .iter()
+                .filter(|result| !result.is_reachable())
+                .count();
+            ensure!(
+                unreachable == 0,
+                "{unreachable} of {} resources are not served by the portal",
+                results.len()
+            );
+        }
+        Commands::ListSites { address } => {
+            let mut wallet = config.load_wallet().await?;
+            let address = match address {
+                Some(address) => address,
+                None => wallet.active_address()?,
+            };
+            let client = wallet.get_client().await?;
+            let site_type = RemoteSiteFactory::new(&client, config.package()).site_type()?;
+            for site_id in get_owned_sites(&client, address, site_type).await? {
+                println!("{site_id}  {}", config.site_url(&site_id)?);
+            }
+        }
+        Commands::Validate {
+            directory,
+            ws_resources,
+            nested_ws_resources,
+            include_dotfiles,
+            symlinks,
+        } => publish::validate_ws_resources(
+            &directory,
+            &ws_resources,
+            nested_ws_resources,
+            FileLister {
+                include_dotfiles,
+                symlinks,
+                skip_unreadable: false,
+            },
+        )?,
+        Commands::Analyze {
+            directory,
+            top,
+            large_resource_threshold,
+            include_dotfiles,
+            symlinks,
+        } => {
+            let resources = analyze::read_directory(
+                &directory,
+                FileLister {
+                    include_dotfiles,
+                    symlinks,
+                    skip_unreadable: false,
+                },
+            )?;
+            analyze::SizeReport::new(resources, top, large_resource_threshold as u64).print();
+        }
+        Commands::Plan {
+            publish_options,
+            exit_code,
+        } => {
+            let lockfile = publish_options
+                .lockfile
+                .clone()
+                .unwrap_or_else(|| PathBuf::from(DEFAULT_LOCKFILE));
+            let n_differences = SiteEditor::new(
+                publish_options,
+                SiteIdentifier::NewSite(String::new()),
+                config,
+                ContinuousEditing::Once,
+                WhenWalrusUpload::Modified,
+            )
+            .plan_from_lockfile(&lockfile)
+            .await?;
+            ensure!(
+                !exit_code || n_differences == 0,
+                "the site differs from the lockfile {}",
+                lockfile.display()
+            );
+        }
+        Commands::LockfileVerify {
+            site_object,
+            lockfile,
+        } => {
+            let n_differences = publish::verify_lockfile(&config, site_object, &lockfile).await?;
+            ensure!(
+                n_differences == 0,
+                "the site {site_object} drifted from the lockfile {}",
+                lockfile.display()
+            );
+        }
+        Commands::Whoami => {
+            let mut wallet = config.load_network_wallet()?;
+            let rpc = wallet.config.get_active_env()?.rpc.clone();
+            config.connect_wallet(&mut wallet).await?;
+            let address = wallet.active_address()?;
+            let balance = sui_balance(&mut wallet).await?;
+            println!("Address:       {address}");
+            println!(
+                "Balance:       {balance} MIST ({:.4} SUI)",
+                balance as f64 / MIST_PER_SUI as f64
+            );
+            println!(
+                "Wallet config: {}",
+                wallet_config_path(&config.general.wallet)?.display()
+            );
+            println!("{}", wallet.config.keystore);
+            println!(
+                "Network:       {}",
+                config
+                    .general
+                    .network
+                    .map_or("not set".to_owned(), |network| {
+                        format!("{network:?}").to_lowercase()
+                    })
+            );
+            println!("Sui RPC:       {rpc}");
+            println!(
+                "Walrus RPC:    {}",
+                config
+                    .general
+                    .rpc_url
+                    .as_deref()
+                    .unwrap_or("from the Walrus configuration")
+            );
+            println!("Package:       {}", config.package());
+            println!(
+                "Portal:        {}",
+                config
+                    .portal_url_template
+                    .as_deref()
+                    .unwrap_or(&config.portal)
+            );
+        }
+        Commands::Tx { digest } => {
+            let wallet = config.load_wallet().await?;
+            let response = wallet
+                .get_client()
+                .await?
+                .read_api()
+                .get_transaction_with_options(
+                    digest,
+                    SuiTransactionBlockResponseOptions::new().with_effects(),
+                )
+                .await?;
+            let effects = response
+                .effects
+                .ok_or(anyhow!("the response for {digest} did not contain effects"))?;
+            println!("{}", effects.to_summary());
+        }
+        Commands::History { site_object } => {
+            let wallet = config.load_wallet().await?;
+            let sui_client = wallet.get_client().await?;
+            let query = SuiTransactionBlockResponseQuery::new(
+                Some(TransactionFilter::ChangedObject(site_object)),
+                Some(
+                    SuiTransactionBlockResponseOptions::new()
+                        .with_input()
+                        .with_effects(),
+                ),
+            );
+            let history = handle_pagination(|cursor| {
+                sui_client
+                    .read_api()
+                    .query_transaction_blocks(query.clone(), cursor, None, true)
+            })
+            .await?;
+            for response in history {
+                println!("{}", DeployRecord::from_response(&response).to_summary());
+            }
+        }
+        Commands::CheckExpiry {
+            site_object,
+            warn_epochs,
+            top,
+        } => {
+            let wallet = config.load_wallet().await?;
+            let site = RemoteSiteFactory::new(&wallet.get_client().await?, config.package())
+                .get_from_chain(site_object)
+                .await?;
+            let walrus = publish::load_walrus(&config);
+            let current_epoch = walrus.info(false).await?.current_epoch;
+            let expiries = expiry::read_expiries(&walrus, &site).await?;
+            let expiring = expiry::expiring(&expiries, current_epoch + warn_epochs);
+            if expiring.is_empty() {
+                println!(
+                    "All the {} resources are stored for more than {warn_epochs} epochs after \
+                    the current epoch {current_epoch}",
+                    expiries.len()
+                );
+                return Ok(());
+            }
+            println!(
+                "{} resources expire within {warn_epochs} epochs of the current epoch \
+                {current_epoch}:",
+                expiring.len()
+            );
+            for expiry in expiring.iter().take(top) {
+                match expiry.certified_until {
+                    Some(epoch) => println!(
+                        "  - {:<40} {} certified until epoch {epoch}",
+                        expiry.path, expiry.blob_id
+                    ),
+                    None => println!("  - {:<40} {} not stored", expiry.path, expiry.blob_id),
+                }
+            }
+            if expiring.len() > top {
+                println!("  and {} more", expiring.len() - top);
+            }
+            std::process::exit(expiry::EXPIRING_EXIT_CODE);
+        }
+        Commands::Count { site_object } => {
+            let wallet = config.load_wallet().await?;
+            let (n_resources, n_metadata) =
+                RemoteSiteFactory::new(&wallet.get_client().await?, config.package())
+                    .count_fields(site_object)
+                    .await?;
+            tracing::info!(n_resources, n_metadata, "counted the fields of the site");
+            println!("{}", n_resources);
+        }
+        Commands::Fields {
+            site_object,
+            raw,
+            prefix,
+        } => {
+            let wallet = config.load_wallet().await?;
+            let sui_client = wallet.get_client().await?;
+            let site_factory = RemoteSiteFactory::new(&sui_client, config.package());
+            for field in site_factory
+                .get_fields(site_object)
+                .await?
+                .into_iter()
+                .filter(|field| {
+                    prefix
+                        .as_deref()
+                        .map_or(true, |prefix| site_factory.has_path_prefix(field, prefix))
+                })
+            {
+                if raw {
+                    println!(
+                        "{}\t{:?}\t{}\t{}\t{}",
+                        field.object_id,
+                        field.type_,
+                        field.name.type_,
+                        field.object_type,
+                        field.name.value
+                    );
+                } else {
+                    println!(
+                        "{}\t{:?}\t{}",
+                        field.object_id,
+                        site_factory.classify_field(&field),
+                        site_factory.field_label(&field)
+                    );
+                }
+            }
+        }
+        Commands::Headers { site_object, path } => {
+            let wallet = config.load_wallet().await?;
+            let resource = RemoteSiteFactory::new(&wallet.get_client().await?, config.package())
+                .get_resource(site_object, &path)
+                .await?
+                .ok_or_else(|| anyhow!("the site {site_object} has no resource at {path}"))?;
+            println!("Headers of {path} (blob ID {}):", resource.blob_id);
+            print!(
+                "{}",
+                headers::to_table(&headers::header_rows(&resource.headers))
+            );
+        }
+        Commands::Root { site_object } => {
+            let wallet = config.load_wallet().await?;
+            let site = RemoteSiteFactory::new(&wallet.get_client().await?, config.package())
+                .get_from_chain(site_object)
+                .await?;
+            println!("{}", site.resource_root());
+        }
+        Commands::ExportManifest {
+            site_object,
+            output,
+        } => {
+            let wallet = config.load_wallet().await?;
+            let site = RemoteSiteFactory::new(&wallet.get_client().await?, config.package())
+                .get_from_chain(site_object)
                 .await?;
-            println!("Pages in site at object id: {}", object);
-            for (name, id) in all_dynamic_fields {
-                println!("  - {:<40} {:?}", name, id);
+            let manifest = serde_json::to_string_pretty(&SiteManifest::new(site_object, &site))?;
+            match output {
+                Some(path) => std::fs::write(&path, manifest).context(format!(
+                    "unable to write the manifest to {}",
+                    path.display()
+                ))?,
+                None => println!("{manifest}"),
             }
         }
         Commands::Convert { object_id } => println!("{}", id_to_base36(&object_id)?),
+        Commands::VerifySubdomain { object_id, base36 } => {
+            verify_base36(&object_id, &base36)?;
+            println!(
+                "The subdomain {} matches the object ID {}",
+                base36, object_id
+            );
+        }
         Commands::ListDirectory { path } => {
             Preprocessor::preprocess(path.as_path())?;
         }
+        Commands::BenchBase36 { count } => {
+            let ids = (0..count).map(|_| ObjectID::random()).collect::<Vec<_>>();
+            let bench = bench_base36(&ids)?;
+            println!(
+                "Encoded {count} object IDs in {:?} ({:.0} IDs/s)",
+                bench.encoding,
+                bench.encoded_per_second()
+            );
+            println!(
+                "Decoded {count} object IDs in {:?} ({:.0} IDs/s)",
+                bench.decoding,
+                bench.decoded_per_second()
+            );
+            println!("All the object IDs decode to themselves");
+        }
+        // Handled in `run`, before the package is required.
+        Commands::ConfigCheck => (),
     };
 
     Ok(())
 }
 
+impl Commands {
+    /// Returns `true` if the command only prints the URL of the site to stdout.
+    fn prints_url_only(&self) -> bool {
+        match self {
+            Commands::Publish {
+                publish_options, ..
+            }
+            | Commands::Update {
+                publish_options, ..
+            }
+            | Commands::Repair {
+                publish_options, ..
+            } => publish_options.print_url_only,
+            _ => false,
+        }
+    }
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     run()
@@ -0,0 +1,151 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Portable manifests describing the content of a deployed site.
+//!
+//! The manifest is a JSON object, meant to be consumed by tools that mirror or verify the site on
+//! other content-addressed systems:
+//!
+//! ```json
+//! {
+//!   "manifest_version": 1,
+//!   "site_object": "0x…",
+//!   "resource_root": "…",
+//!   "resources": [
+//!     {
+//!       "path": "/index.html",
+//!       "content_type": "text/html",
+//!       "size": null,
+//!       "sha256": "…",
+//!       "walrus_blob_id": "…",
+//!       "allowed_methods": ["GET", "HEAD"]
+//!     }
+//!   ],
+//!   "routes": { "/*": "/index.html" }
+//! }
+//! ```
+//!
+//! The `sha256` is the hex-encoded SHA-256 digest of the content of the resource, and can be
+//! compared with the digest of the same content on any other system. The `size` is only known if
+//! the resource has a `content-length` header, and is `null` otherwise. The `allowed_methods` are
+//! the ones of the `allow` header, and are `null` if the resource does not declare any. Fields are
+//! only added in the same `manifest_version`; any other change increments it.
+
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+use sui_types::base_types::ObjectID;
+
+use crate::site::{resource::hash_to_hex, SiteData};
+
+/// The version of the manifest format.
+pub const MANIFEST_VERSION: u32 = 1;
+
+/// The manifest of a site.
+#[derive(Debug, Clone, Serialize)]
+pub struct SiteManifest {
+    pub manifest_version: u32,
+    pub site_object: ObjectID,
+    /// The root of the Merkle tree of the resources, as printed by the `root` command.
+    pub resource_root: String,
+    /// The resources, sorted by path.
+    pub resources: Vec<ManifestResource>,
+    pub routes: BTreeMap<String, String>,
+}
+
+/// A resource in the manifest of a site.
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct ManifestResource {
+    pub path: String,
+    pub content_type: Option<String>,
+    pub size: Option<u64>,
+    pub sha256: String,
+    pub walrus_blob_id: String,
+    pub allowed_methods: Option<Vec<String>>,
+}
+
+impl SiteManifest {
+    /// Creates the manifest of the site stored at the object.
+    pub fn new(site_object: ObjectID, site: &SiteData) -> Self {
+        let mut resources = site
+            .resources()
+            .inner
+            .iter()
+            .map(|resource| {
+                let headers = &resource.info.headers.0;
+                ManifestResource {
+                    path: resource.info.path.clone(),
+                    content_type: headers.get("content-type").cloned(),
+                    size: headers
+                        .get("content-length")
+                        .and_then(|length| length.parse().ok()),
+                    sha256: hash_to_hex(&resource.info.blob_hash),
+                    walrus_blob_id: resource.info.blob_id.to_string(),
+                    allowed_methods: headers.get("allow").map(|allow| {
+                        allow
+                            .split(',')
+                            .map(|method| method.trim().to_owned())
+                            .filter(|method| !method.is_empty())
+                            .collect()
+                    }),
+                }
+            })
+            .collect::<Vec<_>>();
+        resources.sort_by(|a, b| a.path.cmp(&b.path));
+        SiteManifest {
+            manifest_version: MANIFEST_VERSION,
+            site_object,
+            resource_root: site.resource_root(),
+            resources,
+            routes: site
+                .routes()
+                .map(|routes| routes.0.clone())
+                .unwrap_or_default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::{
+        site::resource::{resource_hash, Resource, ResourceSet},
+        types::HttpHeaders,
+        walrus::types::BlobId,
+    };
+
+    #[test]
+    fn test_site_manifest() {
+        let headers = HttpHeaders(BTreeMap::from([
+            ("content-type".to_owned(), "video/mp4".to_owned()),
+            ("content-length".to_owned(), "3".to_owned()),
+            ("allow".to_owned(), "GET, HEAD".to_owned()),
+        ]));
+        let resource = Resource::new(
+            "/intro.mp4".to_owned(),
+            PathBuf::from("intro.mp4"),
+            headers,
+            BlobId([0; BlobId::LENGTH]),
+            resource_hash(b"abc"),
+            3,
+        );
+        let site = SiteData::new(ResourceSet::from_iter([resource]), None);
+        let manifest = SiteManifest::new(ObjectID::ZERO, &site);
+        assert_eq!(manifest.manifest_version, MANIFEST_VERSION);
+        assert_eq!(
+            manifest.resources,
+            vec![ManifestResource {
+                path: "/intro.mp4".to_owned(),
+                content_type: Some("video/mp4".to_owned()),
+                size: Some(3),
+                // The SHA-256 digest of `abc`.
+                sha256: "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+                    .to_owned(),
+                walrus_blob_id: BlobId([0; BlobId::LENGTH]).to_string(),
+                allowed_methods: Some(vec!["GET".to_owned(), "HEAD".to_owned()]),
+            }]
+        );
+    }
+}
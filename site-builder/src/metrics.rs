@@ -0,0 +1,202 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Metrics of the deploys, written in the Prometheus text format, e.g., for the textfile collector
+//! of the node exporter.
+
+use std::{fmt::Write, path::Path, time::Duration};
+
+use anyhow::{Context, Result};
+use sui_types::base_types::ObjectID;
+
+/// The metrics of the deploys of a site.
+///
+/// The gauges are the ones of the last successful deploy; the failures are counted across the
+/// deploys recorded in the same file.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct DeployMetrics {
+    /// The site, if known; the publish of a new site can fail before the site is created.
+    pub site: Option<ObjectID>,
+    pub duration: Duration,
+    pub gas_mist: u64,
+    pub resources: usize,
+    pub blobs_uploaded: usize,
+    pub failures: u64,
+}
+
+/// The name, type, and help of each metric, in the order in which they are written.
+const METRICS: [(&str, &str, &str); 5] = [
+    (
+        "walrus_deploy_duration_seconds",
+        "gauge",
+        "The duration of the last successful deploy.",
+    ),
+    (
+        "walrus_deploy_gas_mist",
+        "gauge",
+        "The gas spent by the last successful deploy, in MIST.",
+    ),
+    (
+        "walrus_deploy_resources_total",
+        "gauge",
+        "The number of resources of the site after the last successful deploy.",
+    ),
+    (
+        "walrus_deploy_blobs_uploaded_total",
+        "gauge",
+        "The number of blobs uploaded to Walrus by the last successful deploy.",
+    ),
+    (
+        "walrus_deploy_failures_total",
+        "counter",
+        "The number of failed deploys.",
+    ),
+];
+
+impl DeployMetrics {
+    /// Records a successful deploy in the metrics file, keeping the count of failures.
+    pub fn record_success(path: &Path, metrics: DeployMetrics) -> Result<()> {
+        let failures = Self::read(path)?.map_or(0, |previous| previous.failures);
+        DeployMetrics {
+            failures,
+            ..metrics
+        }
+        .write(path)
+    }
+
+    /// Records a failed deploy in the metrics file, keeping the metrics of the last successful
+    /// deploy.
+    pub fn record_failure(path: &Path, site: Option<ObjectID>) -> Result<()> {
+        let previous = Self::read(path)?.unwrap_or_default();
+        DeployMetrics {
+            site: site.or(previous.site),
+            failures: previous.failures + 1,
+            ..previous
+        }
+        .write(path)
+    }
+
+    /// Reads the metrics written to the file, if it exists.
+    fn read(path: &Path) -> Result<Option<Self>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        let text = std::fs::read_to_string(path).context(format!(
+            "could not read the metrics file {}",
+            path.display()
+        ))?;
+        Ok(Some(Self::parse(&text)))
+    }
+
+    /// Parses the metrics, ignoring the lines that are not recognized.
+    fn parse(text: &str) -> Self {
+        let mut metrics = Self::default();
+        for line in text.lines().filter(|line| !line.starts_with('#')) {
+            let Some((series, value)) = line.rsplit_once(' ') else {
+                continue;
+            };
+            let (name, labels) = series.split_once('{').unwrap_or((series, ""));
+            if let Some(site) = labels
+                .strip_prefix("site=\"")
+                .and_then(|labels| labels.split_once('"'))
+                .and_then(|(site, _)| site.parse().ok())
+            {
+                metrics.site = Some(site);
+            }
+            let Ok(value) = value.parse::<f64>() else {
+                continue;
+            };
+            match name {
+                "walrus_deploy_duration_seconds" => {
+                    metrics.duration = Duration::try_from_secs_f64(value).unwrap_or_default()
+                }
+                "walrus_deploy_gas_mist" => metrics.gas_mist = value as u64,
+                "walrus_deploy_resources_total" => metrics.resources = value as usize,
+                "walrus_deploy_blobs_uploaded_total" => metrics.blobs_uploaded = value as usize,
+                "walrus_deploy_failures_total" => metrics.failures = value as u64,
+                _ => (),
+            }
+        }
+        metrics
+    }
+
+    /// Formats the metrics in the Prometheus text format.
+    fn to_text(&self) -> String {
+        let labels = self
+            .site
+            .map(|site| format!("{{site=\"{site}\"}}"))
+            .unwrap_or_default();
+        let values = [
+            format!("{:.3}", self.duration.as_secs_f64()),
+            self.gas_mist.to_string(),
+            self.resources.to_string(),
+            self.blobs_uploaded.to_string(),
+            self.failures.to_string(),
+        ];
+        let mut text = String::new();
+        for ((name, kind, help), value) in METRICS.iter().zip(values) {
+            writeln!(text, "# HELP {name} {help}").expect("writing to a string cannot fail");
+            writeln!(text, "# TYPE {name} {kind}").expect("writing to a string cannot fail");
+            writeln!(text, "{name}{labels} {value}").expect("writing to a string cannot fail");
+        }
+        text
+    }
+
+    /// Writes the metrics to the file, through a temporary file, so that a scraper never reads a
+    /// partial file.
+    fn write(&self, path: &Path) -> Result<()> {
+        let mut temp_path = path.as_os_str().to_owned();
+        temp_path.push(".tmp");
+        std::fs::write(&temp_path, self.to_text())
+            .and_then(|()| std::fs::rename(&temp_path, path))
+            .context(format!(
+                "could not write the metrics file {}",
+                path.display()
+            ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_text_and_parse() {
+        let site = ObjectID::random();
+        let metrics = DeployMetrics {
+            site: Some(site),
+            duration: Duration::from_millis(12_500),
+            gas_mist: 4_000_000,
+            resources: 12,
+            blobs_uploaded: 3,
+            failures: 2,
+        };
+        let text = metrics.to_text();
+        assert!(text.contains("# TYPE walrus_deploy_failures_total counter\n"));
+        assert!(text.contains(&format!(
+            "walrus_deploy_gas_mist{{site=\"{site}\"}} 4000000\n"
+        )));
+        assert_eq!(DeployMetrics::parse(&text), metrics);
+    }
+
+    #[test]
+    fn test_record() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("walrus-sites.prom");
+        DeployMetrics::record_failure(&path, None).unwrap();
+        DeployMetrics::record_success(
+            &path,
+            DeployMetrics {
+                site: Some(ObjectID::ZERO),
+                resources: 5,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        DeployMetrics::record_failure(&path, None).unwrap();
+        let metrics = DeployMetrics::read(&path).unwrap().unwrap();
+        assert_eq!(metrics.site, Some(ObjectID::ZERO));
+        assert_eq!(metrics.resources, 5);
+        assert_eq!(metrics.failures, 2);
+    }
+}
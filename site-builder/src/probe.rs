@@ -0,0 +1,160 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Measurement of the latency of the resources of a site, as served by the portal.
+
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use futures::{stream, StreamExt};
+use serde::Serialize;
+
+use crate::{site::SiteData, util::http_client_builder};
+
+/// The result of the request of a resource through the portal.
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ProbeResult {
+    /// The resource path.
+    pub path: String,
+    /// The HTTP status of the response, if one was received.
+    pub status: Option<u16>,
+    /// The time until the headers of the response were received, in milliseconds.
+    pub time_to_first_byte_ms: u64,
+    /// The error of the request, if it failed.
+    pub error: Option<String>,
+}
+
+impl ProbeResult {
+    /// Whether the portal served the resource successfully.
+    pub fn is_reachable(&self) -> bool {
+        self.status
+            .is_some_and(|status| (200..300).contains(&status))
+    }
+}
+
+/// Requests every resource of the site through the portal at `site_url`, with at most
+/// `concurrency` requests in flight.
+///
+/// The results are sorted with the unreachable resources first, then from the slowest.
+pub(crate) async fn probe_site(
+    site: &SiteData,
+    site_url: &str,
+    concurrency: usize,
+    timeout: Duration,
+    proxy: Option<&str>,
+) -> Result<Vec<ProbeResult>> {
+    let paths = site
+        .resources()
+        .inner
+        .iter()
+        .map(|resource| resource.info.path.clone())
+        .collect();
+    probe_paths(paths, site_url, concurrency, timeout, proxy).await
+}
+
+/// Requests the resource paths through the portal at `site_url`, with at most `concurrency`
+/// requests in flight, e.g., to populate the cache of the portal.
+///
+/// The results are sorted as in [`probe_site`].
+pub(crate) async fn probe_paths(
+    paths: Vec<String>,
+    site_url: &str,
+    concurrency: usize,
+    timeout: Duration,
+    proxy: Option<&str>,
+) -> Result<Vec<ProbeResult>> {
+    let client = http_client_builder(proxy)?.timeout(timeout).build()?;
+    let site_url = site_url.trim_end_matches('/');
+    let mut results = stream::iter(paths)
+        .map(|path| {
+            let client = &client;
+            async move {
+                let start = Instant::now();
+                let response = client.get(format!("{site_url}{path}")).send().await;
+                let time_to_first_byte_ms = start.elapsed().as_millis() as u64;
+                tracing::debug!(%path, time_to_first_byte_ms, "probed the resource");
+                match response {
+                    Ok(response) => ProbeResult {
+                        path,
+                        status: Some(response.status().as_u16()),
+                        time_to_first_byte_ms,
+                        error: None,
+                    },
+                    Err(error) => ProbeResult {
+                        path,
+                        status: error.status().map(|status| status.as_u16()),
+                        time_to_first_byte_ms,
+                        error: Some(error.to_string()),
+                    },
+                }
+            }
+        })
+        .buffer_unordered(concurrency.max(1))
+        .collect::<Vec<_>>()
+        .await;
+    sort_results(&mut results);
+    Ok(results)
+}
+
+/// Sorts the results with the unreachable resources first, then from the slowest.
+fn sort_results(results: &mut [ProbeResult]) {
+    results.sort_by(|a, b| {
+        a.is_reachable()
+            .cmp(&b.is_reachable())
+            .then(b.time_to_first_byte_ms.cmp(&a.time_to_first_byte_ms))
+            .then(a.path.cmp(&b.path))
+    });
+}
+
+/// Formats the results as a table, one resource per line.
+pub(crate) fn to_table(results: &[ProbeResult]) -> String {
+    let mut table = format!("{:>6} {:>9}  {}\n", "STATUS", "TTFB (ms)", "PATH");
+    for result in results {
+        let status = result
+            .status
+            .map_or_else(|| "-".to_owned(), |status| status.to_string());
+        table.push_str(&format!(
+            "{status:>6} {:>9}  {}",
+            result.time_to_first_byte_ms, result.path
+        ));
+        if let Some(error) = &result.error {
+            table.push_str(&format!(" ({error})"));
+        }
+        table.push('\n');
+    }
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(path: &str, status: Option<u16>, time_to_first_byte_ms: u64) -> ProbeResult {
+        ProbeResult {
+            path: path.to_owned(),
+            status,
+            time_to_first_byte_ms,
+            error: None,
+        }
+    }
+
+    #[test]
+    fn test_sort_results() {
+        let mut results = vec![
+            result("/fast.js", Some(200), 10),
+            result("/slow.js", Some(200), 500),
+            result("/missing.js", Some(404), 5),
+            result("/timeout.js", None, 30_000),
+        ];
+        sort_results(&mut results);
+        let paths = results
+            .iter()
+            .map(|result| result.path.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            paths,
+            vec!["/timeout.js", "/missing.js", "/slow.js", "/fast.js"]
+        );
+    }
+}
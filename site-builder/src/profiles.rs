@@ -0,0 +1,103 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Bundled defaults for the networks on which Walrus Sites can be published.
+
+use std::str::FromStr;
+
+use clap::ValueEnum;
+use serde::Deserialize;
+use sui_types::base_types::ObjectID;
+
+/// The Walrus Sites package on Testnet.
+const TESTNET_PACKAGE: &str = "0xc5bebae319fc9d2a9dc858b7484cdbd6ef219decf4662dc81a11dc69bb7a5fa7";
+
+/// The chain identifier of Mainnet, as returned by the full nodes.
+const MAINNET_CHAIN_ID: &str = "35834a8a";
+/// The chain identifier of Testnet, as returned by the full nodes.
+const TESTNET_CHAIN_ID: &str = "4c78adac";
+
+/// The network on which to operate.
+#[derive(Debug, ValueEnum, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Network {
+    Mainnet,
+    Testnet,
+    Devnet,
+    Localnet,
+}
+
+/// The network-specific defaults selected with `--network`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NetworkProfile {
+    /// The URL of the Sui RPC endpoint.
+    pub rpc_url: &'static str,
+    /// The Walrus Sites package, if one is known for the network.
+    pub package: Option<ObjectID>,
+}
+
+impl Network {
+    /// Returns the defaults for the network.
+    pub fn profile(&self) -> NetworkProfile {
+        match self {
+            Network::Mainnet => NetworkProfile {
+                rpc_url: "https://fullnode.mainnet.sui.io:443",
+                package: None,
+            },
+            Network::Testnet => NetworkProfile {
+                rpc_url: "https://fullnode.testnet.sui.io:443",
+                package: Some(
+                    ObjectID::from_str(TESTNET_PACKAGE).expect("the package ID is valid"),
+                ),
+            },
+            Network::Devnet => NetworkProfile {
+                rpc_url: "https://fullnode.devnet.sui.io:443",
+                package: None,
+            },
+            Network::Localnet => NetworkProfile {
+                rpc_url: "http://127.0.0.1:9000",
+                package: None,
+            },
+        }
+    }
+
+    /// Returns true if the chain identifier can be the one of the network.
+    ///
+    /// The chains of Devnet and of the local networks cannot be identified, so any chain other
+    /// than the public ones can be theirs.
+    pub fn matches_chain(&self, chain_id: &str) -> bool {
+        match Network::from_chain_id(chain_id) {
+            Some(network) => network == *self,
+            None => !matches!(self, Network::Mainnet | Network::Testnet),
+        }
+    }
+
+    /// Returns the public network with the given chain identifier, if any.
+    ///
+    /// Devnet and local networks are reset regularly, and cannot be identified by their chain.
+    pub fn from_chain_id(chain_id: &str) -> Option<Network> {
+        match chain_id {
+            MAINNET_CHAIN_ID => Some(Network::Mainnet),
+            TESTNET_CHAIN_ID => Some(Network::Testnet),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profiles() {
+        assert!(Network::Testnet.profile().package.is_some());
+        assert_eq!(Network::Localnet.profile().rpc_url, "http://127.0.0.1:9000");
+        assert_eq!(Network::from_chain_id("35834a8a"), Some(Network::Mainnet));
+        assert_eq!(Network::from_chain_id("00000000"), None);
+        assert!(Network::Testnet.matches_chain("4c78adac"));
+        assert!(!Network::Mainnet.matches_chain("4c78adac"));
+        assert!(!Network::Mainnet.matches_chain("00000000"));
+        assert!(Network::Devnet.matches_chain("00000000"));
+        assert!(!Network::Devnet.matches_chain("35834a8a"));
+    }
+}
@@ -2,45 +2,116 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use std::{
-    path::{Path, PathBuf},
+    collections::{BTreeMap, BTreeSet},
+    num::NonZeroUsize,
+    path::{Component, Path, PathBuf},
     sync::mpsc::channel,
+    time::{Duration, Instant},
 };
 
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, bail, ensure, Context, Result};
 use clap::Parser;
 use notify::{RecursiveMode, Watcher};
+use serde::{Deserialize, Serialize};
 use sui_sdk::rpc_types::{
     SuiExecutionStatus,
     SuiTransactionBlockEffects,
     SuiTransactionBlockResponse,
 };
-use sui_types::base_types::SuiAddress;
+use sui_types::base_types::{ObjectID, SuiAddress};
+use tempfile::TempDir;
 
 use crate::{
     display,
+    git::GitMetadata,
+    image,
+    metrics::DeployMetrics,
     preprocessor::Preprocessor,
+    probe,
     site::{
+        base_href::BaseHref,
+        cache::CachePolicy,
+        chunking::ChunkStrategy,
         config::WSResources,
-        manager::{SiteIdentifier, SiteManager},
-        resource::ResourceManager,
+        csp::CspGenerator,
+        directory_index::DirectoryIndex,
+        links::{dangling_references, page_resources},
+        lockfile::{SiteLock, DEFAULT_LOCKFILE},
+        manager::{SiteIdentifier, SiteManager, UpdateOptions},
+        plan::SitePlan,
+        resource::{
+            hash_to_hex,
+            ContentTypeOverride,
+            FileLister,
+            Mount,
+            ResourceManager,
+            ResourceOptions,
+            SymlinkPolicy,
+        },
+        snapshot::{SiteSnapshot, DEFAULT_SNAPSHOT_DIR},
+        RemoteSiteFactory,
+        SiteData,
     },
-    summary::{SiteDataDiffSummary, Summarizable},
+    summary::{GasReport, SiteDataDiffSummary, Summarizable},
+    types::Routes,
     util::{
+        ensure_sui_balance,
         get_site_id_from_response,
         id_to_base36,
-        load_wallet_context,
         path_or_defaults_if_exist,
+        verify_base36,
+        ResourceSelection,
     },
-    walrus::Walrus,
+    walrus::{output::Epoch, Walrus},
+    webhook::{Webhook, WebhookPayload},
     Config,
 };
 
-const DEFAULT_WS_RESOURCES_FILE: &str = "ws-resources.json";
+pub(crate) const DEFAULT_WS_RESOURCES_FILE: &str = "ws-resources.json";
+/// The resource path the portal looks up for the root URL of a site.
+const ROOT_RESOURCE_PATH: &str = "/index.html";
+/// The default entry point of a site, served by the portal at the root URL.
+const DEFAULT_ENTRY_POINT: &str = ROOT_RESOURCE_PATH;
+/// The default largest percentage of the resources that an update can remove.
+const DEFAULT_MAX_DELETION_PERCENT: u8 = 50;
+/// The default size above which a warning is displayed for a resource: 10 MiB.
+pub(crate) const DEFAULT_LARGE_RESOURCE_THRESHOLD: usize = 10 * 1024 * 1024;
+/// The maximum number of requests in flight to warm the cache of the portal.
+const PREWARM_CONCURRENCY: usize = 8;
+/// The timeout of each request to warm the cache of the portal.
+const PREWARM_TIMEOUT: Duration = Duration::from_secs(30);
 
 #[derive(Parser, Debug, Clone)]
 pub struct PublishOptions {
     /// The directory containing the site sources.
+    ///
+    /// With `--from-image`, the directory into which the files are extracted, which must be empty
+    /// or missing.
     pub directory: PathBuf,
+    /// Extract the site from this container image, e.g., `registry.example.com/frontend:1.2`,
+    /// instead of reading a directory built locally.
+    ///
+    /// The image is pulled if needed, and its files under `--image-path` are copied into the site
+    /// directory with the container CLI, without running the image. The links that point outside
+    /// of the site and the special files are removed.
+    #[clap(long, value_name = "IMAGE")]
+    pub from_image: Option<String>,
+    /// The directory of the image containing the site, with `--from-image`.
+    #[clap(
+        long,
+        value_name = "PATH",
+        default_value = "/usr/share/nginx/html",
+        requires = "from_image"
+    )]
+    pub image_path: String,
+    /// The container CLI used to extract the image, e.g., `podman`.
+    #[clap(
+        long,
+        value_name = "BINARY",
+        default_value = "docker",
+        requires = "from_image"
+    )]
+    pub container_cli: String,
     /// The path to the Walrus sites resources file.
     ///
     /// This JSON configuration file defined HTTP resource headers and other utilities for your
@@ -50,6 +121,12 @@ pub struct PublishOptions {
     /// The configuration file _will not_ be uploaded to Walrus.
     #[clap(long)]
     ws_resources: Option<PathBuf>,
+    /// Also read the `ws-resources.json` files in the subdirectories of the site directory.
+    ///
+    /// Each nested file configures the files under its directory, with paths relative to it, and
+    /// the nearest file wins for the same path. The nested files are not uploaded either.
+    #[clap(long, action)]
+    pub nested_ws_resources: bool,
     /// The number of epochs for which to save the resources on Walrus.
     #[clap(long, default_value_t = 1)]
     pub epochs: u64,
@@ -57,6 +134,423 @@ pub struct PublishOptions {
     /// See the `list-directory` command. Warning: Rewrites all `index.html` files.
     #[clap(long, action)]
     pub list_directory: bool,
+    /// Include dotfiles and dot-directories (e.g., `.well-known/`) in the site.
+    ///
+    /// By default, all files and directories with a name starting with `.` are skipped.
+    #[clap(long, action)]
+    pub include_dotfiles: bool,
+    /// Additional directories to publish under a path of the site, in the `<DIR>:<PATH>` format.
+    ///
+    /// For example, `--mount docs:/docs` publishes the contents of the `docs` directory under
+    /// `/docs`. Can be repeated. The main directory is always mounted at `/`.
+    #[clap(long = "mount", value_name = "DIR:PATH")]
+    pub mounts: Vec<Mount>,
+    /// Compute and print the operations needed to update the site, without executing them.
+    ///
+    /// The summary includes the number of transactions on Sui the update is split into with the
+    /// `--chunk-strategy`, and the number of resource operations in each.
+    #[clap(long, action)]
+    pub dry_run: bool,
+    /// Write the plan computed by the dry run to this file.
+    ///
+    /// The plan can be reviewed, and then executed with the `apply` command.
+    #[clap(long, requires = "dry_run")]
+    pub dry_run_file: Option<PathBuf>,
+    /// The size in bytes above which a warning is displayed for a resource.
+    ///
+    /// Large resources are served as a single blob by the portal, and may load slowly.
+    #[clap(long, default_value_t = DEFAULT_LARGE_RESOURCE_THRESHOLD)]
+    pub large_resource_threshold: usize,
+    /// Also extend the storage of all the blobs of the site until at least this Walrus epoch.
+    ///
+    /// The blobs that expire earlier are stored again for the missing epochs, in addition to
+    /// applying the changes to the site.
+    #[clap(long, value_name = "EPOCH")]
+    pub extend_to: Option<Epoch>,
+    /// Print only the URL of the site to stdout on success, for use in scripts.
+    ///
+    /// All the other output, including the logs, goes to stderr.
+    #[clap(long, action, conflicts_with = "dry_run")]
+    pub print_url_only: bool,
+    /// Only publish the files whose resource path matches the glob pattern, e.g., `/css/*.css`.
+    ///
+    /// In the pattern, `*` does not match `/`, while `**` does, and `**/` also matches no
+    /// directory, e.g., `/**/*.css` matches `/style.css`. Only the matching resources are added or
+    /// updated: the resources of the site that do not match are never removed, and the routes are
+    /// left unchanged.
+    #[clap(long, value_name = "PATTERN", conflicts_with = "dry_run")]
+    pub only: Option<String>,
+    /// Only publish the files listed in this file, one path per line, relative to the site
+    /// directory; `-` reads the list from stdin.
+    ///
+    /// Meant for a pipeline that already knows which files changed. As with `--only`, the listed
+    /// files are added or updated, and the other resources of the site are never removed; the
+    /// other files are neither read nor hashed. Fails if a listed file does not exist.
+    #[clap(
+        long,
+        value_name = "PATH",
+        conflicts_with_all = ["dry_run", "only"]
+    )]
+    pub files_from: Option<PathBuf>,
+    /// Only publish the resources of the tag defined in `ws-resources.json`. Can be repeated, to
+    /// publish the resources of several tags.
+    ///
+    /// As with `--only`, the matching resources are added, updated, or removed, and the other
+    /// resources of the site are never removed.
+    #[clap(
+        long = "tag",
+        value_name = "TAG",
+        conflicts_with_all = ["dry_run", "only", "files_from"]
+    )]
+    pub tags: Vec<String>,
+    /// Set the `ETag` header of every resource to the hash of its content.
+    ///
+    /// The tag only changes when the content changes, which allows conditional requests to be
+    /// cached in front of the portal. An `ETag` set in `ws-resources.json` takes precedence.
+    #[clap(long, action)]
+    pub emit_etags: bool,
+    /// Publish the site even if no files are found in the directory.
+    #[clap(long, action)]
+    pub allow_empty: bool,
+    /// How to handle the symbolic links in the directory.
+    ///
+    /// When following, the links that create a cycle are skipped.
+    #[clap(long, value_enum, default_value_t = SymlinkPolicy::Skip)]
+    pub symlinks: SymlinkPolicy,
+    /// Set the content type of the resource at an exact path, in the `<PATH>=<MIME>` format.
+    ///
+    /// For example, `--content-type /manifest=application/manifest+json`. Can be repeated. Takes
+    /// precedence over the `content_types` and the headers in `ws-resources.json`, and over the
+    /// type inferred from the extension.
+    #[clap(long = "content-type", value_name = "PATH=MIME")]
+    pub content_types: Vec<ContentTypeOverride>,
+    /// Skip the update if the site did not change since the last publish recorded in this file.
+    ///
+    /// The file stores the object ID of the site and a fingerprint of its resources and routes,
+    /// and is written after every successful publish. If the fingerprint of the local site matches
+    /// the one recorded for the same site, nothing is submitted. Changes made to the site on chain
+    /// by other means are not detected.
+    #[clap(long, value_name = "PATH", conflicts_with = "dry_run")]
+    pub fingerprint_file: Option<PathBuf>,
+    /// Post the result of the publish as JSON to this URL on success.
+    ///
+    /// The payload contains the site object ID, its Base36 subdomain and URL, the transaction
+    /// digest, the gas used, and the number of resources. A failure to notify the webhook only
+    /// results in a warning.
+    #[clap(long, value_name = "URL", conflicts_with = "dry_run")]
+    pub webhook: Option<String>,
+    /// Also post the error to the webhook if the publish fails.
+    #[clap(long, action, requires = "webhook")]
+    pub webhook_on_failure: bool,
+    /// The timeout for each attempt to notify the webhook, in seconds.
+    #[clap(long, value_name = "SECONDS", default_value_t = 10)]
+    pub webhook_timeout: u64,
+    /// Write the metrics of the publish to this file, in the Prometheus text format, e.g., in the
+    /// directory of the textfile collector of the node exporter.
+    ///
+    /// The file contains the duration, the gas spent, the number of resources, and the number of
+    /// blobs uploaded by the last successful publish, and the number of failed publishes, labeled
+    /// by site object ID. It is rewritten after every publish, keeping the count of failures.
+    /// A failure to write the file only results in a warning.
+    #[clap(long, value_name = "PATH", conflicts_with = "dry_run")]
+    pub metrics_file: Option<PathBuf>,
+    /// Request the root URL, the entry point, and the resources that the entry point references
+    /// through the portal after the publish, to populate the cache of the portal for the first
+    /// visitors.
+    ///
+    /// The resources that fail to load only result in a warning.
+    #[clap(long, action, conflicts_with = "dry_run")]
+    pub prewarm: bool,
+    /// How to split the updates into transactions, if they do not fit in one.
+    ///
+    /// Every transaction respects the command and size limits of Sui. With `count`, the resources
+    /// are packed in path order; with `bytes`, the new resources are packed from the smallest.
+    #[clap(long, value_enum, default_value_t = ChunkStrategy::Count)]
+    pub chunk_strategy: ChunkStrategy,
+    /// The maximum number of commands in each transaction.
+    ///
+    /// Defaults to 1000, or to the limit of the protocol of the network if it is lower, as read
+    /// from the full node. Set a lower value if the RPC provider enforces a stricter limit.
+    #[clap(long, value_name = "N")]
+    pub max_commands_per_tx: Option<NonZeroUsize>,
+    /// The maximum number of resources added or removed in each transaction, each of which
+    /// creates or deletes an object.
+    ///
+    /// Defaults to 1000, or to the limit of the protocol of the network if it is lower, as read
+    /// from the full node.
+    #[clap(long, value_name = "N")]
+    pub max_objects_per_tx: Option<NonZeroUsize>,
+    /// Whether to delete the blobs of the resources removed from the site, after the update.
+    ///
+    /// Only the deletable blob objects owned by the wallet, whose blobs are no longer used by any
+    /// resource of the sites owned by the wallet, are deleted. The sites of other owners with the
+    /// same content cannot be checked. Keeping the blobs allows to roll back the site cheaply.
+    #[clap(long, value_name = "BOOL", action = clap::ArgAction::Set, default_value_t = false)]
+    pub prune_removed_blobs: bool,
+    /// The number of times the upload of a single blob is retried before failing the command.
+    ///
+    /// The delay between the attempts starts at one second and doubles at every retry. The
+    /// resources whose upload was retried are listed at the end of the upload.
+    #[clap(long, default_value_t = 2)]
+    pub upload_retries: u32,
+    /// Split a dedicated gas coin of this amount, in MIST, from the largest coin of the wallet,
+    /// and pay for all the transactions of the update with it.
+    ///
+    /// The coin is split in a preliminary transaction. This avoids contention on the gas coins
+    /// when several jobs share the same address. The amount must cover the gas budget.
+    #[clap(long, value_name = "MIST")]
+    pub prepare_gas: Option<u64>,
+    /// How to set the `cache-control` headers of the resources, based on their content type.
+    ///
+    /// With `default`, the HTML pages are revalidated at every request and the static assets with
+    /// a content hash in the file name are cached forever; with `aggressive`, all static assets
+    /// are cached forever. The headers set in ws-resources.json always take precedence.
+    #[clap(long, value_enum, default_value_t = CachePolicy::None)]
+    pub cache_policy: CachePolicy,
+    /// Serve the HTML pages with the headers that enable cross-origin isolation.
+    ///
+    /// Sets `Cross-Origin-Opener-Policy: same-origin` and `Cross-Origin-Embedder-Policy:
+    /// require-corp` on the HTML pages, as needed by threaded WASM apps that use
+    /// `SharedArrayBuffer`; `.wasm` files are always served as `application/wasm`. The headers set
+    /// in ws-resources.json take precedence.
+    #[clap(long, action)]
+    pub wasm_app: bool,
+    /// Only allow the files with these extensions or content types in the site, e.g.,
+    /// `--allow-types html,css,js,image/svg+xml`.
+    ///
+    /// Publishing fails if any other file is found in the directory, so that no unexpected file
+    /// (e.g., a `.env` or a key) is ever published. By default, all types are allowed.
+    #[clap(long, value_name = "TYPES", value_delimiter = ',')]
+    pub allow_types: Vec<String>,
+    /// Set a `Content-Security-Policy` header on the HTML pages, generated from the scripts,
+    /// stylesheets, images, and fonts they reference.
+    ///
+    /// The policy only allows loading them from the site itself and from the origins found in the
+    /// page; inline scripts and styles are blocked. A header set in ws-resources.json takes
+    /// precedence.
+    #[clap(long, action)]
+    pub csp: bool,
+    /// Set the generated policy with the `Content-Security-Policy-Report-Only` header instead,
+    /// which only reports the violations; useful to roll out a policy safely.
+    #[clap(long, action)]
+    pub csp_report_only: bool,
+    /// An additional source allowed by the generated policy in all the directives, e.g.,
+    /// `https://analytics.example.com`. Can be repeated.
+    #[clap(long = "csp-source", value_name = "SOURCE")]
+    pub csp_sources: Vec<String>,
+    /// Only store the headers set in `ws-resources.json` and with `--content-type`.
+    ///
+    /// None of the headers is added automatically: not even the `Content-Type` inferred from the
+    /// extension, nor the `Content-Encoding`, the cache, ETag, CSP, cross-origin isolation, and
+    /// range headers of the other options. The portal then serves the resources without them.
+    #[clap(long, action)]
+    pub no_auto_headers: bool,
+    /// Do not store this header when it is added automatically, e.g., `--exclude-header
+    /// content-encoding`. Can be repeated.
+    ///
+    /// The header is still stored on the resources that set it in `ws-resources.json`.
+    #[clap(long = "exclude-header", value_name = "NAME")]
+    pub excluded_headers: Vec<String>,
+    /// Allow resources named like the reserved fields of the site, e.g., a file `routes` at the
+    /// root of the directory.
+    ///
+    /// By default, publishing fails for such resources, as they cannot be told apart from the
+    /// reserved fields by the tools that address the fields of the site by name.
+    #[clap(long, action)]
+    pub allow_reserved_paths: bool,
+    /// Warn about the references in the HTML pages and stylesheets that will not resolve once
+    /// published.
+    ///
+    /// The references are resolved against the published resources and the routes; the URLs of
+    /// the development machine (`file://`, `localhost`) are also reported. Other origins are not
+    /// checked.
+    #[clap(long, action)]
+    pub check_links: bool,
+    /// Like `--check-links`, but fail instead of publishing if there are dangling references.
+    #[clap(long, action)]
+    pub strict_links: bool,
+    /// Skip the files and directories that cannot be read, e.g., for lack of permissions,
+    /// instead of failing.
+    ///
+    /// The skipped entries are listed in a warning. By default, an unreadable file fails the
+    /// command, so that no file is silently left out of the site.
+    #[clap(long, action)]
+    pub skip_unreadable: bool,
+    /// Save a snapshot of the site on chain before updating it, to undo the update with the
+    /// `rollback` command.
+    ///
+    /// The snapshot lists the resources and routes of the site, and is written in the
+    /// `--snapshot-dir` directory. Only the Sui object is restored on rollback: the blobs must
+    /// still be stored, so `--prune-removed-blobs` should not be used together with snapshots.
+    #[clap(long, action)]
+    pub snapshot: bool,
+    /// The directory in which the snapshots are saved.
+    #[clap(long, value_name = "DIR", default_value = DEFAULT_SNAPSHOT_DIR)]
+    pub snapshot_dir: PathBuf,
+    /// The resource path of the entry point of the site, served at the root URL, e.g.,
+    /// `--entry-point /app/index.html`.
+    ///
+    /// The entry point is always the first resource added to the site. The portal looks up the
+    /// root URL as `/index.html`, so another entry point is set as the route of `/index.html`,
+    /// unless the routes already define it or the site has an `/index.html` resource. Fails if
+    /// the entry point is not a resource of the site; a warning is only displayed if the default
+    /// `/index.html` is missing.
+    #[clap(
+        long,
+        visible_alias = "default-route",
+        value_name = "PATH",
+        default_value = DEFAULT_ENTRY_POINT
+    )]
+    pub entry_point: String,
+    /// How the `index.html` of each directory is served at the path of the directory.
+    ///
+    /// The portal serves both `/about/` and `/about` with the resource `/about`. With `alias`,
+    /// the resource `/about` is added with the same blob as `/about/index.html`, which is only
+    /// uploaded once; with `route`, a route from `/about` to `/about/index.html` is added.
+    /// Existing resources and routes are never replaced.
+    #[clap(long, value_enum, default_value_t = DirectoryIndex::None)]
+    pub directory_index: DirectoryIndex,
+    /// Read the commit, the branch, and the dirty state of the git worktree of the directory, and
+    /// include them in the summary and in the webhook payload.
+    ///
+    /// The site object has no field for metadata, so the revision is not stored on chain.
+    #[clap(long, action)]
+    pub git_metadata: bool,
+    /// Fail before uploading anything if the git worktree of the directory has changes not
+    /// committed, including untracked files.
+    #[clap(long, action)]
+    pub require_clean: bool,
+    /// Write the blob ID and the content hash of every resource to this lockfile after the
+    /// update, to review the content of the deploys in version control.
+    ///
+    /// Without a path, the lockfile is `walrus.lock` in the current directory.
+    #[clap(
+        long,
+        value_name = "PATH",
+        num_args = 0..=1,
+        default_missing_value = DEFAULT_LOCKFILE
+    )]
+    pub lockfile: Option<PathBuf>,
+    /// Fail before uploading anything if the resources differ from the ones in the lockfile.
+    #[clap(long, action, requires = "lockfile")]
+    pub frozen: bool,
+    /// Allow the update to change or remove the resources listed as `immutable` in
+    /// ws-resources.json, instead of failing.
+    #[clap(long, action)]
+    pub allow_immutable_change: bool,
+    /// Fail if the update would remove more than this percentage of the resources of the site,
+    /// e.g., because the wrong or an empty directory is published.
+    #[clap(
+        long,
+        value_name = "PERCENT",
+        default_value_t = DEFAULT_MAX_DELETION_PERCENT,
+        value_parser = clap::value_parser!(u8).range(0..=100)
+    )]
+    pub max_deletion_percent: u8,
+    /// Allow the update to remove more than `--max-deletion-percent` of the resources.
+    ///
+    /// Also allowed with `--yes`.
+    #[clap(long, action)]
+    pub confirm_large_deletion: bool,
+    /// Fail before uploading anything if the blobs to store total more than this many bytes.
+    ///
+    /// Only the blobs actually stored count: the resources with the same content are counted
+    /// once, and the blobs already stored on Walrus are not counted.
+    #[clap(long, value_name = "BYTES")]
+    pub max_total_size: Option<u64>,
+    /// Inject a `<base href>` with this URL in the HTML pages with a `<head>`, e.g., `/app/`, so
+    /// that their relative references resolve where the site is deployed.
+    ///
+    /// The pages that already have a `<base>` element are left unchanged.
+    #[clap(long, value_name = "URL")]
+    pub base_href: Option<String>,
+    /// Replace the `<base>` elements already in the pages with the one set by `--base-href`.
+    #[clap(long, action, requires = "base_href")]
+    pub force_base_href: bool,
+}
+
+impl PublishOptions {
+    /// Returns the `<base>` element to inject in the HTML pages, if any.
+    fn base_href_element(&self) -> Result<Option<BaseHref>> {
+        let Some(href) = self.base_href.clone() else {
+            return Ok(None);
+        };
+        ensure!(!href.is_empty(), "the URL of `--base-href` cannot be empty");
+        if !href.ends_with('/') {
+            display::check_warning(format!(
+                "the base href {href} does not end with `/`, so the relative references resolve \
+                against its parent"
+            ))?;
+        }
+        Ok(Some(BaseHref {
+            href,
+            force: self.force_base_href,
+        }))
+    }
+
+    /// Returns the options to read the resources, with the given selection and proxy.
+    fn resource_options(
+        &self,
+        only: Option<ResourceSelection>,
+        proxy: Option<String>,
+    ) -> Result<ResourceOptions> {
+        Ok(ResourceOptions {
+            include_dotfiles: self.include_dotfiles,
+            only,
+            emit_etags: self.emit_etags,
+            symlinks: self.symlinks,
+            content_types: self.content_types.clone(),
+            cache_policy: self.cache_policy,
+            wasm_app: self.wasm_app,
+            allowed_types: self.allow_types.clone(),
+            csp: self.csp_generator(),
+            allow_reserved_paths: self.allow_reserved_paths,
+            skip_unreadable: self.skip_unreadable,
+            proxy,
+            no_auto_headers: self.no_auto_headers,
+            // The header names are stored in lowercase.
+            excluded_headers: self
+                .excluded_headers
+                .iter()
+                .map(|name| name.to_lowercase())
+                .collect(),
+            base_href: self.base_href_element()?,
+            offline: false,
+        })
+    }
+
+    /// Returns the options to update the site.
+    ///
+    /// The selection of the resources and the paths declared in ws-resources.json are left empty,
+    /// to be set by the caller.
+    fn update_options(&self) -> UpdateOptions {
+        UpdateOptions {
+            extend_to: self.extend_to,
+            only: None,
+            chunk_strategy: self.chunk_strategy,
+            max_commands_per_tx: self.max_commands_per_tx.map(NonZeroUsize::get),
+            max_objects_per_tx: self.max_objects_per_tx.map(NonZeroUsize::get),
+            prune_removed_blobs: self.prune_removed_blobs,
+            upload_retries: self.upload_retries,
+            prepare_gas: self.prepare_gas,
+            snapshot_dir: self.snapshot.then(|| self.snapshot_dir.clone()),
+            entry_point: Some(self.entry_point.clone()),
+            order: vec![],
+            immutable: BTreeSet::new(),
+            allow_immutable_change: self.allow_immutable_change,
+            max_deletion_percent: self.max_deletion_percent,
+            confirm_large_deletion: self.confirm_large_deletion,
+            max_total_size: self.max_total_size,
+        }
+    }
+
+    /// Returns the generator of the CSP header selected by the options, if any.
+    fn csp_generator(&self) -> Option<CspGenerator> {
+        (self.csp || self.csp_report_only || !self.csp_sources.is_empty()).then(|| CspGenerator {
+            report_only: self.csp_report_only,
+            extra_sources: self.csp_sources.clone(),
+        })
+    }
 }
 
 /// The continuous editing options.
@@ -86,6 +580,9 @@ pub(crate) enum WhenWalrusUpload {
     Always,
     /// Only update modified
     Modified,
+    /// Update the modified blobs, and the blobs of the unchanged resources that are no longer
+    /// stored on Walrus.
+    Missing,
 }
 
 impl WhenWalrusUpload {
@@ -135,76 +632,700 @@ impl SiteEditor {
         &self.publish_options.directory
     }
 
+    /// All the directories composing the site, with the main directory mounted at the root.
+    pub fn mounts(&self) -> Vec<Mount> {
+        std::iter::once(Mount::root(self.directory().to_owned()))
+            .chain(self.publish_options.mounts.iter().cloned())
+            .collect()
+    }
+
+    /// Lists the files of the site directory as the resource manager reads them.
+    fn file_lister(&self) -> FileLister {
+        FileLister {
+            include_dotfiles: self.publish_options.include_dotfiles,
+            symlinks: self.publish_options.symlinks,
+            skip_unreadable: self.publish_options.skip_unreadable,
+        }
+    }
+
+    /// Sets the entry point as the route of the root URL if needed, and checks that it exists.
+    ///
+    /// The route is part of the routes of the site, so changing the entry point updates them.
+    fn with_entry_point(&self, site_data: SiteData) -> Result<SiteData> {
+        let entry_point = self.publish_options.entry_point.as_str();
+        let has_resource = |path: &str| {
+            site_data
+                .resources()
+                .inner
+                .iter()
+                .any(|resource| resource.info.path == path)
+        };
+        if !has_resource(entry_point) {
+            // With `--only`, `--files-from`, or `--tag`, the entry point may be on chain already.
+            if self.publish_options.only.is_some()
+                || self.publish_options.files_from.is_some()
+                || !self.publish_options.tags.is_empty()
+            {
+                return Ok(site_data);
+            }
+            ensure!(
+                entry_point == DEFAULT_ENTRY_POINT,
+                "the entry point {entry_point} is not a resource of the site"
+            );
+            display::check_warning(format!(
+                "the site has no entry point {entry_point}; the root URL will not serve a \
+                landing page"
+            ))?;
+            return Ok(site_data);
+        }
+        let has_root_route = site_data
+            .routes()
+            .is_some_and(|routes| routes.0.contains_key(ROOT_RESOURCE_PATH));
+        if entry_point == ROOT_RESOURCE_PATH || has_root_route {
+            return Ok(site_data);
+        }
+        if has_resource(ROOT_RESOURCE_PATH) {
+            // The resources take precedence over the routes in the portal.
+            display::check_warning(format!(
+                "the site has a {ROOT_RESOURCE_PATH} resource, which the root URL serves instead \
+                of the entry point {entry_point}"
+            ))?;
+            return Ok(site_data);
+        }
+        let mut routes = site_data.routes().cloned().unwrap_or_else(Routes::empty);
+        routes
+            .0
+            .insert(ROOT_RESOURCE_PATH.to_owned(), entry_point.to_owned());
+        Ok(SiteData::new(site_data.resources().clone(), Some(routes)))
+    }
+
+    /// Fails if the resources differ from the lockfile, with `--frozen`.
+    ///
+    /// With a selection, only the selected resources are published, so the other resources of
+    /// the lockfile are not compared.
+    fn check_frozen(
+        &self,
+        site_data: &SiteData,
+        selection: Option<&ResourceSelection>,
+    ) -> Result<()> {
+        let (true, Some(path)) = (
+            self.publish_options.frozen,
+            self.publish_options.lockfile.as_ref(),
+        ) else {
+            return Ok(());
+        };
+        let (local, lock) = (SiteLock::from(site_data), SiteLock::read(path)?);
+        let differences = match selection {
+            Some(selection) => local
+                .selected(selection)
+                .differences(&lock.selected(selection)),
+            None => local.differences(&lock),
+        };
+        ensure!(
+            differences.is_empty(),
+            "the resources differ from the lockfile {} (`--frozen`):\n{}",
+            path.display(),
+            differences
+                .iter()
+                .map(|difference| format!("  - {difference}"))
+                .collect::<Vec<_>>()
+                .join("\n")
+        );
+        Ok(())
+    }
+
+    /// Displays a warning for the resources larger than the configured threshold.
+    fn warn_large_resources(&self, site_data: &SiteData) -> Result<()> {
+        let threshold = self.publish_options.large_resource_threshold;
+        let large_resources = site_data
+            .resources()
+            .inner
+            .iter()
+            .filter(|resource| resource.unencoded_size > threshold)
+            .map(|resource| {
+                format!(
+                    "  - {} ({} bytes)",
+                    resource.info.path, resource.unencoded_size
+                )
+            })
+            .collect::<Vec<_>>();
+        if large_resources.is_empty() {
+            return Ok(());
+        }
+        display::check_warning(format!(
+            "the following resources are larger than {threshold} bytes, and may load slowly when \
+            served by the portal; consider splitting them into smaller files:\n{}",
+            large_resources.join("\n")
+        ))
+    }
+
+    /// Reports the dangling references in the site, if requested.
+    ///
+    /// Fails with `--strict-links`, and only warns with `--check-links`.
+    fn check_links(&self, site_data: &SiteData) -> Result<()> {
+        if !self.publish_options.check_links && !self.publish_options.strict_links {
+            return Ok(());
+        }
+        let dangling = dangling_references(site_data);
+        if dangling.is_empty() {
+            return Ok(());
+        }
+        let message = format!(
+            "found {} references that will not resolve once published:\n{}",
+            dangling.len(),
+            dangling
+                .iter()
+                .map(|dangling| format!(
+                    "  - {}: {} ({})",
+                    dangling.file, dangling.reference, dangling.reason
+                ))
+                .collect::<Vec<_>>()
+                .join("\n")
+        );
+        ensure!(!self.publish_options.strict_links, message);
+        display::check_warning(message)
+    }
+
     /// Run the editing operations requested.
-    pub async fn run(&self) -> Result<()> {
-        match self.continuous_editing {
-            ContinuousEditing::Once => self.run_single_and_print_summary().await?,
-            ContinuousEditing::Watch => self.run_continuous().await?,
+    ///
+    /// Returns the object ID of the site, if it was updated once, i.e., unless the run is a dry
+    /// run, watches the directory, or finds no change since the last publish.
+    pub async fn run(&self) -> Result<Option<ObjectID>> {
+        if let Some(image) = self.publish_options.from_image.as_ref() {
+            image::extract(
+                &self.publish_options.container_cli,
+                image,
+                &self.publish_options.image_path,
+                self.directory(),
+            )?;
+        }
+        if self.publish_options.dry_run {
+            return self.run_dry_run().await.map(|()| None);
+        }
+        let result = match self.continuous_editing {
+            ContinuousEditing::Once => self.run_single_and_print_summary().await,
+            ContinuousEditing::Watch => self.run_continuous().await.map(|()| None),
+        };
+        if let (Err(_), Some(path)) = (&result, &self.publish_options.metrics_file) {
+            let site = match &self.site_id {
+                SiteIdentifier::ExistingSite(site_id) => Some(*site_id),
+                SiteIdentifier::NewSite(_) => None,
+            };
+            if let Err(error) = DeployMetrics::record_failure(path, site) {
+                display::warning(format!("{error:#}"));
+            }
+        }
+        if let (Err(error), Some(webhook)) = (&result, self.webhook()) {
+            if self.publish_options.webhook_on_failure {
+                let payload = WebhookPayload::Failure {
+                    error: format!("{error:#}"),
+                };
+                if let Err(webhook_error) = webhook.notify(&payload).await {
+                    display::warning(webhook_error);
+                }
+            }
+        }
+        result
+    }
+
+    /// Returns the webhook to notify of the result, if any.
+    fn webhook(&self) -> Option<Webhook> {
+        self.publish_options.webhook.as_ref().map(|url| {
+            Webhook::new(
+                url.clone(),
+                Duration::from_secs(self.publish_options.webhook_timeout),
+            )
+            .with_proxy(self.config.general.proxy.clone())
+        })
+    }
+
+    /// Updates the site once.
+    ///
+    /// Returns `None` if the update was skipped because the fingerprint did not change.
+    async fn run_single_edit(&self) -> Result<Option<EditOutcome>> {
+        let git = self.git_metadata()?;
+        // The local files are stored by the update, so they are kept until it completes.
+        let (mut site_manager, local_site_data, _local_files) = self.load_site().await?;
+        let fingerprint = local_site_data.fingerprint()?;
+        if let Some(site_id) = self.unchanged_site(&fingerprint)? {
+            if self.publish_options.print_url_only {
+                println!("{}", self.config.site_url(&site_id)?);
+            } else {
+                println!("No changes since the last publish of site {site_id}, skipping.");
+            }
+            return Ok(None);
+        }
+        let (response, summary) = site_manager.update_site(&local_site_data).await?;
+        if let Some(path) = self.publish_options.lockfile.as_ref() {
+            let published = SiteLock::from(&local_site_data);
+            // With a selection, the resources that were not selected are unchanged on chain.
+            let lock = match site_manager.options.only.as_ref() {
+                Some(selection) if path.exists() => {
+                    SiteLock::read(path)?.with_selected(published, selection)
+                }
+                _ => published,
+            };
+            lock.write(path)?;
+        }
+        Ok(Some(EditOutcome {
+            active_address: site_manager.active_address()?,
+            response,
+            summary,
+            gas_estimated: site_manager.gas_estimated,
+            gas_spent: site_manager.gas_spent,
+            blobs_uploaded: site_manager.blobs_uploaded,
+            prewarm_paths: if self.publish_options.prewarm {
+                prewarm_paths(&local_site_data, &self.publish_options.entry_point)
+            } else {
+                vec![]
+            },
+            fingerprint,
+            resource_root: local_site_data.resource_root(),
+            n_resources: local_site_data.resources().inner.len(),
+            git,
+        }))
+    }
+
+    /// Reads the revision of the directory, if requested, failing if it must be clean and is not.
+    fn git_metadata(&self) -> Result<Option<GitMetadata>> {
+        if !self.publish_options.git_metadata && !self.publish_options.require_clean {
+            return Ok(None);
+        }
+        let git = GitMetadata::read(self.directory())?;
+        tracing::debug!(?git, "read the git revision of the site");
+        if self.publish_options.require_clean {
+            git.ensure_clean()?;
+        }
+        Ok(self.publish_options.git_metadata.then_some(git))
+    }
+
+    /// Returns the ID of the site if it is the one recorded in the fingerprint file, with the
+    /// same fingerprint.
+    fn unchanged_site(&self, fingerprint: &str) -> Result<Option<ObjectID>> {
+        let (Some(path), SiteIdentifier::ExistingSite(site_id)) = (
+            self.publish_options.fingerprint_file.as_ref(),
+            &self.site_id,
+        ) else {
+            return Ok(None);
+        };
+        let Some(recorded) = SiteFingerprint::read(path)? else {
+            return Ok(None);
+        };
+        tracing::debug!(?recorded, fingerprint, "checking the recorded fingerprint");
+        Ok(
+            (recorded.site_object == *site_id && recorded.fingerprint == fingerprint)
+                .then_some(*site_id),
+        )
+    }
+
+    /// Computes the operations needed to update the site, and prints them without executing them.
+    ///
+    /// If a dry-run file is specified, the plan is written to it, to be executed later with the
+    /// `apply` command.
+    async fn run_dry_run(&self) -> Result<()> {
+        let (site_manager, local_site_data, local_files) = self.load_site().await?;
+        let plan = site_manager.plan_update(&local_site_data).await?;
+        display::header("Dry run completed");
+        println!("{}", plan.to_summary());
+        if let Some(path) = self.publish_options.dry_run_file.as_ref() {
+            plan.write(path)?;
+            println!("Plan written to: {}", path.display());
+            // The plan refers to the local files, which are stored when it is applied.
+            let local_files = local_files.into_path();
+            tracing::debug!(?local_files, "keeping the local files of the plan");
         }
         Ok(())
     }
 
-    async fn run_single_edit(
+    /// Returns the resources to publish, if only some of them are selected.
+    fn resource_selection(
         &self,
-    ) -> Result<(SuiAddress, SuiTransactionBlockResponse, SiteDataDiffSummary)> {
+        ws_resources: &Option<WSResources>,
+    ) -> Result<Option<ResourceSelection>> {
+        if let Some(pattern) = self.publish_options.only.as_ref() {
+            return Ok(Some(ResourceSelection::Glob(pattern.clone())));
+        }
+        if !self.publish_options.tags.is_empty() {
+            let defined = ws_resources
+                .as_ref()
+                .and_then(|config| config.tags.as_ref());
+            let mut patterns = vec![];
+            for tag in self.publish_options.tags.iter() {
+                let tag_patterns = defined.and_then(|tags| tags.get(tag)).ok_or_else(|| {
+                    anyhow!(
+                        "the tag {tag} is not defined in {DEFAULT_WS_RESOURCES_FILE}; the tags \
+                        defined are: {}",
+                        defined
+                            .map(|tags| tags.keys().cloned().collect::<Vec<_>>().join(", "))
+                            .filter(|tags| !tags.is_empty())
+                            .unwrap_or_else(|| "(none)".to_owned())
+                    )
+                })?;
+                patterns.extend(tag_patterns.iter().cloned());
+            }
+            return Ok(Some(ResourceSelection::Globs(patterns)));
+        }
+        let Some(list) = self.publish_options.files_from.as_ref() else {
+            return Ok(None);
+        };
+        let text = if list.as_os_str() == "-" {
+            std::io::read_to_string(std::io::stdin())
+                .context("could not read the files from stdin")?
+        } else {
+            std::fs::read_to_string(list).context(format!(
+                "could not read the list of files {}",
+                list.display()
+            ))?
+        };
+        let mounts = self.mounts();
+        let paths = text
+            .lines()
+            .map(str::trim)
+            .filter(|file| !file.is_empty())
+            .map(|file| {
+                ensure!(
+                    !Path::new(file)
+                        .components()
+                        .any(|component| component == Component::ParentDir),
+                    "the listed file {file} must not contain `..` components"
+                );
+                let full_path = self.directory().join(file);
+                ensure!(
+                    full_path.is_file(),
+                    "the listed file {file} does not exist in {}",
+                    self.directory().display()
+                );
+                // The file belongs to the most nested mount that contains it.
+                let mount = mounts
+                    .iter()
+                    .filter(|mount| full_path.starts_with(&mount.directory))
+                    .max_by_key(|mount| mount.directory.components().count())
+                    .ok_or_else(|| {
+                        anyhow!("the listed file {file} is outside the site directory")
+                    })?;
+                mount.resource_path(&full_path)
+            })
+            .collect::<Result<BTreeSet<_>>>()?;
+        if !self.publish_options.print_url_only {
+            println!("Publishing only the {} listed files", paths.len());
+        }
+        Ok(Some(ResourceSelection::Paths(paths)))
+    }
+
+    /// Reads the local site, and creates the site manager to update it.
+    ///
+    /// Also returns the directory of the local files written while reading the site, e.g., the
+    /// downloaded remote resources, which is removed when dropped.
+    async fn load_site(&self) -> Result<(SiteManager, SiteData, TempDir)> {
+        let mut wallet = self.config.load_wallet().await?;
+        ensure_sui_balance(&mut wallet).await?;
+        let walrus = load_walrus(&self.config);
+        let local_site = self.read_site(walrus.clone(), false).await?;
+
+        let site_manager = SiteManager::new(
+            self.config.clone(),
+            walrus,
+            wallet,
+            self.site_id.clone(),
+            self.publish_options.epochs,
+            self.when_upload.clone(),
+        )
+        .await?
+        .with_options(UpdateOptions {
+            only: local_site.selection,
+            order: local_site.order,
+            immutable: local_site.immutable,
+            ..self.publish_options.update_options()
+        });
+        Ok((site_manager, local_site.data, local_site.local_files))
+    }
+
+    /// Reads and checks the resources of the local site, as they are published.
+    ///
+    /// If `offline` is set, the Walrus CLI is not used: the blob IDs are not computed, and the
+    /// remote resources are skipped.
+    async fn read_site(&self, walrus: Walrus, offline: bool) -> Result<LocalSite> {
         if self.publish_options.list_directory {
             display::action(format!("Preprocessing: {}", self.directory().display()));
             Preprocessor::preprocess(self.directory())?;
             display::done();
         }
 
-        let wallet = load_wallet_context(&self.config.general.wallet)?;
-
-        let walrus = Walrus::new(
-            self.config.walrus_binary(),
-            self.config.gas_budget(),
-            self.config.general.rpc_url.clone(),
-            self.config.general.walrus_config.clone(),
-            self.config.general.wallet.clone(),
-        );
-
         let (ws_resources, ws_resources_path) =
             load_ws_resources(&self.publish_options.ws_resources, self.directory())?;
-        if let Some(path) = ws_resources_path.as_ref() {
+        if let Some(path) = ws_resources_path
+            .as_ref()
+            .filter(|_| !self.publish_options.print_url_only)
+        {
             println!(
                 "Using the Walrus sites resources file: {}",
                 path.to_string_lossy()
             );
         }
+        let (ws_resources, nested_ws_resources_paths) = if self.publish_options.nested_ws_resources
+        {
+            load_nested_ws_resources(
+                ws_resources,
+                &ws_resources_path,
+                self.directory(),
+                self.file_lister(),
+            )?
+        } else {
+            (ws_resources, vec![])
+        };
+        for path in nested_ws_resources_paths
+            .iter()
+            .filter(|_| !self.publish_options.print_url_only)
+        {
+            println!("Using the nested resources file: {}", path.display());
+        }
+        let selection = self.resource_selection(&ws_resources)?;
+        let required = ws_resources
+            .as_ref()
+            .and_then(|config| config.required.clone())
+            .unwrap_or_default();
+        let immutable = ws_resources
+            .as_ref()
+            .and_then(|config| config.immutable.clone())
+            .unwrap_or_default();
+        let order = ws_resources
+            .as_ref()
+            .and_then(|config| config.order.clone())
+            .unwrap_or_default();
+        let remote_paths = ws_resources
+            .as_ref()
+            .and_then(|config| config.remote_resources.as_ref())
+            .map(|remote_resources| remote_resources.keys().cloned().collect())
+            .unwrap_or_default();
 
-        let mut resource_manager =
-            ResourceManager::new(walrus.clone(), ws_resources, ws_resources_path).await?;
-        display::action(format!(
-            "Parsing the directory {} and locally computing blob IDs",
-            self.directory().to_string_lossy()
-        ));
-        let local_site_data = resource_manager.read_dir(self.directory()).await?;
-        display::done();
-        tracing::debug!(?local_site_data, "resources loaded from directory");
-
-        let mut site_manager = SiteManager::new(
-            self.config.clone(),
+        let mut resource_manager = ResourceManager::new(
             walrus,
-            wallet,
-            self.site_id.clone(),
-            self.publish_options.epochs,
-            self.when_upload.clone(),
+            ws_resources,
+            ws_resources_path,
+            nested_ws_resources_paths,
+            ResourceOptions {
+                offline,
+                ..self
+                    .publish_options
+                    .resource_options(selection.clone(), self.config.general.proxy.clone())?
+            },
         )
         .await?;
-        let (response, summary) = site_manager.update_site(&local_site_data).await?;
-        Ok((site_manager.active_address()?, response, summary))
+        display::action(if offline {
+            format!(
+                "Parsing the directory {}",
+                self.directory().to_string_lossy()
+            )
+        } else {
+            format!(
+                "Parsing the directory {} and locally computing blob IDs",
+                self.directory().to_string_lossy()
+            )
+        });
+        let local_site_data = resource_manager.read_mounts(&self.mounts()).await?;
+        let local_files = resource_manager.into_local_files();
+        display::done();
+        tracing::debug!(?local_site_data, "resources loaded from directory");
+        check_listed_files(&selection, &local_site_data)?;
+        ensure!(
+            self.publish_options.allow_empty || !local_site_data.resources().inner.is_empty(),
+            "no files found under {}; did the build succeed? Use `--allow-empty` to publish \
+            anyway",
+            self.directory().display()
+        );
+        self.warn_large_resources(&local_site_data)?;
+        let local_site_data = self.with_entry_point(local_site_data)?;
+        let local_site_data = self.publish_options.directory_index.apply(local_site_data);
+        self.check_links(&local_site_data)?;
+        self.check_frozen(&local_site_data, selection.as_ref())?;
+        // With `--only`, `--files-from`, or `--tag`, the other resources are already on chain.
+        if selection.is_none() {
+            check_required(&local_site_data, &required)?;
+        }
+        Ok(LocalSite {
+            data: local_site_data,
+            local_files,
+            selection,
+            order,
+            immutable,
+            remote_paths,
+        })
     }
 
-    async fn run_single_and_print_summary(&self) -> Result<()> {
-        let (active_address, response, summary) = self.run_single_edit().await?;
-        print_summary(
+    /// Compares the resources of the local site with the lockfile, without any network access.
+    ///
+    /// The resources are read as they would be published, and only their contents are compared.
+    /// The remote resources cannot be downloaded offline, and the resources outside of the
+    /// selection are not read, so neither is compared. Returns the number of resources that
+    /// differ.
+    pub async fn plan_from_lockfile(&self, lockfile: &Path) -> Result<usize> {
+        let lock = SiteLock::read(lockfile)?;
+        let local_site = self.read_site(load_walrus(&self.config), true).await?;
+        let local = local_site
+            .data
+            .resources()
+            .inner
+            .iter()
+            .map(|resource| {
+                (
+                    resource.info.path.clone(),
+                    hash_to_hex(&resource.info.blob_hash),
+                )
+            })
+            .collect::<BTreeMap<_, _>>();
+        let mut ignored = lock
+            .paths()
+            .filter(|path| {
+                local_site
+                    .selection
+                    .as_ref()
+                    .is_some_and(|selection| !selection.matches(path))
+            })
+            .cloned()
+            .collect::<BTreeSet<_>>();
+
+        println!(
+            "Comparing the site with the lockfile {}",
+            lockfile.display()
+        );
+        if !local_site.remote_paths.is_empty() {
+            display::warning(format!(
+                "the {} remote resources cannot be compared offline",
+                local_site.remote_paths.len()
+            ));
+        }
+        ignored.extend(local_site.remote_paths);
+        let differences = lock.content_differences(&local, &ignored);
+        for difference in differences.iter() {
+            println!("  - {difference}");
+        }
+        if differences.is_empty() {
+            println!("The site matches the lockfile ({} resources)", local.len());
+        } else {
+            println!("{} resources differ from the lockfile", differences.len());
+        }
+        Ok(differences.len())
+    }
+
+    async fn run_single_and_print_summary(&self) -> Result<Option<ObjectID>> {
+        let started = Instant::now();
+        let Some(outcome) = self.run_single_edit().await? else {
+            return Ok(None);
+        };
+        let site_object = print_summary(
             &self.config,
-            &active_address,
+            &outcome.active_address,
             &self.site_id,
-            &response,
-            &summary,
+            &outcome.response,
+            &outcome.summary,
+            outcome.gas_estimated,
+            self.publish_options.print_url_only,
         )?;
-        Ok(())
+        if !self.publish_options.print_url_only {
+            println!("Resource root: {}", outcome.resource_root);
+            if let Some(git) = &outcome.git {
+                println!("Git commit: {git}");
+            }
+        }
+        if let Some(path) = self.publish_options.fingerprint_file.as_ref() {
+            SiteFingerprint {
+                site_object,
+                fingerprint: outcome.fingerprint.clone(),
+            }
+            .write(path)?;
+        }
+        if let Some(webhook) = self.webhook() {
+            let base36 = id_to_base36(&site_object)?;
+            let payload = WebhookPayload::Success {
+                site_object,
+                url: self.config.site_url(&site_object)?,
+                base36,
+                digest: outcome
+                    .response
+                    .effects
+                    .is_some()
+                    .then(|| outcome.response.digest.to_string()),
+                gas_used: outcome.gas_spent,
+                resource_count: outcome.n_resources,
+                resource_root: outcome.resource_root.clone(),
+                git: outcome.git.clone(),
+            };
+            // The site was published, so failing to notify the webhook is not an error.
+            if let Err(error) = webhook.notify(&payload).await {
+                display::warning(error);
+            }
+        }
+        if !outcome.prewarm_paths.is_empty() {
+            self.prewarm(&site_object, outcome.prewarm_paths.clone())
+                .await;
+        }
+        if let Some(path) = self.publish_options.metrics_file.as_ref() {
+            let metrics = DeployMetrics {
+                site: Some(site_object),
+                duration: started.elapsed(),
+                gas_mist: outcome.gas_spent,
+                resources: outcome.n_resources,
+                blobs_uploaded: outcome.blobs_uploaded,
+                failures: 0,
+            };
+            if let Err(error) = DeployMetrics::record_success(path, metrics) {
+                display::warning(format!("{error:#}"));
+            }
+        }
+        Ok(Some(site_object))
+    }
+
+    /// Requests the paths through the portal, to populate its cache.
+    ///
+    /// The site is already published, so the failures only result in warnings.
+    async fn prewarm(&self, site_object: &ObjectID, paths: Vec<String>) {
+        let n_paths = paths.len();
+        let site_url = match self.config.site_url(site_object) {
+            Ok(site_url) => site_url,
+            Err(error) => return display::warning(format!("could not warm the cache: {error:#}")),
+        };
+        display::action(format!(
+            "Warming the cache of the portal with {n_paths} resources"
+        ));
+        let results = match probe::probe_paths(
+            paths,
+            &site_url,
+            PREWARM_CONCURRENCY,
+            PREWARM_TIMEOUT,
+            self.config.general.proxy.as_deref(),
+        )
+        .await
+        {
+            Ok(results) => results,
+            Err(error) => {
+                display::failed();
+                return display::warning(format!("could not warm the cache: {error:#}"));
+            }
+        };
+        display::done();
+        let failed = results
+            .iter()
+            .filter(|result| !result.is_reachable())
+            .map(|result| {
+                let reason = result.error.clone().unwrap_or_else(|| {
+                    result.status.map_or_else(
+                        || "no response".to_owned(),
+                        |status| format!("HTTP {status}"),
+                    )
+                });
+                format!("  - {} ({reason})", result.path)
+            })
+            .collect::<Vec<_>>();
+        if !self.publish_options.print_url_only {
+            println!("Warmed {} of {n_paths} resources", n_paths - failed.len());
+        }
+        if !failed.is_empty() {
+            display::warning(format!(
+                "could not warm {} resources:\n{}",
+                failed.len(),
+                failed.join("\n")
+            ));
+        }
     }
 
     async fn run_continuous(&self) -> Result<()> {
@@ -215,7 +1336,9 @@ impl SiteEditor {
 
         // Add a path to be watched. All files and directories at that path and
         // below will be monitored for changes.
-        watcher.watch(self.directory(), RecursiveMode::Recursive)?;
+        for mount in self.mounts() {
+            watcher.watch(&mount.directory, RecursiveMode::Recursive)?;
+        }
 
         loop {
             match rx.recv() {
@@ -229,13 +1352,92 @@ impl SiteEditor {
     }
 }
 
+/// Returns the root URL, the entry point, and the resources of the site that the entry point
+/// references, e.g., its scripts and stylesheets.
+fn prewarm_paths(site_data: &SiteData, entry_point: &str) -> Vec<String> {
+    let resources = &site_data.resources().inner;
+    let paths = resources
+        .iter()
+        .map(|resource| resource.info.path.as_str())
+        .collect::<BTreeSet<_>>();
+    let Some(entry) = resources
+        .iter()
+        .find(|resource| resource.info.path == entry_point)
+    else {
+        return vec!["/".to_owned()];
+    };
+    let html = std::fs::read_to_string(&entry.full_path).unwrap_or_default();
+    ["/".to_owned(), entry_point.to_owned()]
+        .into_iter()
+        .chain(
+            page_resources(&html, entry_point, &paths)
+                .into_iter()
+                .filter(|path| path != entry_point),
+        )
+        .collect()
+}
+
+/// Fails if any of the resources required in `ws-resources.json` is missing from the site.
+fn check_required(site_data: &SiteData, required: &BTreeSet<String>) -> Result<()> {
+    let missing = required
+        .iter()
+        .filter(|path| {
+            !site_data
+                .resources()
+                .inner
+                .iter()
+                .any(|resource| resource.info.path == **path)
+        })
+        .map(|path| format!("  - {path}"))
+        .collect::<Vec<_>>();
+    ensure!(
+        missing.is_empty(),
+        "the site is missing {} required resources; did the build succeed?\n{}",
+        missing.len(),
+        missing.join("\n")
+    );
+    Ok(())
+}
+
+/// Fails if any of the files listed with `--files-from` was not read as a resource.
+///
+/// The listed files exist, but the files that are never resources, e.g., the dotfiles, the
+/// symbolic links, or the `ws-resources.json` files, are skipped when reading the directory.
+fn check_listed_files(selection: &Option<ResourceSelection>, site_data: &SiteData) -> Result<()> {
+    let Some(ResourceSelection::Paths(paths)) = selection else {
+        return Ok(());
+    };
+    let read = site_data
+        .resources()
+        .inner
+        .iter()
+        .map(|resource| resource.info.path.as_str())
+        .collect::<BTreeSet<_>>();
+    let skipped = paths
+        .iter()
+        .filter(|path| !read.contains(path.as_str()))
+        .map(|path| format!("  - {path}"))
+        .collect::<Vec<_>>();
+    ensure!(
+        skipped.is_empty(),
+        "{} of the listed files are not resources of the site, e.g., because they are dotfiles \
+        (see `--include-dotfiles`), symbolic links (see `--symlinks`), or ws-resources.json \
+        files:\n{}",
+        skipped.len(),
+        skipped.join("\n")
+    );
+    Ok(())
+}
+
 fn print_summary(
     config: &Config,
     address: &SuiAddress,
     site_id: &SiteIdentifier,
     response: &SuiTransactionBlockResponse,
     summary: &impl Summarizable,
-) -> Result<()> {
+    gas_estimated: Option<u64>,
+    print_url_only: bool,
+) -> Result<ObjectID> {
     if let Some(SuiTransactionBlockEffects::V1(eff)) = response.effects.as_ref() {
         if let SuiExecutionStatus::Failure { error } = &eff.status {
             return Err(anyhow!(
@@ -245,34 +1447,415 @@ fn print_summary(
         }
     }
 
+    let object_id = match site_id {
+        SiteIdentifier::ExistingSite(id) => *id,
+        SiteIdentifier::NewSite(_) => get_site_id_from_response(
+            *address,
+            response
+                .effects
+                .as_ref()
+                .ok_or(anyhow::anyhow!("response did not contain effects"))?,
+        )?,
+    };
+    let subdomain = id_to_base36(&object_id)?;
+    verify_base36(&object_id, &subdomain)?;
+    let url = config.site_url(&object_id)?;
+    if print_url_only {
+        println!("{url}");
+        return Ok(object_id);
+    }
+
     display::header("Execution completed");
+    if let Some(label) = config.general.label.as_ref() {
+        println!("Transaction label: {}", label);
+    }
     println!("{}\n", summary.to_summary());
-    let object_id = match site_id {
-        SiteIdentifier::ExistingSite(id) => {
-            println!("Site object ID: {}", id);
-            *id
-        }
+    match site_id {
+        SiteIdentifier::ExistingSite(id) => println!("Site object ID: {}", id),
         SiteIdentifier::NewSite(name) => {
-            let id = get_site_id_from_response(
-                *address,
-                response
-                    .effects
-                    .as_ref()
-                    .ok_or(anyhow::anyhow!("response did not contain effects"))?,
-            )?;
-            println!("Created new site: {}\nNew site object ID: {}", name, id);
-            id
+            println!(
+                "Created new site: {}\nNew site object ID: {}",
+                name, object_id
+            )
+        }
+    }
+    println!("Browse the resulting site at: {url}");
+
+    if let Some(effects) = response.effects.as_ref() {
+        let gas_report = GasReport::new(config.gas_budget(), effects, gas_estimated);
+        println!("\n{}", gas_report.to_summary());
+        if config.general.json_gas_report {
+            println!("{}", serde_json::to_string(&gas_report)?);
         }
+    }
+    Ok(object_id)
+}
+
+/// Executes a plan computed with `--dry-run --dry-run-file`.
+///
+/// Fails if the site on chain changed since the plan was computed.
+pub(crate) async fn apply_plan(config: Config, path: &Path) -> Result<()> {
+    let plan = SitePlan::read(path)?;
+    let mut wallet = config.load_wallet().await?;
+    ensure_sui_balance(&mut wallet).await?;
+    let walrus = load_walrus(&config);
+    let mut site_manager = SiteManager::new(
+        config.clone(),
+        walrus,
+        wallet,
+        plan.site_id.clone(),
+        plan.epochs,
+        WhenWalrusUpload::from_force_flag(plan.force),
+    )
+    .await?;
+    let (response, summary) = site_manager.apply_plan(&plan).await?;
+    print_summary(
+        &config,
+        &site_manager.active_address()?,
+        &plan.site_id,
+        &response,
+        &summary,
+        site_manager.gas_estimated,
+        false,
+    )?;
+    Ok(())
+}
+
+/// Restores an existing site to its most recent snapshot.
+pub(crate) async fn rollback(config: Config, site_id: ObjectID, snapshot_dir: &Path) -> Result<()> {
+    let snapshot = SiteSnapshot::read_latest(snapshot_dir, site_id)?;
+    let mut wallet = config.load_wallet().await?;
+    ensure_sui_balance(&mut wallet).await?;
+    let walrus = load_walrus(&config);
+    let site_identifier = SiteIdentifier::ExistingSite(site_id);
+    let mut site_manager = SiteManager::new(
+        config.clone(),
+        walrus,
+        wallet,
+        site_identifier.clone(),
+        1,
+        WhenWalrusUpload::Modified,
+    )
+    .await?;
+    tracing::info!(
+        timestamp_ms = snapshot.timestamp_ms,
+        "restoring the most recent snapshot of the site"
+    );
+    let (response, summary) = site_manager
+        .restore_snapshot(&SiteData::from(&snapshot.site))
+        .await?;
+    print_summary(
+        &config,
+        &site_manager.active_address()?,
+        &site_identifier,
+        &response,
+        &summary,
+        site_manager.gas_estimated,
+        false,
+    )?;
+    Ok(())
+}
+
+/// Moves resources of an existing site to new paths, without re-uploading the blobs.
+pub(crate) async fn move_resources(
+    config: Config,
+    site_id: ObjectID,
+    old_path: &str,
+    new_path: &str,
+    glob: bool,
+) -> Result<()> {
+    let mut wallet = config.load_wallet().await?;
+    ensure_sui_balance(&mut wallet).await?;
+    let walrus = load_walrus(&config);
+    let mut site_manager = SiteManager::new(
+        config,
+        walrus,
+        wallet,
+        SiteIdentifier::ExistingSite(site_id),
+        1,
+        WhenWalrusUpload::Modified,
+    )
+    .await?;
+    display::action("Moving the resources on the Walrus Site object on Sui");
+    let (response, moves) = site_manager
+        .move_resources(old_path, new_path, glob)
+        .await?;
+    display::done();
+
+    display::header("Execution completed");
+    for (old, new) in moves.iter() {
+        println!("  - moved resource {old} to {new}");
+    }
+    println!("Transaction digest: {}", response.digest);
+    Ok(())
+}
+
+/// Updates the metadata of an existing site, without touching its resources.
+pub(crate) async fn set_metadata(config: Config, site_id: ObjectID, name: &str) -> Result<()> {
+    let mut wallet = config.load_wallet().await?;
+    ensure_sui_balance(&mut wallet).await?;
+    let walrus = load_walrus(&config);
+    let mut site_manager = SiteManager::new(
+        config,
+        walrus,
+        wallet,
+        SiteIdentifier::ExistingSite(site_id),
+        1,
+        WhenWalrusUpload::Modified,
+    )
+    .await?;
+    display::action("Updating the metadata of the Walrus Site object on Sui");
+    let response = site_manager.set_metadata(name).await?;
+    display::done();
+
+    display::header("Execution completed");
+    println!("  - set the name of the site to: {name}");
+    println!("Transaction digest: {}", response.digest);
+    Ok(())
+}
+
+/// Removes all the resources and routes from the site, resuming a previous destroy if any.
+pub(crate) async fn destroy_site(config: Config, site_id: ObjectID) -> Result<()> {
+    let mut wallet = config.load_wallet().await?;
+    ensure_sui_balance(&mut wallet).await?;
+    let walrus = load_walrus(&config);
+    let mut site_manager = SiteManager::new(
+        config,
+        walrus,
+        wallet,
+        SiteIdentifier::ExistingSite(site_id),
+        1,
+        WhenWalrusUpload::Modified,
+    )
+    .await?;
+    let responses = site_manager.destroy().await?;
+
+    display::header("Execution completed");
+    println!("The site {site_id} is now empty");
+    for response in responses.iter() {
+        println!("Transaction digest: {}", response.digest);
+    }
+    Ok(())
+}
+
+/// The local site, as read by [`SiteEditor::read_site`].
+struct LocalSite {
+    data: SiteData,
+    /// The directory of the local files written while reading the site, which are stored from it.
+    local_files: TempDir,
+    /// The resources selected with `--only`, `--files-from`, or `--tag`, if any.
+    selection: Option<ResourceSelection>,
+    /// The order of the resources declared in ws-resources.json.
+    order: Vec<String>,
+    /// The immutable resources declared in ws-resources.json.
+    immutable: BTreeSet<String>,
+    /// The paths of the remote resources declared in ws-resources.json.
+    remote_paths: BTreeSet<String>,
+}
+
+/// The outcome of a single edit of the site.
+struct EditOutcome {
+    active_address: SuiAddress,
+    response: SuiTransactionBlockResponse,
+    summary: SiteDataDiffSummary,
+    /// The gas estimated with dry runs, if a maximum budget is set.
+    gas_estimated: Option<u64>,
+    /// The gas spent by the transactions executed, in MIST.
+    gas_spent: u64,
+    /// The number of blobs stored on Walrus.
+    blobs_uploaded: usize,
+    /// The paths requested through the portal after the publish, with `--prewarm`.
+    prewarm_paths: Vec<String>,
+    /// The fingerprint of the local site.
+    fingerprint: String,
+    /// The Merkle root of the resources of the local site.
+    resource_root: String,
+    /// The number of resources of the local site.
+    n_resources: usize,
+    /// The git revision of the local site, if requested.
+    git: Option<GitMetadata>,
+}
+
+/// The fingerprint of the last version of a site published, recorded with `--fingerprint-file`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SiteFingerprint {
+    /// The object ID of the site.
+    site_object: ObjectID,
+    /// The fingerprint of the resources and the routes of the site.
+    fingerprint: String,
+}
+
+impl SiteFingerprint {
+    /// Reads the fingerprint file, returning `None` if it does not exist.
+    fn read(path: &Path) -> Result<Option<Self>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        let contents = std::fs::read_to_string(path).context(format!(
+            "unable to read the fingerprint file {}",
+            path.display()
+        ))?;
+        serde_json::from_str(&contents)
+            .context(format!(
+                "unable to parse the fingerprint file {}",
+                path.display()
+            ))
+            .map(Some)
+    }
+
+    /// Writes the fingerprint file.
+    fn write(&self, path: &Path) -> Result<()> {
+        std::fs::write(path, serde_json::to_string_pretty(self)?).context(format!(
+            "unable to write the fingerprint file {}",
+            path.display()
+        ))
+    }
+}
+
+/// Validates the `ws-resources.json` file against the files in the site directory.
+///
+/// All the problems found are printed, and an error is returned if any of them is an error.
+///
+/// With `nested`, the files in the subdirectories are merged into the root file, and validated
+/// together.
+pub(crate) fn validate_ws_resources(
+    directory: &Path,
+    ws_resources: &Option<PathBuf>,
+    nested: bool,
+    file_lister: FileLister,
+) -> Result<()> {
+    let (ws_resources, ws_resources_path) = load_ws_resources(ws_resources, directory)?;
+    let (ws_resources, nested_paths) = if nested {
+        load_nested_ws_resources(ws_resources, &ws_resources_path, directory, file_lister)?
+    } else {
+        (ws_resources, vec![])
+    };
+    let Some(ws_resources) = ws_resources else {
+        bail!(
+            "no {DEFAULT_WS_RESOURCES_FILE} file found in {}",
+            directory.display()
+        );
     };
+    for path in ws_resources_path.iter().chain(nested_paths.iter()) {
+        println!(
+            "Validating the Walrus sites resources file: {}",
+            path.display()
+        );
+    }
+    let root = Mount::root(directory.to_owned());
+    let resource_paths = file_lister
+        .iter_dir(directory)?
+        .into_iter()
+        .filter(|full_path| {
+            ws_resources_path.as_ref() != Some(full_path) && !nested_paths.contains(full_path)
+        })
+        .map(|full_path| root.resource_path(&full_path))
+        .collect::<Result<BTreeSet<_>>>()?;
 
-    println!(
-        "Browse the resulting site at: https://{}.{}",
-        id_to_base36(&object_id)?,
-        config.portal
+    let problems = ws_resources.validate(&resource_paths);
+    for problem in problems.iter() {
+        println!("  - {problem}");
+    }
+    let n_errors = problems.iter().filter(|problem| problem.is_error).count();
+    ensure!(
+        n_errors == 0,
+        "the resources file is invalid: {n_errors} errors found"
     );
+    println!("The resources file is valid ({} warnings)", problems.len());
     Ok(())
 }
 
+/// Compares the resources of the site on chain with the lockfile, to detect the changes made
+/// outside of the lockfile, e.g., an out-of-band deploy or a replaced blob.
+///
+/// Returns the number of resources that drifted from the lockfile.
+pub(crate) async fn verify_lockfile(
+    config: &Config,
+    site_object: ObjectID,
+    lockfile: &Path,
+) -> Result<usize> {
+    let lock = SiteLock::read(lockfile)?;
+    let wallet = config.load_wallet().await?;
+    let site = RemoteSiteFactory::new(&wallet.get_client().await?, config.package())
+        .get_from_chain(site_object)
+        .await?;
+
+    println!(
+        "Comparing the site {site_object} with the lockfile {}",
+        lockfile.display()
+    );
+    let differences = lock.chain_differences(&SiteLock::from(&site));
+    for difference in differences.iter() {
+        println!("  - {difference}");
+    }
+    if differences.is_empty() {
+        println!(
+            "The site on chain matches the lockfile ({} resources)",
+            site.resources().inner.len()
+        );
+    } else {
+        println!(
+            "{} resources on chain drifted from the lockfile",
+            differences.len()
+        );
+    }
+    Ok(differences.len())
+}
+
+/// Creates the controller for the Walrus CLI from the configuration.
+pub(crate) fn load_walrus(config: &Config) -> Walrus {
+    Walrus::new(
+        config.walrus_binary(),
+        config.gas_budget(),
+        config.general.rpc_url.clone(),
+        config.general.walrus_config.clone(),
+        config.general.wallet.clone(),
+    )
+    .with_proxy(config.general.proxy.clone())
+}
+
+/// Finds the `ws-resources.json` files in the subdirectories of the site directory, and merges
+/// them into the configuration of the root file, if any, from the root down.
+///
+/// Returns the merged configuration, and the paths of the nested files. The files are listed
+/// like the resources, so the ones in the skipped directories are ignored.
+fn load_nested_ws_resources(
+    ws_resources: Option<WSResources>,
+    ws_resources_path: &Option<PathBuf>,
+    site_dir: &Path,
+    file_lister: FileLister,
+) -> Result<(Option<WSResources>, Vec<PathBuf>)> {
+    let root = Mount::root(site_dir.to_owned());
+    let mut nested_paths = file_lister
+        .iter_dir(site_dir)?
+        .into_iter()
+        .filter(|path| {
+            path.file_name()
+                .is_some_and(|name| name == DEFAULT_WS_RESOURCES_FILE)
+                && path.parent() != Some(site_dir)
+                && ws_resources_path.as_ref() != Some(path)
+        })
+        .collect::<Vec<_>>();
+    if nested_paths.is_empty() {
+        return Ok((ws_resources, nested_paths));
+    }
+    // The parents are merged before their subdirectories, so that the nearest file wins.
+    nested_paths.sort_by_key(|path| (path.components().count(), path.clone()));
+    let mut merged = ws_resources.unwrap_or_default();
+    for path in nested_paths.iter() {
+        let resource_path = root.resource_path(path)?;
+        let prefix = resource_path
+            .strip_suffix(DEFAULT_WS_RESOURCES_FILE)
+            .and_then(|prefix| prefix.strip_suffix('/'))
+            .expect("the path ends with the name of the file");
+        merged
+            .merge_nested(WSResources::read(path)?, prefix)
+            .context(format!("invalid resources file {}", path.display()))?;
+    }
+    Ok((Some(merged), nested_paths))
+}
+
 /// Gets the configuration from the provided file, or looks in the default directory.
 fn load_ws_resources(
     path: &Option<PathBuf>,
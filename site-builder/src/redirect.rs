@@ -0,0 +1,105 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Sites that only redirect their visitors to another URL.
+//!
+//! The portal has no redirect responses, so a redirect site is a regular site, recognizable by
+//! its content:
+//!
+//! - a single resource, `/index.html`, which redirects to the target URL with a `refresh` meta
+//!   tag, and also with a script that keeps the path, query, and fragment of the request if
+//!   `--keep-path` is set;
+//! - a route from `/*` to `/index.html`, so that every path of the site redirects;
+//! - a `walrus-sites-redirect` meta tag with the target URL, for the tools that need to tell a
+//!   redirect site apart from any other site.
+
+use std::path::Path;
+
+use anyhow::{ensure, Context, Result};
+use reqwest::Url;
+
+/// The name of the meta tag holding the target URL of a redirect site.
+pub const REDIRECT_META_NAME: &str = "walrus-sites-redirect";
+
+/// Writes the sources of the site redirecting to the target URL to the directory.
+pub(crate) fn write_redirect_site(
+    directory: &Path,
+    target_url: &str,
+    keep_path: bool,
+) -> Result<()> {
+    let target_url = Url::parse(target_url).context(format!("invalid URL {target_url}"))?;
+    ensure!(
+        matches!(target_url.scheme(), "http" | "https"),
+        "the target of a redirect must be an HTTP or HTTPS URL, not {target_url}"
+    );
+    std::fs::create_dir_all(directory)?;
+    std::fs::write(
+        directory.join("index.html"),
+        redirect_page(target_url.as_str(), keep_path),
+    )?;
+    std::fs::write(
+        directory.join("ws-resources.json"),
+        serde_json::to_string_pretty(&serde_json::json!({ "routes": { "/*": "/index.html" } }))?,
+    )?;
+    Ok(())
+}
+
+/// Returns the HTML page redirecting to the target URL.
+fn redirect_page(target_url: &str, keep_path: bool) -> String {
+    let attribute = escape_attribute(target_url);
+    let script = if keep_path {
+        // The target is a JSON string, with `</` escaped so that it cannot close the script.
+        let target = serde_json::to_string(target_url.trim_end_matches('/'))
+            .expect("strings are serializable")
+            .replace("</", "<\\/");
+        format!(
+            "\n    <script>location.replace({target} + location.pathname + location.search + \
+            location.hash);</script>"
+        )
+    } else {
+        String::new()
+    };
+    format!(
+        r#"<!DOCTYPE html>
+<html>
+  <head>
+    <meta charset="utf-8">
+    <meta name="{REDIRECT_META_NAME}" content="{attribute}">
+    <meta http-equiv="refresh" content="0; url={attribute}">
+    <link rel="canonical" href="{attribute}">{script}
+    <title>Redirecting</title>
+  </head>
+  <body>
+    <p>This site has moved to <a href="{attribute}">{attribute}</a>.</p>
+  </body>
+</html>
+"#
+    )
+}
+
+/// Escapes the value to be used in a quoted HTML attribute or in text.
+fn escape_attribute(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redirect_page() {
+        let page = redirect_page("https://example.com/?a=1&b=\"2\"", false);
+        assert!(page.contains(
+            r#"<meta http-equiv="refresh" content="0; url=https://example.com/?a=1&amp;b=&quot;2&quot;">"#
+        ));
+        assert!(!page.contains("<script>"));
+
+        let page = redirect_page("https://example.com/</script>/", true);
+        assert!(page.contains(r#"location.replace("https://example.com/<\/script>" + "#));
+    }
+}
@@ -0,0 +1,196 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! A local relay of the requests of the Sui RPC client through a proxy.
+//!
+//! The Sui RPC client connects directly to the full node, and does not support proxies. Instead,
+//! the client is pointed at the relay, which forwards each JSON-RPC request to the full node with
+//! an HTTP client that goes through the proxy, and sends the response back.
+
+use anyhow::{ensure, Context, Result};
+use sui_sdk::wallet_context::WalletContext;
+use tokio::{
+    io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+    net::{TcpListener, TcpStream},
+};
+
+use crate::util::http_client_builder;
+
+/// The headers of the requests that are not forwarded, as they only concern the connection to the
+/// relay.
+const HOP_BY_HOP_HEADERS: [&str; 4] = ["connection", "content-length", "host", "keep-alive"];
+
+/// An HTTP request received by the relay.
+#[derive(Debug, PartialEq, Eq)]
+struct RelayedRequest {
+    /// The headers to forward, with lowercase names.
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+/// Starts relaying the requests received on a local port to the RPC URL, through the proxy, and
+/// returns the URL of the relay.
+///
+/// The relay only listens on the loopback interface, and runs until the end of the process.
+async fn start(rpc_url: &str, proxy: &str) -> Result<String> {
+    let client = http_client_builder(Some(proxy))?.build()?;
+    let listener = TcpListener::bind(("127.0.0.1", 0))
+        .await
+        .context("could not start the relay of the Sui RPC through the proxy")?;
+    let address = listener.local_addr()?;
+    tracing::debug!(%address, rpc_url, "relaying the Sui RPC through the proxy");
+    let rpc_url = rpc_url.to_owned();
+    tokio::spawn(async move {
+        loop {
+            let stream = match listener.accept().await {
+                Ok((stream, _)) => stream,
+                Err(error) => {
+                    tracing::debug!(?error, "could not accept a connection to the relay");
+                    continue;
+                }
+            };
+            let client = client.clone();
+            let rpc_url = rpc_url.clone();
+            tokio::spawn(async move {
+                if let Err(error) = relay_connection(stream, &client, &rpc_url).await {
+                    tracing::warn!(?error, "the relay of the Sui RPC through the proxy failed");
+                }
+            });
+        }
+    });
+    Ok(format!("http://{address}"))
+}
+
+/// Points the active environment of the wallet at a relay of its RPC through the proxy.
+///
+/// Must be called before the first RPC client of the wallet is created.
+pub(crate) async fn relay_wallet_rpc(wallet: &mut WalletContext, proxy: &str) -> Result<()> {
+    let alias = wallet.config.get_active_env()?.alias.clone();
+    let env = wallet
+        .config
+        .envs
+        .iter_mut()
+        .find(|env| env.alias == alias)
+        .expect("the active env is one of the envs");
+    env.rpc = start(&env.rpc, proxy).await?;
+    Ok(())
+}
+
+/// Relays the requests received on the connection, in order, until it is closed.
+async fn relay_connection(
+    stream: TcpStream,
+    client: &reqwest::Client,
+    rpc_url: &str,
+) -> Result<()> {
+    let (reader, mut writer) = stream.into_split();
+    let mut reader = BufReader::new(reader);
+    while let Some(request) = read_request(&mut reader).await? {
+        let mut forwarded = client.post(rpc_url).body(request.body);
+        for (name, value) in request.headers.iter() {
+            forwarded = forwarded.header(name, value);
+        }
+        let response = forwarded
+            .send()
+            .await
+            .context(format!("could not reach {rpc_url} through the proxy"))?;
+        let status = response.status();
+        let mut head = format!(
+            "HTTP/1.1 {} {}\r\n",
+            status.as_u16(),
+            status.canonical_reason().unwrap_or_default()
+        );
+        // The encoding is passed back, as the body is relayed as received.
+        for name in [
+            reqwest::header::CONTENT_TYPE,
+            reqwest::header::CONTENT_ENCODING,
+        ] {
+            if let Some(value) = response.headers().get(&name) {
+                head.push_str(&format!("{name}: {}\r\n", value.to_str()?));
+            }
+        }
+        let body = response.bytes().await?;
+        head.push_str(&format!("content-length: {}\r\n\r\n", body.len()));
+        writer.write_all(head.as_bytes()).await?;
+        writer.write_all(&body).await?;
+        writer.flush().await?;
+    }
+    Ok(())
+}
+
+/// Reads the next request of the connection, or returns `None` if the connection was closed.
+///
+/// The body must have a `content-length`, as the requests of the Sui RPC client do.
+async fn read_request(reader: &mut (impl AsyncBufRead + Unpin)) -> Result<Option<RelayedRequest>> {
+    let mut line = String::new();
+    // The request line is ignored, as all the requests are posted to the RPC URL.
+    if reader.read_line(&mut line).await? == 0 {
+        return Ok(None);
+    }
+    let mut headers = vec![];
+    let mut content_length = 0;
+    loop {
+        line.clear();
+        ensure!(
+            reader.read_line(&mut line).await? > 0,
+            "the connection was closed in the headers of the request"
+        );
+        let header = line.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        let Some((name, value)) = header.split_once(':') else {
+            continue;
+        };
+        let name = name.trim().to_ascii_lowercase();
+        let value = value.trim().to_owned();
+        ensure!(
+            name != "transfer-encoding",
+            "the requests with a `transfer-encoding` cannot be relayed"
+        );
+        if name == "content-length" {
+            content_length = value
+                .parse()
+                .context(format!("invalid content length: {value}"))?;
+        }
+        if !HOP_BY_HOP_HEADERS.contains(&name.as_str()) {
+            headers.push((name, value));
+        }
+    }
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body).await?;
+    Ok(Some(RelayedRequest { headers, body }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_read_request() {
+        let body = r#"{"jsonrpc":"2.0","id":1,"method":"sui_getChainIdentifier"}"#;
+        let connection = format!(
+            "POST / HTTP/1.1\r\nHost: 127.0.0.1:9000\r\nContent-Type: application/json\r\n\
+            Content-Length: {}\r\n\r\n{body}POST / HTTP/1.1\r\ncontent-length: 2\r\n\r\n{{}}",
+            body.len()
+        );
+        let mut reader = connection.as_bytes();
+        assert_eq!(
+            read_request(&mut reader).await.unwrap(),
+            Some(RelayedRequest {
+                headers: vec![("content-type".to_owned(), "application/json".to_owned())],
+                body: body.as_bytes().to_vec(),
+            })
+        );
+        assert_eq!(
+            read_request(&mut reader).await.unwrap(),
+            Some(RelayedRequest {
+                headers: vec![],
+                body: b"{}".to_vec(),
+            })
+        );
+        assert_eq!(read_request(&mut reader).await.unwrap(), None);
+
+        let mut chunked = "POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n".as_bytes();
+        assert!(read_request(&mut chunked).await.is_err());
+    }
+}
@@ -0,0 +1,298 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Local serving of a published site, mirroring the behavior of the portal.
+
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use futures::{stream, StreamExt, TryStreamExt};
+use tokio::{
+    fs::File,
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+};
+
+use crate::{
+    display,
+    site::{resource::check_file_hash, SiteData},
+    types::{HttpHeaders, Routes},
+    walrus::Walrus,
+};
+
+/// The maximum size of the request head that is read.
+const MAX_REQUEST_HEAD: usize = 8 * 1024;
+
+/// The path of the page served when no resource matches the request.
+const NOT_FOUND_PAGE: &str = "/404.html";
+
+/// A resource downloaded from Walrus, ready to be served.
+#[derive(Debug, Clone)]
+struct ServedResource {
+    headers: HttpHeaders,
+    /// The file to which the content was downloaded.
+    path: PathBuf,
+}
+
+/// A site downloaded from Walrus, served as the portal would serve it.
+#[derive(Debug, Clone)]
+pub(crate) struct LocalSite {
+    resources: BTreeMap<String, ServedResource>,
+    routes: Option<Routes>,
+}
+
+impl LocalSite {
+    /// Downloads the contents of all the resources of the site from Walrus to files in the
+    /// directory, with at most `concurrency` blobs read at the same time.
+    ///
+    /// Each blob is written to its file as soon as it is read, and the files are only read again
+    /// when served, so that the site is never held in memory. As the portal does, the contents are
+    /// checked against the hashes stored on chain. If `lenient` is set, a mismatch is only
+    /// reported with a warning.
+    pub async fn download(
+        site: &SiteData,
+        walrus: &Walrus,
+        lenient: bool,
+        directory: &Path,
+        concurrency: usize,
+    ) -> Result<Self> {
+        std::fs::create_dir_all(directory)
+            .with_context(|| format!("could not create {}", directory.display()))?;
+        let n_resources = site.resources().inner.len();
+        display::action(format!(
+            "Downloading {n_resources} resources to {}",
+            directory.display()
+        ));
+        let resources = stream::iter(site.resources().inner.iter().enumerate())
+            .map(|(index, resource)| async move {
+                // The paths of the resources are not used as file names, as they may not be valid
+                // on the local file system.
+                let path = directory.join(index.to_string());
+                walrus
+                    .read_to_file(resource.info.blob_id, path.clone())
+                    .await
+                    .with_context(|| format!("could not download {}", resource.info.path))?;
+                if let Err(error) = check_file_hash(&resource.info, &path) {
+                    if !lenient {
+                        return Err(error);
+                    }
+                    display::warning(error);
+                }
+                tracing::info!(
+                    resource = resource.info.path,
+                    ?path,
+                    "downloaded the resource"
+                );
+                Ok((
+                    resource.info.path.clone(),
+                    ServedResource {
+                        headers: resource.info.headers.clone(),
+                        path,
+                    },
+                ))
+            })
+            .buffer_unordered(concurrency.max(1))
+            .try_collect::<BTreeMap<_, _>>()
+            .await?;
+        display::done();
+        Ok(LocalSite {
+            resources,
+            routes: site.routes().cloned(),
+        })
+    }
+
+    /// Returns the HTTP status and the resource served for the path, if any.
+    ///
+    /// The path is looked up first among the resources, then among the routes, and finally the
+    /// `/404.html` page is served, with the status `404 Not Found` as in the portal.
+    fn resolve(&self, path: &str) -> Option<(&'static str, &ServedResource)> {
+        let path = normalize_path(path);
+        self.resources
+            .get(&path)
+            .or_else(|| {
+                self.routes
+                    .as_ref()
+                    .and_then(|routes| match_route(&path, routes))
+                    .and_then(|target| self.resources.get(target))
+            })
+            .map(|resource| ("200 OK", resource))
+            .or_else(|| {
+                self.resources
+                    .get(NOT_FOUND_PAGE)
+                    .map(|resource| ("404 Not Found", resource))
+            })
+    }
+
+    /// Writes the HTTP response for a request of the path to the stream.
+    ///
+    /// The content of the resource is copied from its file to the stream, without reading it
+    /// whole.
+    async fn respond(&self, path: &str, stream: &mut TcpStream) -> Result<()> {
+        let Some((status, resource)) = self.resolve(path) else {
+            let body = format!("Resource at {path} not found!");
+            let response = format!(
+                "HTTP/1.1 404 Not Found\r\ncontent-type: text/plain\r\ncontent-length: {}\r\n\
+                connection: close\r\n\r\n{body}",
+                body.len()
+            );
+            stream.write_all(response.as_bytes()).await?;
+            return Ok(());
+        };
+        let mut file = File::open(&resource.path).await?;
+        let mut head = format!("HTTP/1.1 {status}\r\n");
+        for (name, value) in resource.headers.0.iter() {
+            if name != "content-length" {
+                head.push_str(&format!("{name}: {value}\r\n"));
+            }
+        }
+        head.push_str(&format!(
+            "content-length: {}\r\nconnection: close\r\n\r\n",
+            file.metadata().await?.len()
+        ));
+        stream.write_all(head.as_bytes()).await?;
+        tokio::io::copy(&mut file, stream).await?;
+        Ok(())
+    }
+
+    /// Serves the site on the given port of the local host, until interrupted with Ctrl-C.
+    pub async fn serve(&self, port: u16) -> Result<()> {
+        let listener = TcpListener::bind(("127.0.0.1", port))
+            .await
+            .context(format!("could not listen on port {port}"))?;
+        println!("Serving the site at: http://localhost:{port}");
+        loop {
+            // The server stops on Ctrl-C, so that the caller can remove the downloaded files.
+            let (stream, _address) = tokio::select! {
+                accepted = listener.accept() => accepted?,
+                _ = tokio::signal::ctrl_c() => return Ok(()),
+            };
+            if let Err(error) = self.handle(stream).await {
+                tracing::warn!(?error, "error while handling a request");
+            }
+        }
+    }
+
+    async fn handle(&self, mut stream: TcpStream) -> Result<()> {
+        let mut request = vec![0; MAX_REQUEST_HEAD];
+        let n_read = stream.read(&mut request).await?;
+        let request = String::from_utf8_lossy(&request[..n_read]);
+        let path = request
+            .lines()
+            .next()
+            .and_then(|line| line.split_whitespace().nth(1))
+            .unwrap_or("/");
+        tracing::info!(path, "serving request");
+        self.respond(path, &mut stream).await
+    }
+}
+
+/// Normalizes the request path as the portal does.
+///
+/// The query and the fragment are removed, `/` is served as `/index.html`, and the trailing
+/// slash is dropped.
+fn normalize_path(path: &str) -> String {
+    let path = path.split(['?', '#']).next().unwrap_or_default();
+    if path.is_empty() || path == "/" {
+        return "/index.html".to_owned();
+    }
+    path.strip_suffix('/').unwrap_or(path).to_owned()
+}
+
+/// Returns the target of the longest route pattern matching the path.
+///
+/// As in the portal, the first `*` of a pattern matches any sequence of characters.
+fn match_route<'a>(path: &str, routes: &'a Routes) -> Option<&'a str> {
+    routes
+        .0
+        .iter()
+        .filter(|(pattern, _)| match pattern.split_once('*') {
+            Some((prefix, suffix)) => {
+                path.len() >= prefix.len() + suffix.len()
+                    && path.starts_with(prefix)
+                    && path.ends_with(suffix)
+            }
+            None => path == pattern.as_str(),
+        })
+        .max_by_key(|(pattern, _)| pattern.len())
+        .map(|(_, target)| target.as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn local_site(paths: &[&str], routes: &[(&str, &str)]) -> LocalSite {
+        LocalSite {
+            resources: paths
+                .iter()
+                .map(|path| {
+                    (
+                        path.to_string(),
+                        ServedResource {
+                            headers: HttpHeaders(BTreeMap::new()),
+                            path: PathBuf::from(path),
+                        },
+                    )
+                })
+                .collect(),
+            routes: Some(Routes(
+                routes
+                    .iter()
+                    .map(|(pattern, target)| (pattern.to_string(), target.to_string()))
+                    .collect(),
+            )),
+        }
+    }
+
+    #[test]
+    fn test_normalize_path() {
+        assert_eq!(normalize_path("/"), "/index.html");
+        assert_eq!(normalize_path("/?query=1"), "/index.html");
+        assert_eq!(normalize_path("/docs/"), "/docs");
+        assert_eq!(normalize_path("/style.css#top"), "/style.css");
+    }
+
+    #[test]
+    fn test_resolve() {
+        let site = local_site(
+            &["/index.html", "/app.html", "/docs.html", "/404.html"],
+            &[("/*", "/app.html"), ("/docs/*", "/docs.html")],
+        );
+        let resolved = |path: &str| {
+            site.resolve(path)
+                .map(|(status, resource)| (status, resource.path.clone()))
+        };
+        assert_eq!(
+            resolved("/"),
+            Some(("200 OK", PathBuf::from("/index.html")))
+        );
+        assert_eq!(
+            resolved("/settings"),
+            Some(("200 OK", PathBuf::from("/app.html")))
+        );
+        assert_eq!(
+            resolved("/docs/intro"),
+            Some(("200 OK", PathBuf::from("/docs.html")))
+        );
+
+        let site = local_site(&["/index.html", "/404.html"], &[]);
+        let resolved = |path: &str| {
+            site.resolve(path)
+                .map(|(status, resource)| (status, resource.path.clone()))
+        };
+        assert_eq!(
+            resolved("/missing"),
+            Some(("404 Not Found", PathBuf::from("/404.html")))
+        );
+        assert_eq!(
+            resolved("/404.html"),
+            Some(("200 OK", PathBuf::from("/404.html")))
+        );
+        assert!(local_site(&["/index.html"], &[])
+            .resolve("/missing")
+            .is_none());
+    }
+}
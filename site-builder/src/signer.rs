@@ -0,0 +1,179 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Signing of transactions with an external command, or with a key from the OS secret store.
+//!
+//! The protocol between the site builder and the command is the following:
+//!
+//! 1. The command is run once per transaction, without a shell; the command line is split on
+//!    whitespace into the program and its arguments.
+//! 2. The site builder writes to the standard input of the command a single line, containing the
+//!    base64 encoding of the BCS-serialized intent message, i.e., the intent followed by the
+//!    transaction data. These are the bytes that must be hashed (Blake2b-256) and signed.
+//! 3. The command writes to its standard output the base64 encoding of the serialized Sui
+//!    signature, i.e., the flag of the signature scheme followed by the signature and the public
+//!    key, as produced by `sui keytool sign`. Leading and trailing whitespace is ignored.
+//! 4. The command exits with status 0 on success. Any other status is reported as an error,
+//!    together with the standard error of the command.
+//!
+//! The key in the OS secret store is the private key exported with `sui keytool export`, either
+//! in the Bech32 (`suiprivkey...`) or in the base64 keystore encoding. It is stored as a generic
+//! password in the Keychain on macOS, in the Credential Manager on Windows, and in the Secret
+//! Service (e.g., GNOME Keyring or KWallet) on Linux.
+
+use std::{
+    io::Write,
+    process::{Command, Stdio},
+};
+
+use anyhow::{anyhow, bail, ensure, Context, Result};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use fastcrypto::traits::ToFromBytes;
+use shared_crypto::intent::{Intent, IntentMessage};
+use sui_types::{
+    base_types::SuiAddress,
+    crypto::{EncodeDecodeBase64, Signature, SuiKeyPair, SuiSignature},
+    transaction::TransactionData,
+};
+
+/// Signs transactions by delegating to an external command.
+#[derive(Debug, Clone)]
+pub(crate) struct CommandSigner {
+    command: String,
+}
+
+impl CommandSigner {
+    /// Creates a new signer that runs the given command line.
+    pub fn new(command: String) -> Self {
+        Self { command }
+    }
+
+    /// Signs the transaction with the external command.
+    ///
+    /// Fails if the signature is not for the given signer address.
+    pub fn sign(
+        &self,
+        transaction: &TransactionData,
+        intent: Intent,
+        signer: SuiAddress,
+    ) -> Result<Signature> {
+        let message = bcs::to_bytes(&IntentMessage::new(intent, transaction.clone()))?;
+        let output = self.run(&format!("{}\n", STANDARD.encode(message)))?;
+        let bytes = STANDARD
+            .decode(output.trim())
+            .context("the output of the signer command is not valid base64")?;
+        let signature = Signature::from_bytes(&bytes).map_err(|error| {
+            anyhow!("the signer command returned an invalid signature: {error}")
+        })?;
+        let address = SuiAddress::from(&signature.to_public_key()?);
+        ensure!(
+            address == signer,
+            "the signer command signed for {address}, but the transaction sender is {signer}"
+        );
+        Ok(signature)
+    }
+
+    /// Runs the command with the given input, and returns its standard output.
+    fn run(&self, input: &str) -> Result<String> {
+        let mut parts = self.command.split_whitespace();
+        let program = parts.next().ok_or(anyhow!("the signer command is empty"))?;
+        tracing::debug!(command = self.command, "running the signer command");
+        let mut child = Command::new(program)
+            .args(parts)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .context(format!(
+                "could not run the signer command `{}`",
+                self.command
+            ))?;
+        child
+            .stdin
+            .take()
+            .expect("the stdin is piped")
+            .write_all(input.as_bytes())?;
+        let output = child.wait_with_output()?;
+        ensure!(
+            output.status.success(),
+            "the signer command failed ({}): {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+        Ok(String::from_utf8(output.stdout)?)
+    }
+}
+
+/// Signs transactions with a private key stored in the OS secret store.
+///
+/// The key is read for every transaction, and is only kept in memory while signing; it is never
+/// printed or written to disk.
+#[derive(Debug, Clone)]
+pub(crate) struct KeyringSigner {
+    service: String,
+    account: String,
+}
+
+impl KeyringSigner {
+    /// Creates a new signer for the key stored under the given service and account.
+    pub fn new(service: String, account: String) -> Self {
+        Self { service, account }
+    }
+
+    /// Signs the transaction with the key from the secret store.
+    ///
+    /// Fails if the key is not the one of the given signer address.
+    pub fn sign(
+        &self,
+        transaction: &TransactionData,
+        intent: Intent,
+        signer: SuiAddress,
+    ) -> Result<Signature> {
+        let keypair = self.keypair()?;
+        let address = SuiAddress::from(&keypair.public());
+        ensure!(
+            address == signer,
+            "the key in the secret store is for {address}, but the transaction sender is {signer}"
+        );
+        Ok(Signature::new_secure(
+            &IntentMessage::new(intent, transaction.clone()),
+            &keypair,
+        ))
+    }
+
+    /// Reads the key from the secret store.
+    fn keypair(&self) -> Result<SuiKeyPair> {
+        let secret = self.read_secret()?;
+        let secret = secret.trim();
+        // The errors must not contain the secret, so they are not propagated.
+        SuiKeyPair::decode(secret)
+            .or_else(|_| SuiKeyPair::decode_base64(secret))
+            .map_err(|_| {
+                anyhow!(
+                    "the secret for service `{}` and account `{}` is not a valid Sui private key",
+                    self.service,
+                    self.account
+                )
+            })
+    }
+
+    /// Reads the secret from the secret store of the platform.
+    fn read_secret(&self) -> Result<String> {
+        tracing::debug!(
+            service = self.service,
+            account = self.account,
+            "reading the key from the secret store"
+        );
+        let entry = keyring::Entry::new(&self.service, &self.account)
+            .context("could not access the OS secret store")?;
+        match entry.get_password() {
+            Ok(secret) => Ok(secret),
+            Err(keyring::Error::NoEntry) => bail!(
+                "no key found in the secret store for service `{}` and account `{}`",
+                self.service,
+                self.account
+            ),
+            Err(error) => Err(error).context("could not read the key from the OS secret store"),
+        }
+    }
+}
@@ -1,30 +1,82 @@
 // Copyright (c) Mysten Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
+pub mod base_href;
 pub mod builder;
+pub mod cache;
+pub mod chunking;
 pub mod config;
 pub mod content;
 pub mod contracts;
+pub mod csp;
+pub mod directory_index;
+pub mod html;
+pub mod links;
+pub mod lockfile;
 pub mod manager;
+pub mod plan;
+pub mod remote;
 pub mod resource;
+pub mod snapshot;
 
-use std::{collections::HashMap, str::FromStr};
+use std::{
+    collections::{BTreeSet, HashMap},
+    str::FromStr,
+};
 
-use anyhow::Result;
-use contracts::get_sui_object;
-use resource::{ResourceOp, ResourceSet};
-use sui_sdk::SuiClient;
-use sui_types::{base_types::ObjectID, dynamic_field::DynamicFieldInfo, TypeTag};
+use anyhow::{bail, Result};
+use contracts::{get_sui_object, AssociatedContractStruct};
+use fastcrypto::hash::{HashFunction, Sha256};
+use move_core_types::{language_storage::StructTag, u256::U256};
+use resource::{hash_to_hex, resource_hash, ResourceOp, ResourceSet};
+use sui_sdk::{rpc_types::SuiObjectDataOptions, SuiClient};
+use sui_types::{
+    base_types::{ObjectID, SuiAddress},
+    dynamic_field::DynamicFieldInfo,
+    TypeTag,
+};
 
 use crate::{
     publish::WhenWalrusUpload,
     summary::SiteDataDiffSummary,
-    types::{ResourceDynamicField, RouteOps, Routes, SuiDynamicField},
-    util::handle_pagination,
+    types::{ResourceDynamicField, RouteOps, Routes, SuiDynamicField, SuiResource, SuiSite},
+    util::{get_dynamic_field_names, get_owned_objects_of_type, ResourceSelection},
 };
 
 pub const SITE_MODULE: &str = "site";
 
+/// The prefix of the leaves of the Merkle tree of the resources.
+const MERKLE_LEAF_PREFIX: u8 = 0;
+/// The prefix of the inner nodes of the Merkle tree of the resources.
+const MERKLE_NODE_PREFIX: u8 = 1;
+
+/// The name of the dynamic field containing the routes of a site.
+const ROUTES_FIELD: &[u8] = b"routes";
+
+/// The dynamic fields of a site that are reserved for purposes other than resources.
+///
+/// Reserved fields are named by a `vector<u8>`. This is the single place where they are listed,
+/// and every command classifies the fields through [`RemoteSiteFactory::classify_field`].
+const RESERVED_FIELDS: &[(&[u8], FieldKind)] = &[(ROUTES_FIELD, FieldKind::Routes)];
+
+/// The kind of a dynamic field of a site.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FieldKind {
+    /// A resource, named by its resource path.
+    Resource,
+    /// The routes of the site.
+    Routes,
+    /// A field that is not known to the site builder.
+    Unknown,
+}
+
+/// Returns the names of the reserved dynamic fields of a site.
+pub fn reserved_field_names() -> impl Iterator<Item = String> {
+    RESERVED_FIELDS
+        .iter()
+        .map(|(name, _)| String::from_utf8_lossy(name).into_owned())
+}
+
 /// The diff between two site data.
 #[derive(Debug)]
 pub struct SiteDataDiff<'a> {
@@ -35,9 +87,25 @@ pub struct SiteDataDiff<'a> {
 
 impl SiteDataDiff<'_> {
     /// Returns `true` if there are updates to be made.
-    #[cfg(test)]
     pub fn has_updates(&self) -> bool {
-        !self.resource_ops.is_empty() || !self.route_ops.is_unchanged()
+        self.resource_ops.iter().any(|op| op.is_change()) || !self.route_ops.is_unchanged()
+    }
+
+    /// Returns the paths of the resources that are deleted and not created again, i.e., removed
+    /// from the site.
+    pub fn removed_paths(&self) -> BTreeSet<&str> {
+        let created = self
+            .resource_ops
+            .iter()
+            .filter(|op| matches!(op, ResourceOp::Created(_)))
+            .map(|op| op.inner().info.path.as_str())
+            .collect::<BTreeSet<_>>();
+        self.resource_ops
+            .iter()
+            .filter(|op| matches!(op, ResourceOp::Deleted(_)))
+            .map(|op| op.inner().info.path.as_str())
+            .filter(|path| !created.contains(path))
+            .collect()
     }
 
     /// Returns the resources that need to be updated on Walrus.
@@ -84,6 +152,95 @@ impl SiteData {
         }
     }
 
+    /// Returns the resources of the site.
+    pub fn resources(&self) -> &ResourceSet {
+        &self.resources
+    }
+
+    /// Returns the site data with only the selected resources, and the given routes.
+    pub fn with_matching_resources(
+        &self,
+        selection: &ResourceSelection,
+        routes: Option<Routes>,
+    ) -> SiteData {
+        SiteData {
+            resources: self
+                .resources
+                .inner
+                .iter()
+                .filter(|resource| selection.matches(&resource.info.path))
+                .cloned()
+                .collect(),
+            routes,
+        }
+    }
+
+    /// Returns the routes of the site.
+    pub fn routes(&self) -> Option<&Routes> {
+        self.routes.as_ref()
+    }
+
+    /// Returns the fingerprint of the site, which changes whenever a resource or a route changes.
+    ///
+    /// The fingerprint is the SHA-256 digest of the resources, in sorted order, including their
+    /// paths, headers, and content hashes, and of the routes.
+    pub fn fingerprint(&self) -> Result<String> {
+        let resources = self
+            .resources
+            .inner
+            .iter()
+            .map(|resource| &resource.info)
+            .collect::<Vec<_>>();
+        let bytes = serde_json::to_vec(&(resources, &self.routes))?;
+        Ok(hash_to_hex(&resource_hash(&bytes)))
+    }
+
+    /// Returns the root of a Merkle tree committing to the paths and contents of the resources.
+    ///
+    /// Each leaf is the SHA-256 digest of the path and the content hash of a resource, in path
+    /// order; each inner node is the digest of its two children, and an unpaired node is carried to
+    /// the next level. Only the paths and the contents are included, so the root can be recomputed
+    /// both from the local files and from the site on chain.
+    pub fn resource_root(&self) -> String {
+        let mut resources = self
+            .resources
+            .inner
+            .iter()
+            .map(|resource| &resource.info)
+            .collect::<Vec<_>>();
+        resources.sort_by(|a, b| a.path.cmp(&b.path));
+        let mut level = resources
+            .iter()
+            .map(|info| {
+                let mut hash_function = Sha256::default();
+                hash_function.update([MERKLE_LEAF_PREFIX]);
+                hash_function.update((info.path.len() as u64).to_le_bytes());
+                hash_function.update(info.path.as_bytes());
+                hash_function.update(info.blob_hash.to_le_bytes());
+                hash_function.finalize().digest
+            })
+            .collect::<Vec<_>>();
+        if level.is_empty() {
+            level.push(Sha256::digest(b"").digest);
+        }
+        while level.len() > 1 {
+            level = level
+                .chunks(2)
+                .map(|nodes| match nodes {
+                    [left, right] => {
+                        let mut hash_function = Sha256::default();
+                        hash_function.update([MERKLE_NODE_PREFIX]);
+                        hash_function.update(left);
+                        hash_function.update(right);
+                        hash_function.finalize().digest
+                    }
+                    _ => nodes[0],
+                })
+                .collect();
+        }
+        hash_to_hex(&U256::from_le_bytes(&level[0]))
+    }
+
     // TODO(giac): rename start and reorder the direction of the diff.
     /// Returns the operations to perform to transform the start set into self.
     pub fn diff<'a>(&'a self, start: &'a SiteData) -> SiteDataDiff<'a> {
@@ -128,13 +285,21 @@ impl RemoteSiteFactory<'_> {
 
     /// Gets the remote site representation stored on chain
     pub async fn get_from_chain(&self, site_id: ObjectID) -> Result<SiteData> {
+        self.get_prefix_from_chain(site_id, "").await
+    }
+
+    /// Gets the resources of the site whose path starts with the prefix, and all its routes.
+    ///
+    /// The paths are read from the names of the dynamic fields, so only the matching resources
+    /// are fetched. The fields are not ordered by name, so all of them are still listed.
+    pub async fn get_prefix_from_chain(&self, site_id: ObjectID, prefix: &str) -> Result<SiteData> {
         let dynamic_fields = self.get_all_dynamic_fields(site_id).await?;
         let resources = ResourceSet::from_iter(
             futures::future::try_join_all(
                 dynamic_fields
                     .iter()
                     // Try to extract the resources.
-                    .filter(|field| field.name.type_ == self.resource_path_tag())
+                    .filter(|field| self.has_path_prefix(field, prefix))
                     .map(|field| {
                         get_sui_object::<ResourceDynamicField>(self.sui_client, field.object_id)
                     }),
@@ -149,10 +314,24 @@ impl RemoteSiteFactory<'_> {
         Ok(SiteData { resources, routes })
     }
 
+    /// Gets the resource of the site at the exact path, if any, fetching only its dynamic field.
+    pub async fn get_resource(&self, site_id: ObjectID, path: &str) -> Result<Option<SuiResource>> {
+        let dynamic_fields = self.get_all_dynamic_fields(site_id).await?;
+        let Some(field) = dynamic_fields
+            .iter()
+            .find(|field| self.get_path_from_info(field).as_deref() == Some(path))
+        else {
+            return Ok(None);
+        };
+        let field =
+            get_sui_object::<ResourceDynamicField>(self.sui_client, field.object_id).await?;
+        Ok(Some(field.value))
+    }
+
     async fn get_routes(&self, dynamic_fields: &[DynamicFieldInfo]) -> Result<Option<Routes>> {
         if let Some(routes_field) = dynamic_fields
             .iter()
-            .find(|field| field.name.type_ == TypeTag::Vector(Box::new(TypeTag::U8)))
+            .find(|field| self.classify_field(field) == FieldKind::Routes)
         {
             let routes = get_sui_object::<SuiDynamicField<Vec<u8>, Routes>>(
                 self.sui_client,
@@ -166,6 +345,77 @@ impl RemoteSiteFactory<'_> {
         }
     }
 
+    /// Counts the dynamic fields of the site, without fetching their values.
+    ///
+    /// Returns the number of resources and the number of other (metadata) fields, e.g., the
+    /// routes.
+    pub async fn count_fields(&self, site_id: ObjectID) -> Result<(usize, usize)> {
+        let dynamic_fields = self.get_all_dynamic_fields(site_id).await?;
+        let n_resources = dynamic_fields
+            .iter()
+            .filter(|field| self.classify_field(field) == FieldKind::Resource)
+            .count();
+        Ok((n_resources, dynamic_fields.len() - n_resources))
+    }
+
+    /// Finds the site with the given name owned by the address, if any.
+    ///
+    /// Fails if the address owns multiple sites with the name, as the site to update would be
+    /// ambiguous.
+    pub async fn find_owned_site(&self, owner: SuiAddress, name: &str) -> Result<Option<ObjectID>> {
+        let sites = get_owned_objects_of_type(
+            self.sui_client,
+            owner,
+            self.site_type()?,
+            SuiObjectDataOptions::new().with_bcs().with_type(),
+        )
+        .await?
+        .iter()
+        .map(SuiSite::try_from_object_data)
+        .collect::<Result<Vec<_>>>()?;
+        let matching = sites
+            .into_iter()
+            .filter(|site| site.name == name)
+            .map(|site| site.id)
+            .collect::<Vec<_>>();
+        match matching.as_slice() {
+            [] => Ok(None),
+            [site_id] => Ok(Some(*site_id)),
+            _ => bail!(
+                "the address {owner} owns {} sites named {name:?}: {}; update one of them by ID",
+                matching.len(),
+                matching
+                    .iter()
+                    .map(ObjectID::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        }
+    }
+
+    /// Returns the Move type of the sites of the package.
+    pub fn site_type(&self) -> Result<StructTag> {
+        contracts::site::Site.to_move_struct_tag(self.package_id, &[])
+    }
+
+    /// Gets all the dynamic fields of the site, including the ones unknown to the site builder.
+    pub async fn get_fields(&self, site_id: ObjectID) -> Result<Vec<DynamicFieldInfo>> {
+        self.get_all_dynamic_fields(site_id).await
+    }
+
+    /// Returns a readable name of the dynamic field of a site.
+    ///
+    /// Resources are named by their path; the names of unknown fields are shown as JSON.
+    pub fn field_label(&self, field: &DynamicFieldInfo) -> String {
+        match self.classify_field(field) {
+            FieldKind::Resource => self
+                .get_path_from_info(field)
+                .unwrap_or_else(|| field.name.value.to_string()),
+            FieldKind::Routes => String::from_utf8_lossy(ROUTES_FIELD).into_owned(),
+            FieldKind::Unknown => field.name.value.to_string(),
+        }
+    }
+
     /// Gets all the resources and their object ids from chain.
     pub async fn get_existing_resources(&self) -> Result<HashMap<String, ObjectID>> {
         let dynamic_fields = self.get_all_dynamic_fields(self.package_id).await?;
@@ -173,14 +423,7 @@ impl RemoteSiteFactory<'_> {
     }
 
     async fn get_all_dynamic_fields(&self, object_id: ObjectID) -> Result<Vec<DynamicFieldInfo>> {
-        let iter = handle_pagination(|cursor| {
-            self.sui_client
-                .read_api()
-                .get_dynamic_fields(object_id, cursor, None)
-        })
-        .await?
-        .collect();
-        Ok(iter)
+        get_dynamic_field_names(self.sui_client, object_id).await
     }
 
     /// Filters the dynamic fields to get the resource object IDs.
@@ -188,19 +431,24 @@ impl RemoteSiteFactory<'_> {
         &self,
         dynamic_fields: &[DynamicFieldInfo],
     ) -> Result<HashMap<String, ObjectID>> {
-        let type_tag = self.resource_path_tag();
         Ok(dynamic_fields
             .iter()
             .filter_map(|field| {
-                self.get_path_from_info(field, &type_tag)
+                self.get_path_from_info(field)
                     .map(|path| (path, field.object_id))
             })
             .collect::<HashMap<String, ObjectID>>())
     }
 
     /// Gets the path of the resource from the dynamic field.
-    fn get_path_from_info(&self, field: &DynamicFieldInfo, name_tag: &TypeTag) -> Option<String> {
-        if field.name.type_ != *name_tag {
+    /// Returns `true` if the field is a resource whose path starts with the prefix.
+    pub fn has_path_prefix(&self, field: &DynamicFieldInfo, prefix: &str) -> bool {
+        self.get_path_from_info(field)
+            .is_some_and(|path| path.starts_with(prefix))
+    }
+
+    fn get_path_from_info(&self, field: &DynamicFieldInfo) -> Option<String> {
+        if self.classify_field(field) != FieldKind::Resource {
             return None;
         }
         field
@@ -212,6 +460,23 @@ impl RemoteSiteFactory<'_> {
             .map(|s| s.to_owned())
     }
 
+    /// Classifies the dynamic field of a site from its name.
+    pub fn classify_field(&self, field: &DynamicFieldInfo) -> FieldKind {
+        if field.name.type_ == self.resource_path_tag() {
+            return FieldKind::Resource;
+        }
+        if field.name.type_ != TypeTag::Vector(Box::new(TypeTag::U8)) {
+            return FieldKind::Unknown;
+        }
+        let Some(name) = field_name_bytes(&field.name.value) else {
+            return FieldKind::Unknown;
+        };
+        RESERVED_FIELDS
+            .iter()
+            .find(|(reserved_name, _)| *reserved_name == name.as_slice())
+            .map_or(FieldKind::Unknown, |(_, kind)| *kind)
+    }
+
     /// Gets the type tag for the ResourcePath move struct
     fn resource_path_tag(&self) -> TypeTag {
         TypeTag::from_str(&format!("{}::{SITE_MODULE}::ResourcePath", self.package_id))
@@ -219,10 +484,28 @@ impl RemoteSiteFactory<'_> {
     }
 }
 
+/// Returns the bytes of a dynamic field name of type `vector<u8>`, given as JSON array.
+fn field_name_bytes(value: &serde_json::Value) -> Option<Vec<u8>> {
+    value
+        .as_array()?
+        .iter()
+        .map(|byte| byte.as_u64().and_then(|byte| u8::try_from(byte).ok()))
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
-    use super::SiteData;
-    use crate::{site::resource::ResourceSet, types::Routes};
+    use std::path::PathBuf;
+
+    use move_core_types::u256::U256;
+
+    use super::{field_name_bytes, SiteData, ROUTES_FIELD};
+    use crate::{
+        publish::WhenWalrusUpload,
+        site::resource::{Resource, ResourceSet},
+        types::{HttpHeaders, Routes},
+        walrus::types::BlobId,
+    };
 
     fn routes_from_pair(key: &str, value: &str) -> Option<Routes> {
         Some(Routes(
@@ -232,6 +515,118 @@ mod tests {
         ))
     }
 
+    #[test]
+    fn test_field_name_bytes() {
+        let routes = serde_json::json!(ROUTES_FIELD);
+        assert_eq!(field_name_bytes(&routes).as_deref(), Some(ROUTES_FIELD));
+        assert_eq!(
+            field_name_bytes(&serde_json::json!([0xff, 0xfe])),
+            Some(vec![0xff, 0xfe])
+        );
+        assert_eq!(field_name_bytes(&serde_json::json!([256])), None);
+        assert_eq!(
+            field_name_bytes(&serde_json::json!({"path": "/index.html"})),
+            None
+        );
+    }
+
+    #[test]
+    fn test_fingerprint() {
+        let site = SiteData::new(ResourceSet::empty(), routes_from_pair("/*", "/index.html"));
+        let same_site = SiteData::new(ResourceSet::empty(), routes_from_pair("/*", "/index.html"));
+        let other_site = SiteData::new(ResourceSet::empty(), routes_from_pair("/*", "/404.html"));
+        assert_eq!(
+            site.fingerprint().unwrap(),
+            same_site.fingerprint().unwrap()
+        );
+        assert_ne!(
+            site.fingerprint().unwrap(),
+            other_site.fingerprint().unwrap()
+        );
+        assert_eq!(site.fingerprint().unwrap().len(), 64);
+    }
+
+    #[test]
+    fn test_resource_root() {
+        let resource = |path: &str, hash: u8| {
+            Resource::new(
+                path.to_owned(),
+                PathBuf::from(path),
+                HttpHeaders(Default::default()),
+                BlobId([0; BlobId::LENGTH]),
+                U256::from(hash),
+                0,
+            )
+        };
+        let site = |resources: Vec<Resource>| {
+            SiteData::new(ResourceSet::from_iter(resources), None).resource_root()
+        };
+        let root = site(vec![
+            resource("/a.html", 1),
+            resource("/b.html", 2),
+            resource("/c.html", 3),
+        ]);
+        assert_eq!(root.len(), 64);
+        assert_eq!(
+            root,
+            site(vec![
+                resource("/c.html", 3),
+                resource("/a.html", 1),
+                resource("/b.html", 2),
+            ])
+        );
+        assert_ne!(
+            root,
+            site(vec![
+                resource("/a.html", 1),
+                resource("/b.html", 2),
+                resource("/c.html", 4),
+            ])
+        );
+        assert_ne!(root, site(vec![resource("/a.html", 1)]));
+        assert_ne!(site(vec![]), site(vec![resource("/a.html", 1)]));
+    }
+
+    #[test]
+    fn test_get_walrus_updates() {
+        let resource = |path: &str, hash: u8| {
+            Resource::new(
+                path.to_owned(),
+                PathBuf::from(path),
+                HttpHeaders(Default::default()),
+                BlobId([hash; BlobId::LENGTH]),
+                U256::from(hash),
+                0,
+            )
+        };
+        let existing = SiteData::new(
+            ResourceSet::from_iter([resource("/a.html", 1), resource("/b.html", 2)]),
+            None,
+        );
+        let local = SiteData::new(
+            ResourceSet::from_iter([resource("/a.html", 1), resource("/b.html", 3)]),
+            None,
+        );
+        let diff = local.diff(&existing);
+        let n_updates = |when_upload| diff.get_walrus_updates(&when_upload).len();
+        // The unchanged resources are only considered with `Missing`, which checks their blobs.
+        assert_eq!(n_updates(WhenWalrusUpload::Modified), 1);
+        assert_eq!(n_updates(WhenWalrusUpload::Missing), 2);
+
+        // The updated resource is deleted and created again, but not removed.
+        let local = SiteData::new(ResourceSet::from_iter([resource("/b.html", 3)]), None);
+        let diff = local.diff(&existing);
+        assert_eq!(
+            diff.removed_paths().into_iter().collect::<Vec<_>>(),
+            vec!["/a.html"]
+        );
+        let diff = existing.replace_all(&local);
+        assert_eq!(
+            diff.removed_paths().into_iter().collect::<Vec<_>>(),
+            vec!["/a.html"]
+        );
+    }
+
     #[test]
     fn test_routes_diff() {
         let cases = vec![
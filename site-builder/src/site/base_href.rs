@@ -0,0 +1,103 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Injection of a `<base href>` in the HTML pages, so that their relative references resolve
+//! against the path where the site is deployed.
+
+use super::html::{tag_end, tags};
+
+/// How the `<base>` element of the HTML pages is set.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BaseHref {
+    /// The URL of the `<base>` element, e.g., `/app/`.
+    pub href: String,
+    /// Replace the `<base>` elements already in the pages, instead of leaving the pages unchanged.
+    pub force: bool,
+}
+
+impl BaseHref {
+    /// Returns the page with the `<base>` element inserted at the start of its `<head>`.
+    ///
+    /// Returns `None` if the page is left unchanged: if it has no `<head>`, or if it already has
+    /// a `<base>` element and `force` is not set.
+    pub fn apply(&self, html: &str) -> Option<String> {
+        let has_base = tags(html).iter().any(|(name, _)| name == "base");
+        if has_base && !self.force {
+            return None;
+        }
+        let html = if has_base {
+            remove_tags(html, "base")
+        } else {
+            html.to_owned()
+        };
+        let head_end = find_tag(&html, "head")?;
+        let mut output = String::with_capacity(html.len() + self.href.len() + 16);
+        output.push_str(&html[..head_end]);
+        output.push_str(&format!(
+            "<base href=\"{}\">",
+            self.href.replace('&', "&amp;").replace('"', "&quot;")
+        ));
+        output.push_str(&html[head_end..]);
+        Some(output)
+    }
+}
+
+/// Returns the index right after the first opening tag with the name, if any.
+fn find_tag(html: &str, name: &str) -> Option<usize> {
+    let lowercase = html.to_ascii_lowercase();
+    let mut offset = 0;
+    while let Some(start) = lowercase[offset..].find(&format!("<{name}")) {
+        let name_end = offset + start + 1 + name.len();
+        let rest = &html[name_end..];
+        if rest.starts_with(|c: char| c.is_whitespace() || c == '>' || c == '/') {
+            return Some(name_end + (tag_end(rest) + 1).min(rest.len()));
+        }
+        offset = name_end;
+    }
+    None
+}
+
+/// Removes all the opening tags with the name.
+fn remove_tags(html: &str, name: &str) -> String {
+    let mut html = html.to_owned();
+    while let Some(end) = find_tag(&html, name) {
+        let start = html[..end]
+            .to_ascii_lowercase()
+            .rfind(&format!("<{name}"))
+            .expect("the tag was found");
+        html.replace_range(start..end, "");
+    }
+    html
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply() {
+        let base_href = BaseHref {
+            href: "/app/".to_owned(),
+            force: false,
+        };
+        assert_eq!(
+            base_href
+                .apply("<html><HEAD lang=\"en\"><title>A</title></head></html>")
+                .as_deref(),
+            Some("<html><HEAD lang=\"en\"><base href=\"/app/\"><title>A</title></head></html>")
+        );
+        // The `<header>` is not the `<head>`.
+        assert_eq!(base_href.apply("<body><header>A</header></body>"), None);
+
+        let page = "<head><base href=\"/\"><title>A</title></head>";
+        assert_eq!(base_href.apply(page), None);
+        let base_href = BaseHref {
+            force: true,
+            ..base_href
+        };
+        assert_eq!(
+            base_href.apply(page).as_deref(),
+            Some("<head><base href=\"/app/\"><title>A</title></head>")
+        );
+    }
+}
@@ -134,6 +134,18 @@ impl SitePtb<Argument> {
         self.transfer_arg(recipient, self.site_argument);
     }
 
+    /// Adds the move call to update the name of the site.
+    pub fn update_name(&mut self, name: &str) -> Result<()> {
+        tracing::debug!(%name, "new Move call: updating the site name");
+        let name_input = self.pt_builder.input(pure_call_arg(&name)?)?;
+        self.add_programmable_move_call(
+            contracts::site::update_name.identifier(),
+            vec![],
+            vec![self.site_argument, name_input],
+        );
+        Ok(())
+    }
+
     /// Adds the move calls to remove a resource from the site, if the resource exists.
     pub fn remove_resource_if_exists(&mut self, resource: &Resource) -> Result<()> {
         tracing::debug!(resource=%resource.info.path, "new Move call: removing resource");
@@ -146,6 +158,17 @@ impl SitePtb<Argument> {
         Ok(())
     }
 
+    /// Adds the move call to move a resource of the site to a new path.
+    pub fn move_resource(&mut self, old_path: &str, new_path: &str) -> Result<()> {
+        tracing::debug!(%old_path, %new_path, "new Move call: moving resource");
+        self.add_key_value_to_argument(
+            contracts::site::move_resource,
+            self.site_argument,
+            old_path,
+            new_path,
+        )
+    }
+
     /// Adds the move calls to create and add a resource to the site, with the specified headers.
     pub fn add_resource(&mut self, resource: &Resource) -> Result<()> {
         tracing::debug!(resource=%resource.info.path, "new Move call: adding resource");
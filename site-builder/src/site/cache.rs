@@ -0,0 +1,145 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Default `cache-control` headers of the resources, based on their content type.
+
+use clap::ValueEnum;
+
+/// The `cache-control` value for resources that never change at the same path.
+const IMMUTABLE: &str = "public, max-age=31536000, immutable";
+
+/// The minimum length of the part of a file name that is taken as a content hash.
+const MIN_HASH_LENGTH: usize = 8;
+
+/// How the `cache-control` headers of the resources are set.
+///
+/// The headers set in ws-resources.json always take precedence over the policy.
+#[derive(Debug, ValueEnum, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CachePolicy {
+    /// Do not set any `cache-control` header.
+    #[default]
+    None,
+    /// Revalidate the HTML pages, and cache the static assets with a hash in the file name
+    /// forever.
+    Default,
+    /// Cache the HTML pages for a few minutes, and all the static assets forever.
+    Aggressive,
+}
+
+impl CachePolicy {
+    /// Returns the `cache-control` header value for the resource, if the policy sets one.
+    pub fn cache_control(&self, resource_path: &str, content_type: &str) -> Option<&'static str> {
+        let is_html = content_type.starts_with("text/html");
+        let is_static = is_static_asset(content_type);
+        match self {
+            CachePolicy::None => None,
+            CachePolicy::Default if is_html => Some("no-cache"),
+            CachePolicy::Default if is_static && has_content_hash(resource_path) => Some(IMMUTABLE),
+            CachePolicy::Default if is_static => Some("public, max-age=3600"),
+            CachePolicy::Default => None,
+            CachePolicy::Aggressive if is_html => Some("public, max-age=300"),
+            CachePolicy::Aggressive if is_static => Some(IMMUTABLE),
+            CachePolicy::Aggressive => Some("public, max-age=86400"),
+        }
+    }
+}
+
+/// Returns `true` for the scripts, stylesheets, fonts, and images.
+fn is_static_asset(content_type: &str) -> bool {
+    [
+        "text/css",
+        "text/javascript",
+        "application/javascript",
+        "image/",
+    ]
+    .iter()
+    .any(|prefix| content_type.starts_with(prefix))
+        || content_type.contains("font")
+}
+
+/// Returns `true` if the file name contains a content hash, as emitted by the bundlers.
+///
+/// A part of the name, separated by `.`, `-`, or `_`, is taken as a hash if it is alphanumeric,
+/// contains a digit, and is at least 8 characters long (e.g., `main.3f2a1b9c.js`).
+fn has_content_hash(resource_path: &str) -> bool {
+    let file_name = resource_path.rsplit('/').next().unwrap_or(resource_path);
+    let stem = file_name
+        .rsplit_once('.')
+        .map_or(file_name, |(stem, _)| stem);
+    stem.split(['.', '-', '_']).any(|part| {
+        part.len() >= MIN_HASH_LENGTH
+            && part.chars().all(|c| c.is_ascii_alphanumeric())
+            && part.chars().any(|c| c.is_ascii_digit())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cache_control() {
+        let cases = vec![
+            (CachePolicy::None, "/index.html", "text/html", None),
+            (
+                CachePolicy::Default,
+                "/index.html",
+                "text/html",
+                Some("no-cache"),
+            ),
+            (
+                CachePolicy::Default,
+                "/assets/main.3f2a1b9c.js",
+                "text/javascript",
+                Some(IMMUTABLE),
+            ),
+            (
+                CachePolicy::Default,
+                "/assets/index-Bk4h2a9Z.css",
+                "text/css",
+                Some(IMMUTABLE),
+            ),
+            (
+                CachePolicy::Default,
+                "/logo.png",
+                "image/png",
+                Some("public, max-age=3600"),
+            ),
+            (CachePolicy::Default, "/data.json", "application/json", None),
+            (
+                CachePolicy::Aggressive,
+                "/index.html",
+                "text/html",
+                Some("public, max-age=300"),
+            ),
+            (
+                CachePolicy::Aggressive,
+                "/logo.png",
+                "image/png",
+                Some(IMMUTABLE),
+            ),
+            (
+                CachePolicy::Aggressive,
+                "/data.json",
+                "application/json",
+                Some("public, max-age=86400"),
+            ),
+        ];
+        for (policy, path, content_type, expected) in cases {
+            assert_eq!(
+                policy.cache_control(path, content_type),
+                expected,
+                "{policy:?} {path}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_has_content_hash() {
+        assert!(has_content_hash("/assets/main.3f2a1b9c.js"));
+        assert!(has_content_hash("/chunk-a1b2c3d4e5.js"));
+        assert!(!has_content_hash("/assets/stylesheet.css"));
+        assert!(!has_content_hash("/main.js"));
+        assert!(!has_content_hash("/3f2a1b9c/main.js"));
+    }
+}
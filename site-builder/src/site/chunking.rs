@@ -0,0 +1,435 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Splitting of the updates of a site into transactions that fit the limits of Sui.
+
+use clap::ValueEnum;
+
+use super::{resource::ResourceOp, SiteDataDiff};
+use crate::types::RouteOps;
+
+/// The maximum number of commands in a single transaction, below the protocol limit of 1024.
+pub const MAX_COMMANDS_PER_TX: usize = 1000;
+
+/// The maximum number of objects created or deleted in a single transaction, below the protocol
+/// limit of 2048.
+pub const MAX_OBJECTS_PER_TX: usize = 1000;
+
+/// The maximum size of the inputs of a single transaction, below the protocol limit of 128 KiB.
+pub const MAX_INPUT_BYTES_PER_TX: usize = 100 * 1024;
+
+/// The estimated serialization overhead of each input of a transaction, in bytes.
+const INPUT_OVERHEAD_BYTES: usize = 8;
+
+/// How the resources are ordered and packed into transactions.
+///
+/// Every transaction respects both the command and the input size limits; the strategy selects
+/// the order in which the resources are packed.
+#[derive(Debug, ValueEnum, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ChunkStrategy {
+    /// Keep the resources in path order, and pack them up to the command limit.
+    #[default]
+    Count,
+    /// Order the new resources by size, smallest first, and pack them up to the size limit.
+    Bytes,
+}
+
+/// The limits that every transaction must respect.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TxLimits {
+    pub max_commands: usize,
+    /// The maximum number of objects created or deleted, i.e., of resources added or removed.
+    pub max_objects: usize,
+    pub max_input_bytes: usize,
+}
+
+impl Default for TxLimits {
+    fn default() -> Self {
+        Self {
+            max_commands: MAX_COMMANDS_PER_TX,
+            max_objects: MAX_OBJECTS_PER_TX,
+            max_input_bytes: MAX_INPUT_BYTES_PER_TX,
+        }
+    }
+}
+
+/// The estimated cost of operations in a transaction.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct Cost {
+    commands: usize,
+    objects: usize,
+    bytes: usize,
+}
+
+impl Cost {
+    fn add(self, other: Cost) -> Cost {
+        Cost {
+            commands: self.commands + other.commands,
+            objects: self.objects + other.objects,
+            bytes: self.bytes + other.bytes,
+        }
+    }
+
+    fn fits(&self, limits: &TxLimits) -> bool {
+        self.commands <= limits.max_commands
+            && self.objects <= limits.max_objects
+            && self.bytes <= limits.max_input_bytes
+    }
+}
+
+/// Splits the updates into the diffs to be executed in successive transactions.
+///
+/// The resources are removed before they are added, so that the updated resources are never
+/// added twice; the routes are updated in the last transaction. At least one diff is returned,
+/// so that a new site is always created.
+///
+/// The resources listed in `order` are added first, in that order, followed by the others in the
+/// order of the strategy. If the entry point of the site is created, it is the first resource
+/// added in any case, so that it is never left out of a site interrupted between transactions.
+pub fn chunk_updates<'a>(
+    updates: &SiteDataDiff<'a>,
+    strategy: ChunkStrategy,
+    entry_point: Option<&str>,
+    order: &[String],
+    limits: &TxLimits,
+) -> Vec<SiteDataDiff<'a>> {
+    let mut ops = updates
+        .resource_ops
+        .iter()
+        .filter(|op| op.is_change())
+        .copied()
+        .collect::<Vec<_>>();
+    if strategy == ChunkStrategy::Bytes {
+        // The sort is stable and keeps the deletions first.
+        ops.sort_by_key(|op| {
+            (
+                matches!(op, ResourceOp::Created(_)),
+                resource_op_cost(op).bytes,
+            )
+        });
+    }
+    if !order.is_empty() {
+        move_listed_first(&mut ops, order);
+    }
+    if let Some(entry_point) = entry_point {
+        move_entry_point_first(&mut ops, entry_point);
+    }
+
+    let mut chunks: Vec<(Vec<ResourceOp<'a>>, Cost)> = vec![];
+    for op in ops {
+        let cost = resource_op_cost(&op);
+        match chunks.last_mut() {
+            Some((chunk, chunk_cost)) if chunk_cost.add(cost).fits(limits) => {
+                chunk.push(op);
+                *chunk_cost = chunk_cost.add(cost);
+            }
+            _ => chunks.push((vec![op], cost)),
+        }
+    }
+
+    let route_cost = route_ops_cost(&updates.route_ops);
+    let routes_fit_last = chunks.last().map_or(true, |(_, chunk_cost)| {
+        chunk_cost.add(route_cost).fits(limits)
+    });
+    if !routes_fit_last {
+        chunks.push((vec![], Cost::default()));
+    }
+    let mut diffs = chunks
+        .into_iter()
+        .map(|(resource_ops, _)| SiteDataDiff {
+            resource_ops,
+            route_ops: RouteOps::Unchanged,
+        })
+        .collect::<Vec<_>>();
+    if diffs.is_empty() {
+        diffs.push(SiteDataDiff {
+            resource_ops: vec![],
+            route_ops: RouteOps::Unchanged,
+        });
+    }
+    diffs
+        .last_mut()
+        .expect("there is at least one diff")
+        .route_ops = updates.route_ops.clone();
+    diffs
+}
+
+/// Moves the creations of the resources listed in `order` before the other creations, in the
+/// listed order.
+///
+/// The sort is stable, so the deletions stay first, and the other creations keep their order.
+fn move_listed_first(ops: &mut [ResourceOp], order: &[String]) {
+    ops.sort_by_key(|op| match op {
+        ResourceOp::Created(resource) => (
+            true,
+            order
+                .iter()
+                .position(|path| *path == resource.info.path)
+                .unwrap_or(order.len()),
+        ),
+        _ => (false, 0),
+    });
+}
+
+/// Moves the creation of the entry point before the creation of any other resource.
+///
+/// The deletions always precede the creations, so an updated entry point is still removed before
+/// it is added again.
+fn move_entry_point_first(ops: &mut Vec<ResourceOp>, entry_point: &str) {
+    let is_created = |op: &ResourceOp| matches!(op, ResourceOp::Created(_));
+    let Some(entry_index) = ops
+        .iter()
+        .position(|op| is_created(op) && op.inner().info.path == entry_point)
+    else {
+        return;
+    };
+    let first_created = ops
+        .iter()
+        .position(is_created)
+        .expect("the entry point is created");
+    let entry = ops.remove(entry_index);
+    ops.insert(first_created, entry);
+}
+
+/// Estimates the cost of the commands added to the PTB for the operation.
+fn resource_op_cost(op: &ResourceOp) -> Cost {
+    let info = &op.inner().info;
+    match op {
+        ResourceOp::Deleted(_) => Cost {
+            commands: 1,
+            objects: 1,
+            bytes: info.path.len() + INPUT_OVERHEAD_BYTES,
+        },
+        ResourceOp::Created(_) => {
+            let header_bytes: usize = info
+                .headers
+                .0
+                .iter()
+                .map(|(name, value)| name.len() + value.len() + 2 * INPUT_OVERHEAD_BYTES)
+                .sum();
+            Cost {
+                // The range, the resource, each header, and the addition to the site.
+                commands: 3 + info.headers.0.len(),
+                // The resource is a dynamic field of the site.
+                objects: 1,
+                // The path, the blob ID, the blob hash, and the range.
+                bytes: info.path.len() + 32 + 32 + 2 + 5 * INPUT_OVERHEAD_BYTES + header_bytes,
+            }
+        }
+        ResourceOp::Unchanged(_) => Cost::default(),
+    }
+}
+
+/// Estimates the cost of the commands added to the PTB for the route operations.
+fn route_ops_cost(route_ops: &RouteOps) -> Cost {
+    match route_ops {
+        RouteOps::Unchanged => Cost::default(),
+        RouteOps::Replace(routes) => Cost {
+            commands: 2 + routes.0.len(),
+            objects: 1,
+            bytes: routes
+                .0
+                .iter()
+                .map(|(name, value)| name.len() + value.len() + 2 * INPUT_OVERHEAD_BYTES)
+                .sum(),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::BTreeMap, path::PathBuf};
+
+    use move_core_types::u256::U256;
+
+    use super::*;
+    use crate::{
+        site::resource::Resource,
+        types::{HttpHeaders, Routes},
+        walrus::types::BlobId,
+    };
+
+    fn resource(path: &str, n_headers: usize) -> Resource {
+        Resource::new(
+            path.to_owned(),
+            PathBuf::from(path),
+            HttpHeaders(
+                (0..n_headers)
+                    .map(|index| (format!("header-{index}"), "value".to_owned()))
+                    .collect(),
+            ),
+            BlobId([0; BlobId::LENGTH]),
+            U256::zero(),
+            0,
+        )
+    }
+
+    #[test]
+    fn test_chunk_updates_by_count() {
+        let resources = (0..600)
+            .map(|index| resource(&format!("/{index}.html"), 0))
+            .collect::<Vec<_>>();
+        let routes = Routes(BTreeMap::from([("/*".to_owned(), "/0.html".to_owned())]));
+        let updates = SiteDataDiff {
+            resource_ops: resources.iter().map(ResourceOp::Created).collect(),
+            route_ops: RouteOps::Replace(routes),
+        };
+        let chunks = chunk_updates(
+            &updates,
+            ChunkStrategy::Count,
+            None,
+            &[],
+            &TxLimits::default(),
+        );
+        // Each resource takes 3 commands.
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(chunks[0].resource_ops.len(), MAX_COMMANDS_PER_TX / 3);
+        assert!(chunks[0].route_ops.is_unchanged());
+        assert!(!chunks[1].route_ops.is_unchanged());
+    }
+
+    #[test]
+    fn test_chunk_updates_with_limits() {
+        let resources = (0..600)
+            .map(|index| resource(&format!("/{index}.html"), 0))
+            .collect::<Vec<_>>();
+        let updates = SiteDataDiff {
+            resource_ops: resources.iter().map(ResourceOp::Deleted).collect(),
+            route_ops: RouteOps::Unchanged,
+        };
+        // Each deletion takes 1 command and deletes 1 object.
+        let limits = TxLimits {
+            max_objects: 250,
+            ..TxLimits::default()
+        };
+        let chunks = chunk_updates(&updates, ChunkStrategy::Count, None, &[], &limits);
+        assert_eq!(chunks.len(), 3);
+        assert_eq!(chunks[0].resource_ops.len(), 250);
+        let limits = TxLimits {
+            max_commands: 100,
+            ..TxLimits::default()
+        };
+        let chunks = chunk_updates(&updates, ChunkStrategy::Count, None, &[], &limits);
+        assert_eq!(chunks.len(), 6);
+    }
+
+    #[test]
+    fn test_chunk_updates_by_bytes() {
+        let large = resource("/large.html", 10);
+        let small = resource("/small.html", 0);
+        let deleted = resource("/deleted.html", 10);
+        let updates = SiteDataDiff {
+            resource_ops: vec![
+                ResourceOp::Created(&large),
+                ResourceOp::Created(&small),
+                ResourceOp::Deleted(&deleted),
+            ],
+            route_ops: RouteOps::Unchanged,
+        };
+        let chunks = chunk_updates(
+            &updates,
+            ChunkStrategy::Bytes,
+            None,
+            &[],
+            &TxLimits::default(),
+        );
+        assert_eq!(chunks.len(), 1);
+        let paths = chunks[0]
+            .resource_ops
+            .iter()
+            .map(|op| op.inner().info.path.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(paths, vec!["/deleted.html", "/small.html", "/large.html"]);
+    }
+
+    #[test]
+    fn test_chunk_updates_entry_point_first() {
+        let asset = resource("/assets/app.js", 0);
+        let index = resource("/index.html", 0);
+        let deleted = resource("/old.html", 0);
+        let updates = SiteDataDiff {
+            resource_ops: vec![
+                ResourceOp::Deleted(&deleted),
+                ResourceOp::Created(&asset),
+                ResourceOp::Created(&index),
+            ],
+            route_ops: RouteOps::Unchanged,
+        };
+        let paths = |strategy| {
+            chunk_updates(
+                &updates,
+                strategy,
+                Some("/index.html"),
+                &[],
+                &TxLimits::default(),
+            )[0]
+            .resource_ops
+            .iter()
+            .map(|op| op.inner().info.path.clone())
+            .collect::<Vec<_>>()
+        };
+        for strategy in [ChunkStrategy::Count, ChunkStrategy::Bytes] {
+            assert_eq!(
+                paths(strategy),
+                vec!["/old.html", "/index.html", "/assets/app.js"]
+            );
+        }
+    }
+
+    #[test]
+    fn test_chunk_updates_in_order() {
+        let resources = (0..400)
+            .map(|index| resource(&format!("/{index:03}.html"), 0))
+            .collect::<Vec<_>>();
+        let updates = SiteDataDiff {
+            resource_ops: resources.iter().map(ResourceOp::Created).collect(),
+            route_ops: RouteOps::Unchanged,
+        };
+        let order = vec!["/399.html".to_owned(), "/100.html".to_owned()];
+        let chunks = chunk_updates(
+            &updates,
+            ChunkStrategy::Count,
+            Some("/200.html"),
+            &order,
+            &TxLimits::default(),
+        );
+        assert_eq!(chunks.len(), 2);
+        // The order is kept across the transactions.
+        let paths = chunks
+            .iter()
+            .flat_map(|chunk| chunk.resource_ops.iter())
+            .map(|op| op.inner().info.path.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            paths[..5],
+            [
+                "/200.html",
+                "/399.html",
+                "/100.html",
+                "/000.html",
+                "/001.html"
+            ]
+        );
+        assert_eq!(paths.len(), 400);
+        assert_eq!(paths[399], "/398.html");
+    }
+
+    #[test]
+    fn test_chunk_updates_empty() {
+        let updates = SiteDataDiff {
+            resource_ops: vec![],
+            route_ops: RouteOps::Unchanged,
+        };
+        assert_eq!(
+            chunk_updates(
+                &updates,
+                ChunkStrategy::Count,
+                None,
+                &[],
+                &TxLimits::default()
+            )
+            .len(),
+            1
+        );
+    }
+}
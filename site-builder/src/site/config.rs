@@ -1,16 +1,23 @@
 // Copyright (c) Mysten Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use std::{collections::BTreeMap, path::Path};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::{self, Display},
+    path::Path,
+};
 
-use anyhow::{Context, Result};
+use anyhow::{ensure, Context, Result};
 use serde::{Deserialize, Serialize};
 
 use super::Routes;
-use crate::types::HttpHeaders;
+use crate::{types::HttpHeaders, util::glob_match};
+
+/// The methods allowed by default, for the patterns of `allowed_methods` with no methods.
+pub const DEFAULT_ALLOWED_METHODS: [&str; 2] = ["GET", "HEAD"];
 
 /// Deserialized object of the file's `ws-resource.json` contents.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct WSResources {
     /// The HTTP headers to be set for the resources.
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -18,6 +25,59 @@ pub struct WSResources {
     /// The HTTP headers to be set for the resources.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub routes: Option<Routes>,
+    /// The resources to be served for the HTTP error statuses, e.g., `"404": "/not-found.html"`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error_pages: Option<BTreeMap<u16, String>>,
+    /// The content types of the resources at exact paths, e.g.,
+    /// `"/manifest": "application/manifest+json"`.
+    ///
+    /// These take precedence over the `Content-Type` headers and the type inferred from the
+    /// extension.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content_types: Option<BTreeMap<String, String>>,
+    /// The resources whose content is downloaded from a URL at publish time, e.g.,
+    /// `"/video.mp4": { "url": "https://cdn.example.com/video.mp4" }`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub remote_resources: Option<BTreeMap<String, RemoteResource>>,
+    /// The resources added to the site first, in this order, e.g., `["/index.html", "/app.js"]`.
+    ///
+    /// The other resources are added afterwards, in path order.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub order: Option<Vec<String>>,
+    /// The resources that must never change once published, e.g., `["/legal/terms.pdf"]`.
+    ///
+    /// Updates that would change or remove them fail, unless `--allow-immutable-change` is set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub immutable: Option<BTreeSet<String>>,
+    /// The resources that every publish must contain, e.g., `["/index.html", "/favicon.ico"]`.
+    ///
+    /// A publish fails if any of them is missing, e.g., because the build dropped a file.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub required: Option<BTreeSet<String>>,
+    /// Named groups of resources, each defined by glob patterns, e.g.,
+    /// `"images": ["/img/**", "/**/*.png"]`.
+    ///
+    /// A publish with `--tag` only updates the resources of the tag.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tags: Option<BTreeMap<String, Vec<String>>>,
+    /// The HTTP methods accepted by the resources matching each glob pattern, stored as their
+    /// `allow` header, e.g., `"/**": ["GET", "HEAD"]`; an empty list allows `GET` and `HEAD`.
+    ///
+    /// The longest matching pattern wins, and an `Allow` header set in `headers` takes
+    /// precedence. The methods are only declared: enforcing them is up to the portal.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub allowed_methods: Option<BTreeMap<String, Vec<String>>>,
+}
+
+/// A resource whose content is downloaded from a URL, and stored as a blob like a local file.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct RemoteResource {
+    /// The HTTP or HTTPS URL of the content.
+    pub url: String,
+    /// The content type of the resource; defaults to the `Content-Type` of the response, then to
+    /// the type inferred from the extension of the path.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<String>,
 }
 
 impl WSResources {
@@ -31,6 +91,313 @@ impl WSResources {
         tracing::info!(?ws_config, "ws resources configuration loaded");
         Ok(ws_config)
     }
+
+    /// Merges the configuration of the `ws-resources.json` file of a subdirectory, whose resources
+    /// are under `prefix`, e.g., `/docs`.
+    ///
+    /// The paths in the nested file are relative to its directory: `/index.html` in
+    /// `docs/ws-resources.json` is `/docs/index.html`, and the route `/*` is `/docs/*`. For the
+    /// same path, the nested file wins: its headers override the ones with the same name, and its
+    /// content type, route, and remote resource replace the ones of this configuration. The
+    /// nested order follows this one, and the immutable and required paths and the patterns of the
+    /// tags are added; the allowed methods of a pattern replace the ones of the same pattern. The
+    /// error pages apply to the whole site, so they can only be set in the root file.
+    ///
+    /// The files are merged from the root down, so that the nearest file wins.
+    pub fn merge_nested(&mut self, nested: WSResources, prefix: &str) -> Result<()> {
+        ensure!(
+            nested.error_pages.is_none(),
+            "the error pages can only be set in the root ws-resources.json, not in the one of {}",
+            prefix
+        );
+        let prefixed = |path: &str| format!("{prefix}{path}");
+        for (path, headers) in nested.headers.into_iter().flatten() {
+            let merged = self
+                .headers
+                .get_or_insert_with(BTreeMap::new)
+                .entry(prefixed(&path))
+                .or_insert_with(|| HttpHeaders(BTreeMap::new()));
+            for (name, value) in headers.0 {
+                merged
+                    .0
+                    .retain(|other, _| !other.eq_ignore_ascii_case(&name));
+                merged.0.insert(name, value);
+            }
+        }
+        for (pattern, target) in nested.routes.into_iter().flat_map(|routes| routes.0) {
+            self.routes
+                .get_or_insert_with(Routes::empty)
+                .0
+                .insert(prefixed(&pattern), prefixed(&target));
+        }
+        for (path, content_type) in nested.content_types.into_iter().flatten() {
+            self.content_types
+                .get_or_insert_with(BTreeMap::new)
+                .insert(prefixed(&path), content_type);
+        }
+        for (path, remote) in nested.remote_resources.into_iter().flatten() {
+            self.remote_resources
+                .get_or_insert_with(BTreeMap::new)
+                .insert(prefixed(&path), remote);
+        }
+        if let Some(order) = nested.order {
+            self.order
+                .get_or_insert_with(Vec::new)
+                .extend(order.iter().map(|path| prefixed(path)));
+        }
+        if let Some(immutable) = nested.immutable {
+            self.immutable
+                .get_or_insert_with(BTreeSet::new)
+                .extend(immutable.iter().map(|path| prefixed(path)));
+        }
+        if let Some(required) = nested.required {
+            self.required
+                .get_or_insert_with(BTreeSet::new)
+                .extend(required.iter().map(|path| prefixed(path)));
+        }
+        for (tag, patterns) in nested.tags.into_iter().flatten() {
+            self.tags
+                .get_or_insert_with(BTreeMap::new)
+                .entry(tag)
+                .or_default()
+                .extend(patterns.iter().map(|pattern| prefixed(pattern)));
+        }
+        for (pattern, methods) in nested.allowed_methods.into_iter().flatten() {
+            self.allowed_methods
+                .get_or_insert_with(BTreeMap::new)
+                .insert(prefixed(&pattern), methods);
+        }
+        Ok(())
+    }
+
+    /// Returns the value of the `allow` header of the resource, if a pattern of
+    /// `allowed_methods` matches its path.
+    pub fn allow_header(&self, resource_path: &str) -> Option<String> {
+        let (_, methods) = self
+            .allowed_methods
+            .iter()
+            .flatten()
+            .filter(|(pattern, _)| glob_match(pattern, resource_path))
+            .max_by_key(|(pattern, _)| pattern.len())?;
+        Some(if methods.is_empty() {
+            DEFAULT_ALLOWED_METHODS.join(", ")
+        } else {
+            methods.join(", ")
+        })
+    }
+
+    /// Checks the configuration against the resource paths of the site, returning all the
+    /// problems found.
+    pub fn validate(&self, resource_paths: &BTreeSet<String>) -> Vec<Problem> {
+        let mut problems = vec![];
+        for (path, remote) in self.remote_resources.iter().flatten() {
+            if let Err(error) = check_remote_resource_path(path) {
+                problems.push(Problem::error(error.to_string()));
+            }
+            if !remote.url.starts_with("https://") && !remote.url.starts_with("http://") {
+                problems.push(Problem::error(format!(
+                    "the remote resource {path} has the URL {}, which is not an HTTP URL",
+                    remote.url
+                )));
+            }
+            if resource_paths.contains(path) {
+                problems.push(Problem::error(format!(
+                    "the remote resource {path} is also a file of the site"
+                )));
+            }
+        }
+        // The rules can also apply to the remote resources.
+        let resource_paths = &resource_paths
+            .iter()
+            .cloned()
+            .chain(
+                self.remote_resources
+                    .iter()
+                    .flatten()
+                    .map(|(path, _)| path.clone()),
+            )
+            .collect::<BTreeSet<_>>();
+        for (path, headers) in self.headers.iter().flatten() {
+            if !resource_paths.contains(path) {
+                problems.push(Problem::error(missing_path_message("headers", path)));
+            }
+            let mut seen: BTreeMap<String, &str> = BTreeMap::new();
+            for (name, value) in headers.0.iter() {
+                match seen.insert(name.to_lowercase(), value.as_str()) {
+                    Some(other) if other != value.as_str() => {
+                        problems.push(Problem::error(format!(
+                        "the headers of {path} set `{name}` more than once, with different values"
+                    )))
+                    }
+                    _ => (),
+                }
+            }
+        }
+        for path in self.content_types.iter().flatten().map(|(path, _)| path) {
+            if !resource_paths.contains(path) {
+                problems.push(Problem::error(missing_path_message("a content type", path)));
+            }
+        }
+        for (pattern, target) in self.routes.iter().flat_map(|routes| routes.0.iter()) {
+            if !pattern.starts_with('/') {
+                problems.push(Problem::error(format!(
+                    "the route {pattern} does not start with `/`"
+                )));
+            }
+            if !resource_paths.contains(target) {
+                problems.push(Problem::error(format!(
+                    "the route {pattern} points to {target}, which is not a file of the site"
+                )));
+            }
+            if pattern.matches('*').count() > 1 {
+                problems.push(Problem::warning(format!(
+                    "the route {pattern} contains more than one `*`; only the first is a wildcard"
+                )));
+            }
+            if !pattern.contains('*') && resource_paths.contains(pattern) {
+                problems.push(Problem::warning(format!(
+                    "the route {pattern} is unreachable, as the file at the same path is served \
+                    instead"
+                )));
+            }
+        }
+        for (status, path) in self.error_pages.iter().flatten() {
+            if !resource_paths.contains(path) {
+                problems.push(Problem::error(format!(
+                    "the error page for status {status} is {path}, which is not a file of the site"
+                )));
+            }
+            if *status != 404 {
+                problems.push(Problem::warning(format!(
+                    "the error page for status {status} is unreachable, as the portal currently \
+                    only serves custom error pages for status 404"
+                )));
+            }
+        }
+        let mut ordered = BTreeSet::new();
+        for path in self.order.iter().flatten() {
+            if !resource_paths.contains(path) {
+                problems.push(Problem::warning(missing_path_message(
+                    "a position in the order",
+                    path,
+                )));
+            }
+            if !ordered.insert(path) {
+                problems.push(Problem::warning(format!(
+                    "the order lists {path} more than once; only the first position is used"
+                )));
+            }
+        }
+        for path in self.immutable.iter().flatten() {
+            if !resource_paths.contains(path) {
+                problems.push(Problem::error(missing_path_message("immutability", path)));
+            }
+        }
+        for path in self.required.iter().flatten() {
+            if !resource_paths.contains(path) {
+                problems.push(Problem::error(format!(
+                    "the required resource {path} is not a file of the site"
+                )));
+            }
+        }
+        for (tag, patterns) in self.tags.iter().flatten() {
+            if patterns.is_empty() {
+                problems.push(Problem::error(format!("the tag {tag} has no patterns")));
+                continue;
+            }
+            for pattern in patterns {
+                if !pattern.starts_with('/') {
+                    problems.push(Problem::error(format!(
+                        "the pattern {pattern} of the tag {tag} must start with `/`"
+                    )));
+                } else if !resource_paths.iter().any(|path| glob_match(pattern, path)) {
+                    problems.push(Problem::warning(format!(
+                        "the pattern {pattern} of the tag {tag} matches no file of the site"
+                    )));
+                }
+            }
+        }
+        for (pattern, methods) in self.allowed_methods.iter().flatten() {
+            if !pattern.starts_with('/') {
+                problems.push(Problem::error(format!(
+                    "the pattern {pattern} of the allowed methods must start with `/`"
+                )));
+            } else if !resource_paths.iter().any(|path| glob_match(pattern, path)) {
+                problems.push(Problem::warning(format!(
+                    "the allowed methods of {pattern} match no file of the site"
+                )));
+            }
+            for method in methods {
+                if method.is_empty() || !method.chars().all(|c| c.is_ascii_uppercase()) {
+                    problems.push(Problem::error(format!(
+                        "the allowed methods of {pattern} contain `{method}`, which is not an \
+                        uppercase HTTP method"
+                    )));
+                }
+            }
+            if !methods.is_empty() && !methods.iter().any(|method| method == "GET") {
+                problems.push(Problem::warning(format!(
+                    "the allowed methods of {pattern} do not contain GET, so the portal may \
+                    refuse to serve the resources"
+                )));
+            }
+        }
+        problems
+    }
+}
+
+/// Checks that the path of a remote resource is an absolute resource path, without `.` or `..`.
+pub fn check_remote_resource_path(path: &str) -> Result<()> {
+    ensure!(
+        path.starts_with('/')
+            && path
+                .split('/')
+                .skip(1)
+                .all(|segment| !matches!(segment, "" | "." | "..")),
+        "the remote resource path {path} must start with `/`, and contain no empty, `.`, or `..` \
+        segments"
+    );
+    Ok(())
+}
+
+/// Returns the message for a rule declared for a path that is not a file of the site.
+fn missing_path_message(rule: &str, path: &str) -> String {
+    if path.contains('*') {
+        format!("{rule} declared for {path}: rules are matched by exact path, not by pattern")
+    } else {
+        format!("{rule} declared for {path}, which is not a file of the site")
+    }
+}
+
+/// A problem found while validating the `ws-resources.json` file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Problem {
+    /// Whether the problem makes the configuration invalid.
+    pub is_error: bool,
+    pub message: String,
+}
+
+impl Problem {
+    fn error(message: String) -> Self {
+        Self {
+            is_error: true,
+            message,
+        }
+    }
+
+    fn warning(message: String) -> Self {
+        Self {
+            is_error: false,
+            message,
+        }
+    }
+}
+
+impl Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = if self.is_error { "error" } else { "warning" };
+        write!(f, "{kind}: {}", self.message)
+    }
 }
 
 #[cfg(test)]
@@ -54,6 +421,247 @@ mod tests {
             }
         "#;
 
+    const ERROR_PAGES_DATA: &str = r#"
+            "error_pages": {
+                "404": "/not-found.html"
+            }
+        "#;
+
+    #[test]
+    fn test_read_error_pages() {
+        let data = format!("{{{},{}}}", ROUTE_DATA, ERROR_PAGES_DATA);
+        let ws_resources =
+            serde_json::from_str::<WSResources>(&data).expect("parsing should succeed");
+        assert_eq!(
+            ws_resources
+                .error_pages
+                .unwrap()
+                .get(&404)
+                .map(String::as_str),
+            Some("/not-found.html")
+        );
+    }
+
+    #[test]
+    fn test_read_content_types() {
+        let data = r#"{"content_types": {"/manifest": "application/manifest+json"}}"#;
+        let ws_resources =
+            serde_json::from_str::<WSResources>(data).expect("parsing should succeed");
+        assert_eq!(
+            ws_resources
+                .content_types
+                .unwrap()
+                .get("/manifest")
+                .map(String::as_str),
+            Some("application/manifest+json")
+        );
+    }
+
+    #[test]
+    fn test_validate() {
+        let data = r#"{
+            "headers": {
+                "/index.html": {"Cache-Control": "no-cache", "cache-control": "max-age=60"},
+                "/*.css": {"Cache-Control": "no-cache"}
+            },
+            "routes": {"/*": "/index.html", "/app": "/missing.html", "/about.html": "/index.html"},
+            "error_pages": {"404": "/index.html", "500": "/index.html"}
+        }"#;
+        let ws_resources =
+            serde_json::from_str::<WSResources>(data).expect("parsing should succeed");
+        let resource_paths = ["/index.html", "/about.html"]
+            .into_iter()
+            .map(String::from)
+            .collect();
+        let problems = ws_resources.validate(&resource_paths);
+        let errors = problems.iter().filter(|problem| problem.is_error).count();
+        let warnings = problems.len() - errors;
+        // The pattern in the headers, the conflicting header, and the missing route target.
+        assert_eq!(errors, 3);
+        // The route shadowed by a file, and the error page for status 500.
+        assert_eq!(warnings, 2);
+    }
+
+    #[test]
+    fn test_validate_remote_resources() {
+        let data = r#"{
+            "remote_resources": {
+                "/video.mp4": {"url": "https://cdn.example.com/video.mp4"},
+                "/index.html": {"url": "https://cdn.example.com/index.html"},
+                "/../secret": {"url": "file:///etc/secret"}
+            },
+            "headers": {"/video.mp4": {"Cache-Control": "no-cache"}}
+        }"#;
+        let ws_resources =
+            serde_json::from_str::<WSResources>(data).expect("parsing should succeed");
+        let resource_paths = ["/index.html"].into_iter().map(String::from).collect();
+        let problems = ws_resources.validate(&resource_paths);
+        // The path with `..`, the URL that is not HTTP, and the path that is also a file; the
+        // headers of the remote resource are valid.
+        assert_eq!(problems.len(), 3, "{problems:?}");
+    }
+
+    #[test]
+    fn test_merge_nested() {
+        let parse = |data: &str| serde_json::from_str::<WSResources>(data).unwrap();
+        let mut ws_resources = parse(
+            r#"{
+                "headers": {
+                    "/index.html": {"Cache-Control": "no-cache"},
+                    "/docs/index.html": {"Cache-Control": "no-cache", "X-Level": "root"}
+                },
+                "routes": {"/*": "/index.html", "/docs/api/*": "/index.html"},
+                "error_pages": {"404": "/404.html"}
+            }"#,
+        );
+        let docs = parse(
+            r#"{
+                "headers": {"/index.html": {"cache-control": "max-age=60"}},
+                "routes": {"/*": "/index.html"},
+                "content_types": {"/manual": "text/plain"}
+            }"#,
+        );
+        let api = parse(
+            r#"{
+                "headers": {"/index.html": {"X-Level": "api"}},
+                "routes": {"/*": "/index.html"},
+                "immutable": ["/v1.json"]
+            }"#,
+        );
+        ws_resources.merge_nested(docs, "/docs").unwrap();
+        ws_resources.merge_nested(api, "/docs/api").unwrap();
+
+        let headers = ws_resources.headers.as_ref().unwrap();
+        assert_eq!(
+            headers["/index.html"].0,
+            BTreeMap::from([("Cache-Control".to_owned(), "no-cache".to_owned())])
+        );
+        // The nearest file wins, whatever the case of the header names.
+        assert_eq!(
+            headers["/docs/index.html"].0,
+            BTreeMap::from([
+                ("X-Level".to_owned(), "root".to_owned()),
+                ("cache-control".to_owned(), "max-age=60".to_owned()),
+            ])
+        );
+        assert_eq!(
+            headers["/docs/api/index.html"].0,
+            BTreeMap::from([("X-Level".to_owned(), "api".to_owned())])
+        );
+        assert_eq!(
+            ws_resources.routes.as_ref().unwrap().0,
+            BTreeMap::from([
+                ("/*".to_owned(), "/index.html".to_owned()),
+                ("/docs/*".to_owned(), "/docs/index.html".to_owned()),
+                ("/docs/api/*".to_owned(), "/docs/api/index.html".to_owned()),
+            ])
+        );
+        assert_eq!(
+            ws_resources.content_types.as_ref().unwrap()["/docs/manual"],
+            "text/plain"
+        );
+        assert_eq!(
+            ws_resources.immutable,
+            Some(BTreeSet::from(["/docs/api/v1.json".to_owned()]))
+        );
+
+        // The error pages can only be set at the root.
+        let nested = parse(r#"{"error_pages": {"404": "/404.html"}}"#);
+        assert!(ws_resources.merge_nested(nested, "/docs").is_err());
+    }
+
+    #[test]
+    fn test_validate_order() {
+        let data = r#"{"order": ["/index.html", "/missing.js", "/index.html"]}"#;
+        let ws_resources =
+            serde_json::from_str::<WSResources>(data).expect("parsing should succeed");
+        let resource_paths = ["/index.html"].into_iter().map(String::from).collect();
+        let problems = ws_resources.validate(&resource_paths);
+        // The missing path and the duplicate path.
+        assert_eq!(problems.len(), 2, "{problems:?}");
+        assert!(problems.iter().all(|problem| !problem.is_error));
+    }
+
+    #[test]
+    fn test_validate_immutable() {
+        let data = r#"{"immutable": ["/legal.pdf", "/missing.pdf"]}"#;
+        let ws_resources =
+            serde_json::from_str::<WSResources>(data).expect("parsing should succeed");
+        let resource_paths = ["/legal.pdf"].into_iter().map(String::from).collect();
+        let problems = ws_resources.validate(&resource_paths);
+        assert_eq!(problems.len(), 1, "{problems:?}");
+        assert!(problems[0].is_error);
+    }
+
+    #[test]
+    fn test_validate_required() {
+        let data = r#"{
+            "required": ["/index.html", "/video.mp4", "/favicon.ico"],
+            "remote_resources": {"/video.mp4": {"url": "https://cdn.example.com/video.mp4"}}
+        }"#;
+        let ws_resources =
+            serde_json::from_str::<WSResources>(data).expect("parsing should succeed");
+        let resource_paths = ["/index.html"].into_iter().map(String::from).collect();
+        let problems = ws_resources.validate(&resource_paths);
+        // The remote resource is a resource of the site.
+        assert_eq!(problems.len(), 1, "{problems:?}");
+        assert!(problems[0].message.contains("/favicon.ico"));
+    }
+
+    #[test]
+    fn test_validate_tags() {
+        let data = r#"{
+            "tags": {
+                "images": ["/img/**", "/**/*.webp"],
+                "docs": ["docs/**"],
+                "empty": []
+            }
+        }"#;
+        let ws_resources =
+            serde_json::from_str::<WSResources>(data).expect("parsing should succeed");
+        let resource_paths = ["/img/logo.png"].into_iter().map(String::from).collect();
+        let problems = ws_resources.validate(&resource_paths);
+        let errors = problems.iter().filter(|problem| problem.is_error).count();
+        // The relative pattern and the tag without patterns.
+        assert_eq!(errors, 2, "{problems:?}");
+        // The pattern that matches no file.
+        assert_eq!(problems.len() - errors, 1, "{problems:?}");
+    }
+
+    #[test]
+    fn test_allowed_methods() {
+        let data = r#"{
+            "allowed_methods": {
+                "/**": [],
+                "/api/**": ["GET", "HEAD", "OPTIONS"],
+                "/form": ["post"],
+                "/upload/**": ["PUT"]
+            }
+        }"#;
+        let ws_resources =
+            serde_json::from_str::<WSResources>(data).expect("parsing should succeed");
+        assert_eq!(
+            ws_resources.allow_header("/index.html").as_deref(),
+            Some("GET, HEAD")
+        );
+        assert_eq!(
+            ws_resources.allow_header("/api/v1.json").as_deref(),
+            Some("GET, HEAD, OPTIONS")
+        );
+        assert_eq!(WSResources::default().allow_header("/index.html"), None);
+
+        let resource_paths = ["/index.html", "/api/v1.json"]
+            .into_iter()
+            .map(String::from)
+            .collect();
+        let problems = ws_resources.validate(&resource_paths);
+        let errors = problems.iter().filter(|problem| problem.is_error).count();
+        // The lowercase method.
+        assert_eq!(errors, 1, "{problems:?}");
+        // The patterns that match no file, and the methods without GET.
+        assert_eq!(problems.len() - errors, 4, "{problems:?}");
+    }
+
     #[test]
     fn test_read_ws_resources() {
         let header_data = format!("{{{}}}", HEADER_DATA);
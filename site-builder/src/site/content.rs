@@ -106,6 +106,7 @@ pub enum ContentType {
     FontTtf,
     TextPlain,
     ApplicationVndvisio,
+    ApplicationWasm,
     AudioWav,
     AudioWebm,
     VideoWebm,
@@ -194,6 +195,7 @@ impl ContentType {
             "ttf" => ContentType::FontTtf,
             "txt" => ContentType::TextPlain,
             "vsd" => ContentType::ApplicationVndvisio,
+            "wasm" => ContentType::ApplicationWasm,
             "wav" => ContentType::AudioWav,
             "weba" => ContentType::AudioWebm,
             "webm" => ContentType::VideoWebm,
@@ -215,6 +217,44 @@ impl ContentType {
             }
         })
     }
+
+    /// Detects the content type from the magic bytes at the start of the content, for the files
+    /// whose extension is missing or not recognized.
+    ///
+    /// Only a few common binary formats, and SVG and XML documents, are detected.
+    pub fn try_from_magic_bytes(content: &[u8]) -> Option<Self> {
+        const SIGNATURES: &[(&[u8], ContentType)] = &[
+            (b"\x89PNG\r\n\x1a\n", ContentType::ImagePng),
+            (b"\xff\xd8\xff", ContentType::ImageJpeg),
+            (b"GIF87a", ContentType::ImageGif),
+            (b"GIF89a", ContentType::ImageGif),
+            (b"\0asm", ContentType::ApplicationWasm),
+            (b"\x1f\x8b", ContentType::ApplicationGzip),
+            (b"%PDF-", ContentType::ApplicationPdf),
+            (b"wOFF", ContentType::FontWoff),
+            (b"wOF2", ContentType::FontWoff2),
+            (b"PK\x03\x04", ContentType::ApplicationZip),
+        ];
+        if let Some((_, content_type)) = SIGNATURES
+            .iter()
+            .find(|(signature, _)| content.starts_with(signature))
+        {
+            return Some(content_type.clone());
+        }
+        if content.len() >= 12 && content.starts_with(b"RIFF") && &content[8..12] == b"WEBP" {
+            return Some(ContentType::ImageWebp);
+        }
+        // The text formats may start with a byte order mark and whitespace.
+        let text = String::from_utf8_lossy(content);
+        let text = text.trim_start_matches('\u{feff}').trim_start();
+        if text.starts_with("<svg") || (text.starts_with("<?xml") && text.contains("<svg")) {
+            Some(ContentType::ImageSvgxml)
+        } else if text.starts_with("<?xml") {
+            Some(ContentType::ApplicationXml)
+        } else {
+            None
+        }
+    }
 }
 
 impl fmt::Display for ContentType {
@@ -295,6 +335,7 @@ impl fmt::Display for ContentType {
             ContentType::FontTtf => write!(f, "font/ttf"),
             ContentType::TextPlain => write!(f, "text/plain"),
             ContentType::ApplicationVndvisio => write!(f, "application/vnd.visio"),
+            ContentType::ApplicationWasm => write!(f, "application/wasm"),
             ContentType::AudioWav => write!(f, "audio/wav"),
             ContentType::AudioWebm => write!(f, "audio/webm"),
             ContentType::VideoWebm => write!(f, "video/webm"),
@@ -392,6 +433,7 @@ impl TryFrom<&str> for ContentType {
             "font/ttf" => Ok(ContentType::FontTtf),
             "text/plain" => Ok(ContentType::TextPlain),
             "application/vnd.visio" => Ok(ContentType::ApplicationVndvisio),
+            "application/wasm" => Ok(ContentType::ApplicationWasm),
             "audio/wav" => Ok(ContentType::AudioWav),
             "audio/webm" => Ok(ContentType::AudioWebm),
             "video/webm" => Ok(ContentType::VideoWebm),
@@ -419,3 +461,46 @@ impl TryFrom<String> for ContentType {
         Self::try_from(value.as_str())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_try_from_magic_bytes() {
+        let cases: &[(&[u8], Option<ContentType>)] = &[
+            (
+                b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR",
+                Some(ContentType::ImagePng),
+            ),
+            (b"\xff\xd8\xff\xe0\0\x10JFIF", Some(ContentType::ImageJpeg)),
+            (b"\0asm\x01\0\0\0", Some(ContentType::ApplicationWasm)),
+            (b"\x1f\x8b\x08\0", Some(ContentType::ApplicationGzip)),
+            (b"%PDF-1.7\n", Some(ContentType::ApplicationPdf)),
+            (b"wOF2\0\x01\0\0", Some(ContentType::FontWoff2)),
+            (b"RIFF\x24\0\0\0WEBPVP8 ", Some(ContentType::ImageWebp)),
+            (
+                b"<svg xmlns=\"http://www.w3.org/2000/svg\"></svg>",
+                Some(ContentType::ImageSvgxml),
+            ),
+            (
+                b"\xef\xbb\xbf<?xml version=\"1.0\"?>\n<svg></svg>",
+                Some(ContentType::ImageSvgxml),
+            ),
+            (
+                b"<?xml version=\"1.0\"?>\n<feed></feed>",
+                Some(ContentType::ApplicationXml),
+            ),
+            (b"RIFF\x24\0\0\0WAVEfmt ", None),
+            (b"plain text", None),
+            (b"", None),
+        ];
+        for (content, expected) in cases {
+            assert_eq!(
+                ContentType::try_from_magic_bytes(content),
+                *expected,
+                "{content:?}"
+            );
+        }
+    }
+}
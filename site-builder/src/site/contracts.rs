@@ -6,17 +6,26 @@
 
 use core::fmt;
 
-use anyhow::{anyhow, Context, Result};
+use anyhow::{anyhow, ensure, Context, Result};
 use move_core_types::{identifier::Identifier, language_storage::StructTag as MoveStructTag};
 use serde::de::DeserializeOwned;
 use sui_sdk::{
-    rpc_types::{SuiData, SuiObjectData, SuiObjectDataOptions, SuiObjectResponse},
+    rpc_types::{
+        ObjectChange,
+        SuiData,
+        SuiObjectData,
+        SuiObjectDataOptions,
+        SuiObjectResponse,
+        SuiTransactionBlockResponseOptions,
+    },
     types::base_types::ObjectID,
     SuiClient,
 };
-use sui_types::TypeTag;
+use sui_types::{move_package::UpgradeCap, TypeTag};
 use tracing::instrument;
 
+use crate::display;
+
 /// A trait for types that correspond to a contract type.
 ///
 /// Implementors of this trait are convertible from [SuiObjectData]s and can
@@ -156,6 +165,110 @@ where
     })
 }
 
+/// The latest version of the Walrus Sites package supported by the site builder.
+///
+/// Newer versions of the package may have changed the signatures of the functions called by the
+/// site builder.
+pub const SUPPORTED_PACKAGE_VERSION: u64 = 1;
+
+/// Checks that the latest version of the Walrus Sites package on chain is supported by the site
+/// builder.
+///
+/// If `warn_only` is set, a warning is displayed instead of returning an error.
+pub(crate) async fn check_package_version(
+    sui_client: &SuiClient,
+    package: ObjectID,
+    warn_only: bool,
+) -> Result<()> {
+    let (latest_package, version) = latest_package_version(sui_client, package).await?;
+    tracing::debug!(%package, %latest_package, version, "Walrus Sites package version");
+
+    let message = format!(
+        "the Walrus Sites package {package} was upgraded to version {version} \
+        ({latest_package}), but this site builder supports up to version \
+        {SUPPORTED_PACKAGE_VERSION}; please upgrade your site builder"
+    );
+    if warn_only {
+        if version > SUPPORTED_PACKAGE_VERSION {
+            display::warning(message);
+        }
+        return Ok(());
+    }
+    ensure!(version <= SUPPORTED_PACKAGE_VERSION, message);
+    Ok(())
+}
+
+/// Returns the ID and the version of the latest upgrade of the package.
+///
+/// Package objects are immutable, so the latest version is read from the `UpgradeCap` of the
+/// package, which is created by the publish transaction and updated by each upgrade. If the
+/// `UpgradeCap` cannot be found, e.g., because it was destroyed to make the package immutable,
+/// the version of the package itself is returned.
+async fn latest_package_version(
+    sui_client: &SuiClient,
+    package: ObjectID,
+) -> Result<(ObjectID, u64)> {
+    let package_data = sui_client
+        .read_api()
+        .get_object_with_options(
+            package,
+            SuiObjectDataOptions::new().with_previous_transaction(),
+        )
+        .await?
+        .data
+        .ok_or_else(|| anyhow!("could not find the Walrus Sites package {package} on chain"))?;
+    let package_version = package_data.version.value();
+    let Some(digest) = package_data.previous_transaction else {
+        return Ok((package, package_version));
+    };
+
+    let object_changes = sui_client
+        .read_api()
+        .get_transaction_with_options(
+            digest,
+            SuiTransactionBlockResponseOptions::new().with_object_changes(),
+        )
+        .await
+        .context(format!(
+            "could not read the transaction {digest} that published the package {package}"
+        ))?
+        .object_changes
+        .unwrap_or_default();
+    let upgrade_cap_id = object_changes.iter().find_map(|change| match change {
+        ObjectChange::Created {
+            object_type,
+            object_id,
+            ..
+        }
+        | ObjectChange::Mutated {
+            object_type,
+            object_id,
+            ..
+        } if *object_type == UpgradeCap::type_() => Some(*object_id),
+        _ => None,
+    });
+    let Some(upgrade_cap_id) = upgrade_cap_id else {
+        tracing::debug!(%package, "the package has no upgrade cap");
+        return Ok((package, package_version));
+    };
+
+    let upgrade_cap = sui_client
+        .read_api()
+        .get_object_with_options(upgrade_cap_id, SuiObjectDataOptions::new().with_bcs())
+        .await?;
+    let Some(bcs) = upgrade_cap
+        .data
+        .and_then(|data| data.bcs)
+        .and_then(|bcs| bcs.try_as_move().map(|raw| raw.bcs_bytes.clone()))
+    else {
+        tracing::debug!(%package, %upgrade_cap_id, "the upgrade cap of the package was destroyed");
+        return Ok((package, package_version));
+    };
+    let upgrade_cap: UpgradeCap = bcs::from_bytes(&bcs)
+        .context(format!("could not read the upgrade cap {upgrade_cap_id}"))?;
+    Ok((upgrade_cap.package.bytes, upgrade_cap.version))
+}
+
 macro_rules! contract_ident {
     (struct $modname:ident::$itemname:ident) => {
         #[allow(non_upper_case_globals)]
@@ -184,6 +297,8 @@ pub mod site {
     use super::*;
 
     contract_ident!(fn site::new_site, 1);
+    contract_ident!(fn site::update_name);
+    contract_ident!(struct site::Site);
     // Resource functions
     contract_ident!(struct site::Resource);
     contract_ident!(struct site::ResourcePath);
@@ -191,6 +306,7 @@ pub mod site {
     contract_ident!(fn site::new_resource);
     contract_ident!(fn site::add_resource);
     contract_ident!(fn site::add_header);
+    contract_ident!(fn site::move_resource);
     // Routes functions
     contract_ident!(struct site::Routes);
     contract_ident!(fn site::create_routes, 1);
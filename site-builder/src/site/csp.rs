@@ -0,0 +1,284 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Generation of the `Content-Security-Policy` header of the HTML pages, from the sources they
+//! reference.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use super::html::tags;
+
+/// The directives for which the sources are collected from the pages.
+const DIRECTIVES: [&str; 5] = [
+    "script-src",
+    "style-src",
+    "img-src",
+    "font-src",
+    "media-src",
+];
+
+/// The file extensions of the fonts, for the `<link rel="preload">` elements without an `as`.
+const FONT_EXTENSIONS: [&str; 4] = [".woff", ".woff2", ".ttf", ".otf"];
+
+/// How the `Content-Security-Policy` header of the HTML pages is generated.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CspGenerator {
+    /// Set the `Content-Security-Policy-Report-Only` header instead, which only reports the
+    /// violations.
+    pub report_only: bool,
+    /// The sources allowed in all the directives, in addition to the ones found in the page.
+    pub extra_sources: Vec<String>,
+}
+
+impl CspGenerator {
+    /// Returns the name of the header to set.
+    pub fn header_name(&self) -> &'static str {
+        if self.report_only {
+            "content-security-policy-report-only"
+        } else {
+            "content-security-policy"
+        }
+    }
+
+    /// Returns the policy for the HTML page.
+    ///
+    /// Everything is restricted to `'self'`, except for the scripts, stylesheets, images, fonts,
+    /// and audio and video, which can also be loaded from the origins referenced in the page and
+    /// from the extra sources. Inline scripts and styles are not allowed.
+    pub fn policy(&self, html: &str) -> String {
+        let mut sources: BTreeMap<&str, BTreeSet<String>> = DIRECTIVES
+            .iter()
+            .map(|directive| (*directive, BTreeSet::new()))
+            .collect();
+        for (directive, url) in referenced_urls(html) {
+            if let Some(source) = url_source(&url) {
+                sources
+                    .get_mut(directive)
+                    .expect("all the directives are initialized")
+                    .insert(source);
+            }
+        }
+
+        let mut policy = vec![format!(
+            "default-src {}",
+            self.with_extra_sources(BTreeSet::new())
+        )];
+        for directive in DIRECTIVES {
+            let directive_sources = sources.remove(directive).unwrap_or_default();
+            policy.push(format!(
+                "{directive} {}",
+                self.with_extra_sources(directive_sources)
+            ));
+        }
+        policy.push("object-src 'none'".to_owned());
+        policy.push("base-uri 'self'".to_owned());
+        policy.join("; ")
+    }
+
+    /// Joins `'self'`, the sources, and the extra sources.
+    fn with_extra_sources(&self, sources: BTreeSet<String>) -> String {
+        let mut all = vec!["'self'".to_owned()];
+        for source in sources
+            .into_iter()
+            .chain(self.extra_sources.iter().cloned())
+        {
+            if !all.contains(&source) {
+                all.push(source);
+            }
+        }
+        all.join(" ")
+    }
+}
+
+/// Returns the source that allows loading the URL, or `None` if it is allowed by `'self'`.
+///
+/// Absolute URLs are allowed by their origin, and `data:` URLs by the scheme.
+fn url_source(url: &str) -> Option<String> {
+    let url = url.trim();
+    let lowercase = url.to_ascii_lowercase();
+    if lowercase.starts_with("data:") {
+        return Some("data:".to_owned());
+    }
+    let (scheme, rest) = if let Some(rest) = lowercase.strip_prefix("https://") {
+        ("https://", rest)
+    } else if let Some(rest) = lowercase.strip_prefix("http://") {
+        ("http://", rest)
+    } else if let Some(rest) = lowercase.strip_prefix("//") {
+        ("https://", rest)
+    } else {
+        return None;
+    };
+    let host = rest
+        .split(['/', '?', '#'])
+        .next()
+        .filter(|host| !host.is_empty())?;
+    Some(format!("{scheme}{host}"))
+}
+
+/// Returns the URLs of the scripts, stylesheets, images, fonts, and media referenced in the HTML,
+/// with the directive that governs them.
+///
+/// The `<source>` elements are images in a `<picture>`, with a `srcset`, and media in a `<video>`
+/// or an `<audio>`, with a `src`.
+fn referenced_urls(html: &str) -> Vec<(&'static str, String)> {
+    let mut urls = vec![];
+    for (name, attributes) in tags(html) {
+        let attribute = |key: &str| attributes.get(key).cloned();
+        let srcset = || {
+            attribute("srcset")
+                .map(|srcset| srcset_urls(&srcset))
+                .unwrap_or_default()
+                .into_iter()
+                .map(|url| ("img-src", url))
+        };
+        match name.as_str() {
+            "script" => urls.extend(attribute("src").map(|src| ("script-src", src))),
+            "img" => {
+                urls.extend(attribute("src").map(|src| ("img-src", src)));
+                urls.extend(srcset());
+            }
+            "source" => {
+                urls.extend(attribute("src").map(|src| ("media-src", src)));
+                urls.extend(srcset());
+            }
+            "video" | "audio" | "track" => {
+                urls.extend(attribute("src").map(|src| ("media-src", src)));
+                urls.extend(attribute("poster").map(|poster| ("img-src", poster)));
+            }
+            "link" => {
+                let Some(href) = attribute("href") else {
+                    continue;
+                };
+                let rel = attribute("rel").unwrap_or_default().to_ascii_lowercase();
+                let kind = attribute("as").unwrap_or_default().to_ascii_lowercase();
+                let is_font = kind == "font"
+                    || FONT_EXTENSIONS
+                        .iter()
+                        .any(|extension| href.to_ascii_lowercase().ends_with(extension));
+                if rel.contains("stylesheet") || kind == "style" {
+                    urls.push(("style-src", href));
+                } else if is_font {
+                    urls.push(("font-src", href));
+                } else if rel.contains("icon") || kind == "image" {
+                    urls.push(("img-src", href));
+                } else if kind == "script" || rel.contains("modulepreload") {
+                    urls.push(("script-src", href));
+                }
+            }
+            _ => (),
+        }
+    }
+    urls
+}
+
+/// Returns the URLs of the candidates of a `srcset` attribute, e.g., `a.png 1x, b.png 2x`.
+fn srcset_urls(srcset: &str) -> Vec<String> {
+    srcset
+        .split(',')
+        .filter_map(|candidate| candidate.split_whitespace().next())
+        .map(str::to_owned)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PAGE: &str = r#"<!DOCTYPE html>
+<html>
+  <head>
+    <!-- <script src="https://commented.example.com/x.js"></script> -->
+    <link rel="stylesheet" href="https://fonts.googleapis.com/css2?family=Inter">
+    <link rel="preload" href="https://fonts.gstatic.com/inter.woff2" crossorigin>
+    <link rel=icon href="/favicon.ico">
+    <script src='https://cdn.example.com/app.js' defer></script>
+    <script src="/local.js"></script>
+  </head>
+  <body>
+    <img alt="a > b" src="//images.example.com/a.png">
+    <img src="data:image/png;base64,AAAA">
+  </body>
+</html>"#;
+
+    #[test]
+    fn test_referenced_urls() {
+        let urls = referenced_urls(PAGE);
+        assert_eq!(urls.len(), 7);
+        assert_eq!(
+            urls[0],
+            (
+                "style-src",
+                "https://fonts.googleapis.com/css2?family=Inter".to_owned()
+            )
+        );
+        assert_eq!(urls[1].0, "font-src");
+        assert_eq!(urls[4], ("script-src", "/local.js".to_owned()));
+        assert_eq!(urls[5].1, "//images.example.com/a.png");
+    }
+
+    #[test]
+    fn test_media_urls() {
+        let urls = referenced_urls(
+            r#"<picture>
+  <source srcset="https://a.example.com/1.webp 1x, https://b.example.com/2.webp 2x">
+  <img src="/1.png" srcset="/2.png 2x,https://c.example.com/3.png 3x">
+</picture>
+<video src="https://media.example.com/a.mp4" poster="https://d.example.com/a.jpg">
+  <source src="https://media.example.com/a.webm" type="video/webm">
+  <track src="/captions.vtt">
+</video>
+<audio src="https://audio.example.com/a.mp3"></audio>"#,
+        );
+        assert_eq!(
+            urls,
+            vec![
+                ("img-src", "https://a.example.com/1.webp".to_owned()),
+                ("img-src", "https://b.example.com/2.webp".to_owned()),
+                ("img-src", "/1.png".to_owned()),
+                ("img-src", "/2.png".to_owned()),
+                ("img-src", "https://c.example.com/3.png".to_owned()),
+                ("media-src", "https://media.example.com/a.mp4".to_owned()),
+                ("img-src", "https://d.example.com/a.jpg".to_owned()),
+                ("media-src", "https://media.example.com/a.webm".to_owned()),
+                ("media-src", "/captions.vtt".to_owned()),
+                ("media-src", "https://audio.example.com/a.mp3".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_url_source() {
+        assert_eq!(
+            url_source("https://CDN.example.com:8443/app.js?v=1").as_deref(),
+            Some("https://cdn.example.com:8443")
+        );
+        assert_eq!(url_source("/local.js"), None);
+        assert_eq!(url_source("img/a.png"), None);
+        assert_eq!(
+            url_source("data:image/png;base64,A").as_deref(),
+            Some("data:")
+        );
+    }
+
+    #[test]
+    fn test_policy() {
+        let generator = CspGenerator {
+            report_only: true,
+            extra_sources: vec!["https://analytics.example.com".to_owned()],
+        };
+        assert_eq!(
+            generator.policy(PAGE),
+            "default-src 'self' https://analytics.example.com; \
+            script-src 'self' https://cdn.example.com https://analytics.example.com; \
+            style-src 'self' https://fonts.googleapis.com https://analytics.example.com; \
+            img-src 'self' data: https://images.example.com https://analytics.example.com; \
+            font-src 'self' https://fonts.gstatic.com https://analytics.example.com; \
+            media-src 'self' https://analytics.example.com; \
+            object-src 'none'; base-uri 'self'"
+        );
+        assert_eq!(
+            generator.header_name(),
+            "content-security-policy-report-only"
+        );
+    }
+}
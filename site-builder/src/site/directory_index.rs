@@ -0,0 +1,160 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Aliases of the directory indices, to serve `/about/index.html` at `/about/` and `/about`.
+//!
+//! The portal serves `/` with `/index.html`, and strips the trailing slash of any other path:
+//! both `/about/` and `/about` are looked up as the resource `/about`, then matched against the
+//! routes. Nothing serves `/about/index.html` at these paths, unless an alias is added:
+//!
+//! - [`DirectoryIndex::Alias`] adds the resource `/about`, with the same blob and headers as
+//!   `/about/index.html`. The blob is only stored once, as the resources share its content.
+//! - [`DirectoryIndex::Route`] adds the route `/about` to `/about/index.html`.
+//!
+//! The aliases are never added over an existing resource or route at the same path. The portal
+//! has no redirects, so the aliased pages are served at both URLs: relative links in them resolve
+//! against the directory only at `/about/`.
+
+use clap::ValueEnum;
+
+use super::{
+    resource::{Resource, ResourceSet},
+    SiteData,
+};
+use crate::types::Routes;
+
+/// The name of the index file of a directory.
+const INDEX_FILE: &str = "index.html";
+
+/// How the directory indices of the site are served at the path of their directory.
+#[derive(Debug, ValueEnum, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DirectoryIndex {
+    /// Only serve the directory indices at their own path.
+    #[default]
+    None,
+    /// Add a resource at the path of the directory, with the same blob as the index.
+    Alias,
+    /// Add a route from the path of the directory to the index.
+    Route,
+}
+
+impl DirectoryIndex {
+    /// Returns the site with the aliases of the directory indices added.
+    pub fn apply(self, site: SiteData) -> SiteData {
+        if self == DirectoryIndex::None {
+            return site;
+        }
+        let resources = &site.resources().inner;
+        let mut routes = site.routes().cloned();
+        let is_taken = |path: &str, routes: &Option<Routes>| {
+            resources.iter().any(|resource| resource.info.path == path)
+                || routes
+                    .as_ref()
+                    .is_some_and(|routes| routes.0.contains_key(path))
+        };
+        let mut aliases = vec![];
+        for resource in resources.iter() {
+            let Some(directory) = directory_of_index(&resource.info.path) else {
+                continue;
+            };
+            if is_taken(directory, &routes) {
+                tracing::debug!(directory, "not aliasing the index over an existing path");
+                continue;
+            }
+            match self {
+                DirectoryIndex::Alias => {
+                    let mut alias = resource.clone();
+                    alias.info.path = directory.to_owned();
+                    aliases.push(alias);
+                }
+                DirectoryIndex::Route => {
+                    routes
+                        .get_or_insert_with(Routes::empty)
+                        .0
+                        .insert(directory.to_owned(), resource.info.path.clone());
+                }
+                DirectoryIndex::None => unreachable!("returned above"),
+            }
+        }
+        SiteData::new(
+            ResourceSet::from_iter(resources.iter().cloned().chain(aliases)),
+            routes,
+        )
+    }
+}
+
+/// Returns the directory of the resource path, if it is the index of a directory other than the
+/// root, e.g., `/about` for `/about/index.html`.
+fn directory_of_index(path: &str) -> Option<&str> {
+    path.strip_suffix(INDEX_FILE)?
+        .strip_suffix('/')
+        .filter(|directory| !directory.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::BTreeMap, path::PathBuf};
+
+    use move_core_types::u256::U256;
+
+    use super::*;
+    use crate::{types::HttpHeaders, walrus::types::BlobId};
+
+    fn resource(path: &str) -> Resource {
+        Resource::new(
+            path.to_owned(),
+            PathBuf::from(path),
+            HttpHeaders(BTreeMap::new()),
+            BlobId([0; BlobId::LENGTH]),
+            U256::zero(),
+            0,
+        )
+    }
+
+    fn paths(site: &SiteData) -> Vec<&str> {
+        site.resources()
+            .inner
+            .iter()
+            .map(|resource| resource.info.path.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn test_directory_of_index() {
+        assert_eq!(directory_of_index("/about/index.html"), Some("/about"));
+        assert_eq!(directory_of_index("/a/b/index.html"), Some("/a/b"));
+        assert_eq!(directory_of_index("/index.html"), None);
+        assert_eq!(directory_of_index("/about/myindex.html"), None);
+    }
+
+    #[test]
+    fn test_apply() {
+        let site = SiteData::new(
+            ResourceSet::from_iter([
+                resource("/index.html"),
+                resource("/about/index.html"),
+                resource("/docs/index.html"),
+                resource("/docs"),
+            ]),
+            None,
+        );
+        let aliased = DirectoryIndex::Alias.apply(site.clone());
+        assert_eq!(
+            paths(&aliased),
+            vec![
+                "/about",
+                "/about/index.html",
+                "/docs",
+                "/docs/index.html",
+                "/index.html"
+            ]
+        );
+        let routed = DirectoryIndex::Route.apply(site.clone());
+        assert_eq!(paths(&routed), paths(&site));
+        assert_eq!(
+            routed.routes().unwrap().0,
+            BTreeMap::from([("/about".to_owned(), "/about/index.html".to_owned())])
+        );
+        assert_eq!(DirectoryIndex::None.apply(site.clone()), site);
+    }
+}
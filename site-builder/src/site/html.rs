@@ -0,0 +1,101 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Minimal scanning of HTML pages, to find the resources they reference.
+
+use std::collections::BTreeMap;
+
+/// Returns the lowercase names and the attributes of the opening tags in the HTML.
+///
+/// This is not a full HTML parser, but is enough to extract the attributes of the elements that
+/// load resources. Comments are skipped.
+pub(crate) fn tags(html: &str) -> Vec<(String, BTreeMap<String, String>)> {
+    let mut tags = vec![];
+    let mut rest = html;
+    while let Some(start) = rest.find('<') {
+        rest = &rest[start + 1..];
+        if let Some(comment) = rest.strip_prefix("!--") {
+            rest = comment.find("-->").map_or("", |end| &comment[end + 3..]);
+            continue;
+        }
+        let end = tag_end(rest);
+        let tag = &rest[..end];
+        rest = &rest[end..];
+
+        let name_end = tag
+            .find(|c: char| c.is_whitespace() || c == '/' || c == '>')
+            .unwrap_or(tag.len());
+        let name = tag[..name_end].to_ascii_lowercase();
+        if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric()) {
+            continue;
+        }
+        tags.push((name, attributes(&tag[name_end..])));
+    }
+    tags
+}
+
+/// Returns the index of the `>` that closes the tag, ignoring the ones in quoted values.
+pub(crate) fn tag_end(tag: &str) -> usize {
+    let mut quote = None;
+    for (index, c) in tag.char_indices() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(open), _) if c == open => quote = None,
+            (None, '>') => return index,
+            _ => (),
+        }
+    }
+    tag.len()
+}
+
+/// Parses the attributes of a tag, with lowercase names.
+fn attributes(mut input: &str) -> BTreeMap<String, String> {
+    let mut attributes = BTreeMap::new();
+    loop {
+        input = input.trim_start_matches(|c: char| c.is_whitespace() || c == '/');
+        let name_end = input
+            .find(|c: char| c.is_whitespace() || c == '=' || c == '/')
+            .unwrap_or(input.len());
+        if name_end == 0 {
+            return attributes;
+        }
+        let name = input[..name_end].to_ascii_lowercase();
+        input = input[name_end..].trim_start();
+        let Some(value_start) = input.strip_prefix('=') else {
+            attributes.insert(name, String::new());
+            continue;
+        };
+        let value_start = value_start.trim_start();
+        let (value, after) = match value_start.chars().next() {
+            Some(quote @ ('"' | '\'')) => {
+                let value = &value_start[1..];
+                let end = value.find(quote).unwrap_or(value.len());
+                (&value[..end], value.get(end + 1..).unwrap_or_default())
+            }
+            _ => {
+                let end = value_start
+                    .find(char::is_whitespace)
+                    .unwrap_or(value_start.len());
+                (&value_start[..end], &value_start[end..])
+            }
+        };
+        attributes.insert(name, value.to_owned());
+        input = after;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tags() {
+        let tags = tags(r#"<!-- <a href="x"> --><IMG alt="a > b" SRC=logo.png hidden/><p>"#);
+        assert_eq!(tags.len(), 2);
+        assert_eq!(tags[0].0, "img");
+        assert_eq!(tags[0].1["alt"], "a > b");
+        assert_eq!(tags[0].1["src"], "logo.png");
+        assert_eq!(tags[0].1["hidden"], "");
+        assert_eq!(tags[1].0, "p");
+    }
+}
@@ -0,0 +1,288 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Checks of the references in the HTML pages and stylesheets of a site.
+
+use std::collections::BTreeSet;
+
+use super::{html::tags, SiteData};
+
+/// The attributes that reference other resources.
+const LINK_ATTRIBUTES: [&str; 4] = ["href", "src", "poster", "data"];
+
+/// A reference in a file of the site that does not resolve to a published resource.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct DanglingReference {
+    /// The resource path of the file containing the reference.
+    pub file: String,
+    /// The reference, as written in the file.
+    pub reference: String,
+    /// Why the reference does not resolve.
+    pub reason: &'static str,
+}
+
+/// Finds the references in the HTML pages and stylesheets of the site that will not resolve.
+///
+/// The references are resolved against the resource paths and the routes of the site, as done by
+/// the portal; references to other origins are not checked, except for the development URLs
+/// (`file://`, and `localhost`) that are always dangling once published.
+pub fn dangling_references(site: &SiteData) -> Vec<DanglingReference> {
+    let paths = site
+        .resources()
+        .inner
+        .iter()
+        .map(|resource| resource.info.path.as_str())
+        .collect::<BTreeSet<_>>();
+    let mut dangling = BTreeSet::new();
+    for resource in site.resources().inner.iter() {
+        let content_type = resource
+            .info
+            .headers
+            .0
+            .get("content-type")
+            .map(String::as_str)
+            .unwrap_or_default();
+        let references = if content_type.starts_with("text/html") {
+            html_references
+        } else if content_type.starts_with("text/css") {
+            css_references
+        } else {
+            continue;
+        };
+        let Ok(content) = std::fs::read_to_string(&resource.full_path) else {
+            tracing::debug!(path=?resource.full_path, "skipping the file that is not UTF-8");
+            continue;
+        };
+        for reference in references(&content) {
+            let reason = match resolve(&resource.info.path, &reference) {
+                Reference::Development => "development URL",
+                Reference::Local(path) if !is_served(site, &paths, &path) => "no such resource",
+                _ => continue,
+            };
+            dangling.insert(DanglingReference {
+                file: resource.info.path.clone(),
+                reference,
+                reason,
+            });
+        }
+    }
+    dangling.into_iter().collect()
+}
+
+/// Returns the resources of the site referenced by the HTML page at the resource path `file`,
+/// e.g., its scripts, stylesheets, and images, in the order in which they first appear.
+pub fn page_resources(html: &str, file: &str, paths: &BTreeSet<&str>) -> Vec<String> {
+    let mut seen = BTreeSet::new();
+    html_references(html)
+        .into_iter()
+        .filter_map(|reference| match resolve(file, &reference) {
+            Reference::Local(path) if paths.contains(path.as_str()) => Some(path),
+            _ => None,
+        })
+        .filter(|path| seen.insert(path.clone()))
+        .collect()
+}
+
+/// Returns the references to other resources in the HTML page.
+fn html_references(html: &str) -> Vec<String> {
+    tags(html)
+        .into_iter()
+        .flat_map(|(_, attributes)| {
+            LINK_ATTRIBUTES
+                .iter()
+                .filter_map(|name| attributes.get(*name).cloned())
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+/// Returns the references in the `url(...)` and `@import` of the stylesheet.
+fn css_references(css: &str) -> Vec<String> {
+    let mut references = vec![];
+    let mut rest = css;
+    while let Some(start) = rest.find("url(") {
+        rest = &rest[start + 4..];
+        let end = rest.find(')').unwrap_or(rest.len());
+        references.push(
+            rest[..end]
+                .trim()
+                .trim_matches(|c| c == '"' || c == '\'')
+                .to_owned(),
+        );
+        rest = &rest[end..];
+    }
+    let mut rest = css;
+    while let Some(start) = rest.find("@import") {
+        rest = rest[start + 7..].trim_start();
+        if let Some(quote @ ('"' | '\'')) = rest.chars().next() {
+            let value = &rest[1..];
+            let end = value.find(quote).unwrap_or(value.len());
+            references.push(value[..end].to_owned());
+        }
+    }
+    references
+}
+
+/// A reference, resolved relative to the file containing it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Reference {
+    /// A path on the site.
+    Local(String),
+    /// A URL that only resolves on the machine of the developer.
+    Development,
+    /// A URL on another origin, or that does not load a resource (e.g., `mailto:`).
+    External,
+}
+
+/// Resolves the reference found in the file at the resource path.
+fn resolve(file: &str, reference: &str) -> Reference {
+    let reference = reference.trim();
+    let lowercase = reference.to_ascii_lowercase();
+    if lowercase.starts_with("file:") {
+        return Reference::Development;
+    }
+    if let Some(rest) = ["http://", "https://", "//"]
+        .iter()
+        .find_map(|scheme| lowercase.strip_prefix(scheme))
+    {
+        let host = rest.split(['/', '?', '#']).next().unwrap_or_default();
+        let hostname = host.rsplit_once(':').map_or(host, |(hostname, _)| hostname);
+        return if ["localhost", "127.0.0.1", "0.0.0.0", "[::1]"].contains(&hostname) {
+            Reference::Development
+        } else {
+            Reference::External
+        };
+    }
+    // Fragments, other schemes (`mailto:`, `data:`, ...), and template placeholders.
+    let has_scheme = reference
+        .split_once(':')
+        .is_some_and(|(scheme, _)| !scheme.contains('/'));
+    if reference.is_empty() || reference.starts_with('#') || has_scheme || reference.contains("{{")
+    {
+        return Reference::External;
+    }
+
+    let path = reference
+        .split(['?', '#'])
+        .next()
+        .expect("split returns at least one item");
+    let joined = if path.starts_with('/') {
+        path.to_owned()
+    } else {
+        let directory = file.rsplit_once('/').map_or("", |(directory, _)| directory);
+        format!("{directory}/{path}")
+    };
+    Reference::Local(normalize(&joined))
+}
+
+/// Removes the `.` and `..` segments of the path.
+fn normalize(path: &str) -> String {
+    let mut segments: Vec<&str> = vec![];
+    for segment in path.split('/').skip(1) {
+        match segment {
+            "." => (),
+            ".." => {
+                segments.pop();
+            }
+            _ => segments.push(segment),
+        }
+    }
+    format!("/{}", segments.join("/"))
+}
+
+/// Checks if the portal serves a resource at the path.
+///
+/// As in the portal, `/` is served by `/index.html`, a trailing `/` is ignored, and the paths
+/// that do not match a resource are matched against the routes.
+fn is_served(site: &SiteData, paths: &BTreeSet<&str>, path: &str) -> bool {
+    let path = if path == "/" {
+        "/index.html"
+    } else {
+        path.strip_suffix('/').unwrap_or(path)
+    };
+    paths.contains(path)
+        || site
+            .routes()
+            .is_some_and(|routes| routes.0.keys().any(|pattern| route_matches(pattern, path)))
+}
+
+/// Checks if the path matches the route pattern, where the first `*` matches any string.
+fn route_matches(pattern: &str, path: &str) -> bool {
+    match pattern.split_once('*') {
+        Some((prefix, suffix)) => {
+            path.len() >= prefix.len() + suffix.len()
+                && path.starts_with(prefix)
+                && path.ends_with(suffix)
+        }
+        None => pattern == path,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_page_resources() {
+        let paths = BTreeSet::from(["/index.html", "/app.js", "/img/logo.png", "/style.css"]);
+        let html = r#"<link href="style.css"><script src="/app.js"></script>
+            <img src="./img/logo.png"><img src="img/logo.png"><a href="/missing.html">
+            <a href="https://example.com/">"#;
+        assert_eq!(
+            page_resources(html, "/index.html", &paths),
+            vec!["/style.css", "/app.js", "/img/logo.png"]
+        );
+    }
+
+    #[test]
+    fn test_resolve() {
+        let local = |path: &str| Reference::Local(path.to_owned());
+        assert_eq!(
+            resolve("/index.html", "/assets/app.js"),
+            local("/assets/app.js")
+        );
+        assert_eq!(
+            resolve("/docs/a.html", "../img/b.png?v=2"),
+            local("/img/b.png")
+        );
+        assert_eq!(
+            resolve("/docs/a.html", "./c.html#top"),
+            local("/docs/c.html")
+        );
+        assert_eq!(
+            resolve("/index.html", "http://localhost:3000/app.js"),
+            Reference::Development
+        );
+        assert_eq!(
+            resolve("/index.html", "file:///home/me/site/app.js"),
+            Reference::Development
+        );
+        assert_eq!(
+            resolve("/index.html", "https://example.com/app.js"),
+            Reference::External
+        );
+        assert_eq!(
+            resolve("/index.html", "mailto:me@example.com"),
+            Reference::External
+        );
+        assert_eq!(resolve("/index.html", "#section"), Reference::External);
+    }
+
+    #[test]
+    fn test_css_references() {
+        let css = r#"@import "base.css"; body { background: url('/img/bg.png'); }
+            @font-face { src: url(fonts/a.woff2) format("woff2"); }"#;
+        assert_eq!(
+            css_references(css),
+            vec!["/img/bg.png", "fonts/a.woff2", "base.css"]
+        );
+    }
+
+    #[test]
+    fn test_route_matches() {
+        assert!(route_matches("/*", "/any/path"));
+        assert!(route_matches("/docs/*.html", "/docs/a.html"));
+        assert!(!route_matches("/docs/*.html", "/blog/a.html"));
+        assert!(route_matches("/about", "/about"));
+    }
+}
@@ -0,0 +1,303 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Lockfiles recording the content of the resources of a site, to review it in version control.
+//!
+//! The lockfile is a JSON object mapping each resource path to its blob ID and to the hex SHA-256
+//! digest of its content, in path order, so that it only changes when the content changes:
+//!
+//! ```json
+//! {
+//!   "lockVersion": 1,
+//!   "resources": {
+//!     "/index.html": { "blobId": "…", "sha256": "…" }
+//!   }
+//! }
+//! ```
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::Path,
+};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use super::{resource::hash_to_hex, SiteData};
+use crate::util::ResourceSelection;
+
+/// The default name of the lockfile.
+pub const DEFAULT_LOCKFILE: &str = "walrus.lock";
+
+/// The version of the lockfile format.
+const LOCK_VERSION: u32 = 1;
+
+/// The content of the resources of a site.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct SiteLock {
+    lock_version: u32,
+    resources: BTreeMap<String, LockedResource>,
+}
+
+/// The content of a resource.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+struct LockedResource {
+    blob_id: String,
+    sha256: String,
+}
+
+impl From<&SiteData> for SiteLock {
+    fn from(site: &SiteData) -> Self {
+        Self {
+            lock_version: LOCK_VERSION,
+            resources: site
+                .resources()
+                .inner
+                .iter()
+                .map(|resource| {
+                    (
+                        resource.info.path.clone(),
+                        LockedResource {
+                            blob_id: resource.info.blob_id.to_string(),
+                            sha256: hash_to_hex(&resource.info.blob_hash),
+                        },
+                    )
+                })
+                .collect(),
+        }
+    }
+}
+
+impl SiteLock {
+    /// Reads the lockfile.
+    pub fn read(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .context(format!("unable to read the lockfile {}", path.display()))?;
+        serde_json::from_str(&contents)
+            .context(format!("unable to parse the lockfile {}", path.display()))
+    }
+
+    /// Writes the lockfile, ending with a newline.
+    pub fn write(&self, path: &Path) -> Result<()> {
+        std::fs::write(path, serde_json::to_string_pretty(self)? + "\n")
+            .context(format!("unable to write the lockfile {}", path.display()))
+    }
+
+    /// Returns the lock of the resources at the paths matching the selection.
+    pub fn selected(&self, selection: &ResourceSelection) -> Self {
+        Self {
+            lock_version: self.lock_version,
+            resources: self
+                .resources
+                .iter()
+                .filter(|(path, _)| selection.matches(path))
+                .map(|(path, resource)| (path.clone(), resource.clone()))
+                .collect(),
+        }
+    }
+
+    /// Returns the lock with the resources matching the selection replaced by the ones of
+    /// `selected`, e.g., after a publish limited to the selection, which leaves the other
+    /// resources of the site unchanged.
+    pub fn with_selected(mut self, selected: SiteLock, selection: &ResourceSelection) -> Self {
+        self.lock_version = LOCK_VERSION;
+        self.resources.retain(|path, _| !selection.matches(path));
+        self.resources.extend(
+            selected
+                .resources
+                .into_iter()
+                .filter(|(path, _)| selection.matches(path)),
+        );
+        self
+    }
+
+    /// Returns the paths of the locked resources, in order.
+    pub fn paths(&self) -> impl Iterator<Item = &String> {
+        self.resources.keys()
+    }
+
+    /// Returns the description of the resources that differ from the other lock, in path order.
+    pub fn differences(&self, other: &SiteLock) -> Vec<String> {
+        differences(&self.resources, &other.resources)
+    }
+
+    /// Returns the description of the local resources that differ from the lock, in path order.
+    ///
+    /// Only the SHA-256 digests of the contents are compared, as computing the blob IDs requires
+    /// the Walrus CLI. The resources locked at the `ignored` paths are not compared.
+    pub fn content_differences(
+        &self,
+        local: &BTreeMap<String, String>,
+        ignored: &BTreeSet<String>,
+    ) -> Vec<String> {
+        let locked = self
+            .resources
+            .iter()
+            .filter(|(path, _)| !ignored.contains(*path))
+            .map(|(path, resource)| (path.clone(), resource.sha256.clone()))
+            .collect();
+        differences(local, &locked)
+    }
+
+    /// Returns the description of the resources of the site on chain that drifted from the lock,
+    /// in path order.
+    ///
+    /// Both the blob IDs and the content digests are compared: a resource pointed to another blob
+    /// has a different blob ID, and a resource whose `blob_hash` was edited under the same blob ID
+    /// has a different digest.
+    pub fn chain_differences(&self, on_chain: &SiteLock) -> Vec<String> {
+        let mut differences = vec![];
+        for (path, resource) in on_chain.resources.iter() {
+            match self.resources.get(path) {
+                None => differences.push(format!("{path} is on chain but not in the lockfile")),
+                Some(locked) if locked.blob_id != resource.blob_id => differences.push(format!(
+                    "{path} has the blob ID {} on chain, {} in the lockfile",
+                    resource.blob_id, locked.blob_id
+                )),
+                Some(locked) if locked.sha256 != resource.sha256 => differences.push(format!(
+                    "{path} has the SHA-256 {} on chain, {} in the lockfile",
+                    resource.sha256, locked.sha256
+                )),
+                Some(_) => (),
+            }
+        }
+        differences.extend(
+            self.resources
+                .keys()
+                .filter(|path| !on_chain.resources.contains_key(*path))
+                .map(|path| format!("{path} is in the lockfile but not on chain")),
+        );
+        differences.sort();
+        differences
+    }
+}
+
+/// Returns the description of the entries of `local` that differ from `locked`, in path order.
+fn differences<T: PartialEq>(
+    local: &BTreeMap<String, T>,
+    locked: &BTreeMap<String, T>,
+) -> Vec<String> {
+    let mut differences = vec![];
+    for (path, resource) in local.iter() {
+        match locked.get(path) {
+            None => differences.push(format!("{path} was added")),
+            Some(locked) if locked != resource => {
+                differences.push(format!("{path} changed"));
+            }
+            Some(_) => (),
+        }
+    }
+    differences.extend(
+        locked
+            .keys()
+            .filter(|path| !local.contains_key(*path))
+            .map(|path| format!("{path} was removed")),
+    );
+    differences.sort();
+    differences
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use move_core_types::u256::U256;
+
+    use super::*;
+    use crate::{
+        site::resource::{Resource, ResourceSet},
+        types::HttpHeaders,
+        walrus::types::BlobId,
+    };
+
+    fn lock(resources: &[(&str, u8)]) -> SiteLock {
+        let site = SiteData::new(
+            ResourceSet::from_iter(resources.iter().map(|(path, content)| {
+                Resource::new(
+                    path.to_string(),
+                    PathBuf::from(path),
+                    HttpHeaders(Default::default()),
+                    BlobId([*content; BlobId::LENGTH]),
+                    U256::from(*content),
+                    0,
+                )
+            })),
+            None,
+        );
+        SiteLock::from(&site)
+    }
+
+    #[test]
+    fn test_differences() {
+        let locked = lock(&[("/a.html", 1), ("/b.html", 2), ("/c.html", 3)]);
+        assert!(locked.differences(&locked.clone()).is_empty());
+        let local = lock(&[("/a.html", 1), ("/b.html", 4), ("/d.html", 5)]);
+        assert_eq!(
+            local.differences(&locked),
+            vec![
+                "/b.html changed",
+                "/c.html was removed",
+                "/d.html was added"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_with_selected() {
+        let locked = lock(&[("/a.html", 1), ("/css/a.css", 2), ("/css/b.css", 3)]);
+        let selection = ResourceSelection::Glob("/css/**".to_owned());
+        assert_eq!(
+            locked.selected(&selection),
+            lock(&[("/css/a.css", 2), ("/css/b.css", 3)])
+        );
+        // The selected resources that were not published are removed; the others are kept.
+        let published = lock(&[("/css/a.css", 4), ("/css/c.css", 5)]);
+        assert_eq!(
+            locked.with_selected(published, &selection),
+            lock(&[("/a.html", 1), ("/css/a.css", 4), ("/css/c.css", 5)])
+        );
+    }
+
+    #[test]
+    fn test_content_differences() {
+        let locked = lock(&[("/a.html", 1), ("/b.html", 2), ("/remote.js", 3)]);
+        let local = BTreeMap::from([
+            ("/a.html".to_owned(), hash_to_hex(&U256::from(1u8))),
+            ("/b.html".to_owned(), hash_to_hex(&U256::from(4u8))),
+        ]);
+        let ignored = BTreeSet::from(["/remote.js".to_owned()]);
+        assert_eq!(
+            locked.content_differences(&local, &ignored),
+            vec!["/b.html changed"]
+        );
+        assert_eq!(
+            locked.content_differences(&local, &BTreeSet::new()),
+            vec!["/b.html changed", "/remote.js was removed"]
+        );
+    }
+
+    #[test]
+    fn test_chain_differences() {
+        let locked = lock(&[("/a.html", 1), ("/b.html", 2), ("/c.html", 3)]);
+        assert!(locked.chain_differences(&locked.clone()).is_empty());
+        let mut on_chain = lock(&[("/a.html", 1), ("/b.html", 4), ("/d.html", 5)]);
+        on_chain
+            .resources
+            .get_mut("/a.html")
+            .expect("the resource is locked")
+            .sha256 = "00".to_owned();
+        let differences = locked.chain_differences(&on_chain);
+        assert_eq!(differences.len(), 4);
+        assert!(differences[0].starts_with("/a.html has the SHA-256 00 on chain"));
+        assert!(differences[1].starts_with("/b.html has the blob ID"));
+        assert_eq!(
+            differences[2..],
+            [
+                "/c.html is in the lockfile but not on chain",
+                "/d.html is on chain but not in the lockfile"
+            ]
+        );
+    }
+}
@@ -1,20 +1,38 @@
 // Copyright (c) Mysten Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use std::{collections::BTreeSet, str::FromStr};
+use std::{collections::BTreeSet, path::PathBuf, str::FromStr, time::Duration};
 
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, bail, ensure, Context, Result};
+use serde::{Deserialize, Serialize};
+use shared_crypto::intent::{Intent, IntentScope};
 use sui_keys::keystore::AccountKeystore;
-use sui_sdk::{rpc_types::SuiTransactionBlockResponse, wallet_context::WalletContext, SuiClient};
+use sui_sdk::{
+    rpc_types::{
+        SuiExecutionStatus,
+        SuiTransactionBlockEffectsAPI,
+        SuiTransactionBlockResponse,
+        SuiTransactionBlockResponseOptions,
+    },
+    wallet_context::WalletContext,
+    SuiClient,
+};
 use sui_types::{
-    base_types::{ObjectID, ObjectRef, SuiAddress},
-    transaction::{ProgrammableTransaction, TransactionData},
+    base_types::{ObjectDigest, ObjectID, ObjectRef, SequenceNumber, SuiAddress},
+    digests::TransactionDigest,
+    object::Owner,
+    programmable_transaction_builder::ProgrammableTransactionBuilder,
+    transaction::{CallArg, ObjectArg, ProgrammableTransaction, Transaction, TransactionData},
     Identifier,
 };
 
 use super::{
     builder::SitePtb,
-    resource::ResourceOp,
+    chunking::{chunk_updates, ChunkStrategy, TxLimits},
+    contracts::check_package_version,
+    plan::SitePlan,
+    resource::{check_file_hash, Resource, ResourceOp},
+    snapshot::SiteSnapshot,
     RemoteSiteFactory,
     SiteData,
     SiteDataDiff,
@@ -22,21 +40,149 @@ use super::{
 };
 use crate::{
     display,
+    profiles::Network,
     publish::WhenWalrusUpload,
-    summary::SiteDataDiffSummary,
-    walrus::Walrus,
+    signer::{CommandSigner, KeyringSigner},
+    summary::{AuditLogEntry, SiteDataDiffSummary},
+    util::{
+        confirm_interactively,
+        execute_without_local_confirmation,
+        get_owned_sites,
+        get_site_id_from_response,
+        object_call_arg,
+        wait_for_object,
+        ResourceSelection,
+        OBJECT_READ_ATTEMPTS,
+        OBJECT_READ_DELAY,
+    },
+    walrus::{
+        output::{
+            BlobStatus,
+            BlobStoreResult,
+            Epoch,
+            RegisterBlobOp,
+            StoreBatchOutput,
+            StoreOutput,
+        },
+        types::BlobId,
+        Walrus,
+    },
     Config,
 };
 
+/// The state of a blob on Walrus, with respect to the epoch until which it must be stored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BlobState {
+    /// The blob is certified at least until the required epoch.
+    Stored,
+    /// The blob is registered but not certified, e.g., because its upload was interrupted.
+    Registered,
+    /// The blob is not stored, or not for long enough.
+    Missing,
+}
+
+impl BlobState {
+    /// Returns the state of the blob with the status, if it must be stored until `end_epoch`
+    /// (exclusive).
+    fn from_status(status: &BlobStatus, end_epoch: Epoch) -> Self {
+        if status
+            .certified_until()
+            .is_some_and(|certified_until| certified_until >= end_epoch)
+        {
+            Self::Stored
+        } else if status.is_registered_only() {
+            Self::Registered
+        } else {
+            Self::Missing
+        }
+    }
+}
+
+/// The maximum number of resources removed from a site in a single transaction.
+const DESTROY_BATCH_SIZE: usize = 500;
+
+/// The number of times a transaction is submitted if the version of its gas coin is stale, or if
+/// it fails with a configured transient error.
+const STALE_GAS_ATTEMPTS: u32 = 3;
+
+/// The delay before resubmitting a transaction that failed with a configured transient error.
+const TRANSIENT_ERROR_RETRY_DELAY: Duration = Duration::from_secs(2);
+
+/// The delay before the first retry of a failed blob upload, doubled at every retry.
+const UPLOAD_RETRY_INITIAL_DELAY: Duration = Duration::from_secs(1);
+
 /// The identifier for the new or existing site.
 ///
 /// Either object ID (existing site) or name (new site).
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum SiteIdentifier {
     ExistingSite(ObjectID),
     NewSite(String),
 }
 
+/// The options of the updates of a site, as set when publishing.
+#[derive(Debug, Clone)]
+pub struct UpdateOptions {
+    /// If set, the storage of all the blobs of the site is extended at least until this epoch.
+    pub extend_to: Option<Epoch>,
+    /// If set, only the selected resources are updated.
+    pub only: Option<ResourceSelection>,
+    /// How the updates are split into transactions.
+    pub chunk_strategy: ChunkStrategy,
+    /// The maximum number of commands per transaction, if set explicitly.
+    pub max_commands_per_tx: Option<usize>,
+    /// The maximum number of objects created or deleted per transaction, if set explicitly.
+    pub max_objects_per_tx: Option<usize>,
+    /// If set, the blobs of the resources removed from the site are deleted after the update.
+    pub prune_removed_blobs: bool,
+    /// The number of times the upload of a single blob is retried, with exponential backoff,
+    /// before failing the command.
+    pub upload_retries: u32,
+    /// If set, a gas coin of this amount, in MIST, is split from the largest coin of the wallet
+    /// before the updates on Sui, and pays for all of them.
+    pub prepare_gas: Option<u64>,
+    /// If set, a snapshot of the existing site is written in this directory before the update.
+    pub snapshot_dir: Option<PathBuf>,
+    /// The resource path of the entry point of the site, which is added before any other resource.
+    pub entry_point: Option<String>,
+    /// The resource paths added first, in this order, as declared in ws-resources.json.
+    pub order: Vec<String>,
+    /// The resource paths that must not be updated or removed, as declared in ws-resources.json.
+    pub immutable: BTreeSet<String>,
+    /// If set, the immutable resources can be updated or removed, with a warning.
+    pub allow_immutable_change: bool,
+    /// The largest percentage of the existing resources that an update can remove, unless
+    /// `--confirm-large-deletion` or `--yes` is set.
+    pub max_deletion_percent: u8,
+    /// If set, the updates can remove more than `max_deletion_percent` of the resources.
+    pub confirm_large_deletion: bool,
+    /// If set, the largest total size in bytes of the blobs stored by an update.
+    pub max_total_size: Option<u64>,
+}
+
+impl Default for UpdateOptions {
+    fn default() -> Self {
+        Self {
+            extend_to: None,
+            only: None,
+            chunk_strategy: ChunkStrategy::default(),
+            max_commands_per_tx: None,
+            max_objects_per_tx: None,
+            prune_removed_blobs: false,
+            upload_retries: 0,
+            prepare_gas: None,
+            snapshot_dir: None,
+            entry_point: None,
+            order: vec![],
+            immutable: BTreeSet::new(),
+            allow_immutable_change: false,
+            max_deletion_percent: 100,
+            confirm_large_deletion: false,
+            max_total_size: None,
+        }
+    }
+}
+
 pub struct SiteManager {
     pub config: Config,
     pub walrus: Walrus,
@@ -44,10 +190,27 @@ pub struct SiteManager {
     pub site_id: SiteIdentifier,
     pub epochs: u64,
     pub when_upload: WhenWalrusUpload,
+    /// How the site is updated.
+    pub options: UpdateOptions,
+    /// The total gas spent by the transactions executed so far, in MIST.
+    pub gas_spent: u64,
+    /// The number of blobs stored on Walrus so far.
+    pub blobs_uploaded: usize,
+    /// The total gas estimated with dry runs for the transactions executed so far, in MIST.
+    ///
+    /// Only set if the gas is estimated before execution, i.e., if a maximum budget is set.
+    pub gas_estimated: Option<u64>,
+    /// The gas coin to use for the next transaction, as updated by the effects of the last one.
+    ///
+    /// Reusing the reference from the effects avoids paying with a stale version of the coin when
+    /// the full node has not yet indexed the previous transaction.
+    gas_coin: Option<ObjectRef>,
+    /// Whether the user confirmed the operation, or no confirmation is needed.
+    confirmed: bool,
 }
 
 impl SiteManager {
-    /// Creates a new site manager.
+    /// Creates a new site manager, with the default options.
     pub async fn new(
         config: Config,
         walrus: Walrus,
@@ -63,9 +226,21 @@ impl SiteManager {
             site_id,
             epochs,
             when_upload,
+            options: UpdateOptions::default(),
+            gas_spent: 0,
+            blobs_uploaded: 0,
+            gas_estimated: None,
+            gas_coin: None,
+            confirmed: false,
         })
     }
 
+    /// Sets the options of the updates.
+    pub fn with_options(mut self, options: UpdateOptions) -> Self {
+        self.options = options;
+        self
+    }
+
     /// Updates the site with the given [`Resource`](super::resource::Resource).
     ///
     /// If the site does not exist, it is created and updated. The resources that need to be updated
@@ -75,40 +250,640 @@ impl SiteManager {
         local_site_data: &SiteData,
     ) -> Result<(SuiTransactionBlockResponse, SiteDataDiffSummary)> {
         tracing::debug!(?self.site_id, "creating or updating site");
+        self.preflight().await?;
+        let existing_site = self.get_existing_site().await?;
+        if let (Some(dir), SiteIdentifier::ExistingSite(site_id)) =
+            (&self.options.snapshot_dir, &self.site_id)
+        {
+            display::action(format!(
+                "Saving a snapshot of the site in {}",
+                dir.display()
+            ));
+            let path = SiteSnapshot::new(*site_id, &existing_site).write(dir)?;
+            display::done();
+            tracing::debug!(?path, "saved the snapshot of the site");
+        }
+        if let Some(selection) = self.options.only.clone() {
+            // Only the matching resources are compared, so that the other resources on chain are
+            // never removed. The routes are left unchanged.
+            let local_site_data =
+                local_site_data.with_matching_resources(&selection, existing_site.routes.clone());
+            let existing_site =
+                existing_site.with_matching_resources(&selection, existing_site.routes.clone());
+            return self
+                .update_site_from(&existing_site, &local_site_data)
+                .await;
+        }
+        self.update_site_from(&existing_site, local_site_data).await
+    }
+
+    /// Restores the site to a snapshot of a previous state.
+    ///
+    /// Nothing is uploaded to Walrus: the blobs of the snapshot must still be stored, i.e., they
+    /// were neither pruned nor expired since the snapshot was taken.
+    pub async fn restore_snapshot(
+        &mut self,
+        snapshot: &SiteData,
+    ) -> Result<(SuiTransactionBlockResponse, SiteDataDiffSummary)> {
+        self.preflight().await?;
+        let existing_site = self.get_existing_site().await?;
+        let site_updates = snapshot.diff(&existing_site);
+        // The blobs must be stored for as long as the blobs of a publish would be, as the end
+        // epoch is exclusive.
+        let required_end_epoch = self.walrus.info(false).await?.current_epoch + self.epochs;
+        let mut missing = BTreeSet::new();
+        for update in site_updates.get_walrus_updates(&WhenWalrusUpload::Modified) {
+            let resource = update.inner();
+            if self
+                .blob_state(&resource.info.blob_id, required_end_epoch)
+                .await
+                != BlobState::Stored
+            {
+                missing.insert(resource.info.path.clone());
+            }
+        }
+        ensure!(
+            missing.is_empty(),
+            "the blobs of some resources of the snapshot are no longer stored on Walrus: {}",
+            missing.into_iter().collect::<Vec<_>>().join(", ")
+        );
+        let result = if site_updates.has_updates() {
+            self.confirm_on_mainnet().await?;
+            self.execute_sui_updates_in_chunks(&site_updates).await?
+        } else {
+            SuiTransactionBlockResponse::default()
+        };
+        Ok((result, site_updates.summary(&WhenWalrusUpload::Modified)))
+    }
+
+    /// Computes the plan to update the site with the given local site data, without executing it.
+    pub async fn plan_update(&self, local_site_data: &SiteData) -> Result<SitePlan> {
+        self.preflight().await?;
+        let existing_site = self.get_existing_site().await?;
+        // The plan is checked as the update would be, so that it is never refused when applied.
+        self.check_immutable(&existing_site, local_site_data)?;
+        let site_updates = self.site_updates(&existing_site, local_site_data);
+        self.check_large_deletion(&existing_site, &site_updates)?;
+        let chunks = chunk_updates(
+            &site_updates,
+            self.options.chunk_strategy,
+            self.options.entry_point.as_deref(),
+            &self.options.order,
+            &self.tx_limits().await,
+        );
+        let estimated_gas = match self.estimate_sui_updates(&site_updates).await {
+            Ok(estimate) => Some(estimate),
+            Err(error) => {
+                tracing::warn!(?error, "could not estimate the gas cost of the update");
+                None
+            }
+        };
+        Ok(SitePlan::new(
+            self.site_id.clone(),
+            self.epochs,
+            self.when_upload.is_always(),
+            &existing_site,
+            local_site_data,
+            &site_updates,
+            &chunks,
+            estimated_gas,
+        ))
+    }
+
+    /// Executes the plan, after checking that neither the site on chain nor the files to store
+    /// have changed since the plan was computed.
+    pub async fn apply_plan(
+        &mut self,
+        plan: &SitePlan,
+    ) -> Result<(SuiTransactionBlockResponse, SiteDataDiffSummary)> {
+        self.preflight().await?;
+        let existing_site = self.get_existing_site().await?;
+        let planned_existing_site = SiteData::from(&plan.existing_site);
+        let drift = planned_existing_site.diff(&existing_site);
+        if drift.has_updates() {
+            tracing::debug!(?drift, "the site changed since the plan was computed");
+            bail!(
+                "the site on chain changed since the plan was computed; compute a new plan \
+                with `--dry-run --dry-run-file`"
+            );
+        }
+        let local_site_data = SiteData::from(&plan.local_site);
+        self.check_planned_files(&existing_site, &local_site_data)?;
+        self.update_site_from(&existing_site, &local_site_data)
+            .await
+    }
+
+    /// Fails if the content of any of the files to store differs from the one hashed when the
+    /// plan was computed, so that only the reviewed content is published.
+    fn check_planned_files(
+        &self,
+        existing_site: &SiteData,
+        local_site_data: &SiteData,
+    ) -> Result<()> {
+        let site_updates = self.site_updates(existing_site, local_site_data);
+        let changed = site_updates
+            .get_walrus_updates(&self.when_upload)
+            .iter()
+            .filter_map(|update| {
+                let resource = update.inner();
+                check_file_hash(&resource.info, &resource.full_path)
+                    .err()
+                    .map(|error| format!("  - {}: {error:#}", resource.full_path.display()))
+            })
+            .collect::<Vec<_>>();
+        ensure!(
+            changed.is_empty(),
+            "{} of the files to store changed since the plan was computed; compute a new plan with \
+            `--dry-run --dry-run-file`:\n{}",
+            changed.len(),
+            changed.join("\n")
+        );
+        Ok(())
+    }
+
+    /// Moves resources of the site to new paths, without re-uploading the blobs.
+    ///
+    /// If `glob` is set, the paths must end with `*`, and all the resources starting with the old
+    /// prefix are moved to the new prefix. Returns the response and the list of moves performed.
+    pub async fn move_resources(
+        &mut self,
+        old_path: &str,
+        new_path: &str,
+        glob: bool,
+    ) -> Result<(SuiTransactionBlockResponse, Vec<(String, String)>)> {
+        let SiteIdentifier::ExistingSite(site_id) = self.site_id else {
+            bail!("resources can only be moved on an existing site");
+        };
+        self.preflight().await?;
+        let existing_site = self.get_existing_site().await?;
+        let existing_paths = existing_site
+            .resources()
+            .inner
+            .iter()
+            .map(|resource| resource.info.path.as_str())
+            .collect::<BTreeSet<_>>();
+        let moves = resource_moves(&existing_paths, old_path, new_path, glob)?;
+
+        let mut ptb = SitePtb::new(
+            self.config.package(),
+            Identifier::from_str(SITE_MODULE).expect("the str provided is valid"),
+        )?
+        .with_call_arg(&self.site_call_arg(site_id).await?)?;
+        for (old, new) in moves.iter() {
+            ptb.move_resource(old, new)?;
+        }
+        let gas_coin = self.gas_coin_ref().await?;
+        let response = self.sign_and_send_ptb(ptb.finish(), gas_coin).await?;
+        Ok((response, moves))
+    }
+
+    /// Updates the metadata of the site, without reading or changing its resources.
+    pub async fn set_metadata(&mut self, name: &str) -> Result<SuiTransactionBlockResponse> {
+        let SiteIdentifier::ExistingSite(site_id) = self.site_id else {
+            bail!("the metadata can only be set on an existing site");
+        };
+        self.preflight().await?;
+        let mut ptb = SitePtb::new(
+            self.config.package(),
+            Identifier::from_str(SITE_MODULE).expect("the str provided is valid"),
+        )?
+        .with_call_arg(&self.site_call_arg(site_id).await?)?;
+        ptb.update_name(name)?;
+        let gas_coin = self.gas_coin_ref().await?;
+        self.sign_and_send_ptb(ptb.finish(), gas_coin).await
+    }
+
+    /// Removes all the resources and the routes from the site, in batches of transactions.
+    ///
+    /// The remaining fields are fetched from chain before every batch, so a destroy that was
+    /// interrupted is resumed by running it again on the same site. The site object itself is
+    /// left on chain, as the Walrus Sites package does not allow deleting it.
+    pub async fn destroy(&mut self) -> Result<Vec<SuiTransactionBlockResponse>> {
+        ensure!(
+            matches!(self.site_id, SiteIdentifier::ExistingSite(_)),
+            "only an existing site can be destroyed"
+        );
+        self.preflight().await?;
+        let mut responses = vec![];
+        let mut previous_remaining = None;
+        loop {
+            let existing_site = self.get_existing_site().await?;
+            let n_resources = existing_site.resources.inner.len();
+            let has_routes = existing_site.routes.is_some();
+            let remaining = n_resources + usize::from(has_routes);
+            println!(
+                "Fields remaining on the site: {} ({} resources{})",
+                remaining,
+                n_resources,
+                if has_routes { " and the routes" } else { "" }
+            );
+            if remaining == 0 {
+                break;
+            }
+            ensure!(
+                previous_remaining.map_or(true, |previous| remaining < previous),
+                "the number of fields on the site did not decrease after removing a batch"
+            );
+            previous_remaining = Some(remaining);
+
+            let batch = SiteData::new(
+                existing_site
+                    .resources
+                    .inner
+                    .iter()
+                    .take(DESTROY_BATCH_SIZE)
+                    .cloned()
+                    .collect(),
+                existing_site.routes.clone(),
+            );
+            let empty_site = SiteData::empty();
+            display::action(format!(
+                "Removing {} resources from the site",
+                batch.resources.inner.len()
+            ));
+            responses.push(self.execute_sui_updates(&empty_site.diff(&batch)).await?);
+            display::done();
+        }
+        Ok(responses)
+    }
+
+    /// The checks to perform before interacting with the site.
+    async fn preflight(&self) -> Result<()> {
+        check_package_version(
+            &self.sui_client().await?,
+            self.config.package(),
+            self.config.general.skip_package_version_check,
+        )
+        .await
+    }
+
+    /// Returns the limits used to split the updates into transactions.
+    ///
+    /// The limits set explicitly are used as they are, unless they exceed the limits of the
+    /// protocol of the network; the default limits are lowered to the limits of the protocol if
+    /// needed. If the protocol config cannot be read, the limits are not checked against it.
+    async fn tx_limits(&self) -> TxLimits {
+        let defaults = TxLimits::default();
+        let (protocol_commands, protocol_objects) = match self.protocol_tx_limits().await {
+            Ok(limits) => limits,
+            Err(error) => {
+                tracing::debug!(?error, "could not read the protocol config");
+                (None, None)
+            }
+        };
+        let limit = |name: &str, set: Option<usize>, default: usize, protocol: Option<usize>| match (
+            set, protocol,
+        ) {
+            (Some(set), Some(protocol)) if set > protocol => {
+                display::warning(format!(
+                    "the maximum number of {name} per transaction {set} is above the limit of \
+                    the protocol, using {protocol}"
+                ));
+                protocol
+            }
+            (Some(set), _) => set,
+            (None, protocol) => protocol.map_or(default, |protocol| default.min(protocol)),
+        };
+        TxLimits {
+            max_commands: limit(
+                "commands",
+                self.options.max_commands_per_tx,
+                defaults.max_commands,
+                protocol_commands,
+            ),
+            max_objects: limit(
+                "objects",
+                self.options.max_objects_per_tx,
+                defaults.max_objects,
+                protocol_objects,
+            ),
+            ..defaults
+        }
+    }
+
+    /// Reads the maximum numbers of commands and of created or deleted objects per transaction
+    /// from the protocol config of the network.
+    async fn protocol_tx_limits(&self) -> Result<(Option<usize>, Option<usize>)> {
+        let config = self
+            .sui_client()
+            .await?
+            .read_api()
+            .get_protocol_config(None)
+            .await?;
+        let attribute = |name: &str| {
+            config
+                .attributes
+                .get(name)
+                .and_then(|value| serde_json::to_value(value.as_ref()?).ok())
+                .and_then(|value| protocol_config_value(&value))
+        };
+        let objects = [
+            "max_num_new_move_object_ids",
+            "max_num_deleted_move_object_ids",
+        ]
+        .into_iter()
+        .filter_map(attribute)
+        .min();
+        Ok((attribute("max_programmable_tx_commands"), objects))
+    }
+
+    /// Fetches the existing site from chain, or returns an empty site if it is a new site.
+    async fn get_existing_site(&self) -> Result<SiteData> {
         let existing_site = match &self.site_id {
             SiteIdentifier::ExistingSite(site_id) => {
-                RemoteSiteFactory::new(&self.sui_client().await?, self.config.package)
+                RemoteSiteFactory::new(&self.sui_client().await?, self.config.package())
                     .get_from_chain(*site_id)
                     .await?
             }
             SiteIdentifier::NewSite(_) => SiteData::empty(),
         };
         tracing::debug!(?existing_site, "checked existing site");
+        Ok(existing_site)
+    }
 
+    /// Computes the operations to go from the existing site to the local site.
+    fn site_updates<'a>(
+        &self,
+        existing_site: &'a SiteData,
+        local_site_data: &'a SiteData,
+    ) -> SiteDataDiff<'a> {
         let site_updates = if self.when_upload.is_always() {
             existing_site.replace_all(local_site_data)
         } else {
-            local_site_data.diff(&existing_site)
+            local_site_data.diff(existing_site)
         };
         tracing::debug!(operations=?site_updates, "list of operations computed");
+        site_updates
+    }
+
+    /// Fails if the update would change or remove any of the immutable resources, unless
+    /// `--allow-immutable-change` is set.
+    fn check_immutable(&self, existing_site: &SiteData, local_site_data: &SiteData) -> Result<()> {
+        let changes = immutable_changes(existing_site, local_site_data, &self.options.immutable);
+        if changes.is_empty() {
+            return Ok(());
+        }
+        let list = changes
+            .iter()
+            .map(|change| format!("  - {change}"))
+            .collect::<Vec<_>>()
+            .join("\n");
+        if self.options.allow_immutable_change {
+            display::warning(format!(
+                "changing the immutable resources (`--allow-immutable-change`):\n{list}"
+            ));
+            return Ok(());
+        }
+        bail!(
+            "the update would change the resources marked as immutable in ws-resources.json; \
+            use `--allow-immutable-change` to change them anyway:\n{list}"
+        )
+    }
+
+    /// Fails if the update removes more than `max_deletion_percent` of the existing resources,
+    /// e.g., because it was pointed at the wrong directory, unless confirmed.
+    fn check_large_deletion(
+        &self,
+        existing_site: &SiteData,
+        site_updates: &SiteDataDiff<'_>,
+    ) -> Result<()> {
+        if self.options.confirm_large_deletion || self.config.general.yes {
+            return Ok(());
+        }
+        let n_existing = existing_site.resources().inner.len();
+        let n_removed = site_updates.removed_paths().len();
+        ensure!(
+            !is_large_deletion(n_removed, n_existing, self.options.max_deletion_percent),
+            "the update would remove {n_removed} of the {n_existing} resources of the site, more \
+            than the {}% allowed by `--max-deletion-percent`; check the directory, or pass \
+            `--confirm-large-deletion` to remove them",
+            self.options.max_deletion_percent
+        );
+        Ok(())
+    }
+
+    /// Updates the site, going from the existing site to the local site.
+    async fn update_site_from(
+        &mut self,
+        existing_site: &SiteData,
+        local_site_data: &SiteData,
+    ) -> Result<(SuiTransactionBlockResponse, SiteDataDiffSummary)> {
+        self.check_immutable(existing_site, local_site_data)?;
+        let site_updates = self.site_updates(existing_site, local_site_data);
+        self.check_large_deletion(existing_site, &site_updates)?;
 
         let walrus_updates = site_updates.get_walrus_updates(&self.when_upload);
-        let result = if !walrus_updates.is_empty() || !site_updates.route_ops.is_unchanged() {
+        let result = if !walrus_updates.is_empty() || site_updates.has_updates() {
+            self.check_signer()?;
             self.publish_to_walrus(&walrus_updates).await?;
-            display::action("Updating the Walrus Site object on Sui");
-            let result = self.execute_sui_updates(&site_updates).await?;
-            display::done();
-            result
+            if site_updates.has_updates() {
+                self.execute_sui_updates_in_chunks(&site_updates).await?
+            } else {
+                // Only blobs that were no longer stored were uploaded again.
+                SuiTransactionBlockResponse::default()
+            }
         } else {
             SuiTransactionBlockResponse::default()
         };
+        if self.options.prune_removed_blobs {
+            self.delete_removed_blobs(&site_updates).await;
+        }
+        if let Some(extend_to) = self.options.extend_to {
+            self.extend_storage(local_site_data, extend_to).await?;
+        }
         Ok((result, site_updates.summary(&self.when_upload)))
     }
 
+    /// Executes the updates on Sui, in as many transactions as needed to fit the limits of Sui.
+    ///
+    /// Returns the response of the first transaction, which creates the site if it is new. After
+    /// the site is created, the following transactions update the new site.
+    async fn execute_sui_updates_in_chunks<'b>(
+        &mut self,
+        updates: &SiteDataDiff<'b>,
+    ) -> Result<SuiTransactionBlockResponse> {
+        if let Some(amount) = self.options.prepare_gas {
+            self.prepare_gas_coin(amount).await?;
+        }
+        let chunks = chunk_updates(
+            updates,
+            self.options.chunk_strategy,
+            self.options.entry_point.as_deref(),
+            &self.options.order,
+            &self.tx_limits().await,
+        );
+        let n_chunks = chunks.len();
+        let mut first_response = None;
+        for (index, chunk) in chunks.iter().enumerate() {
+            if n_chunks == 1 {
+                display::action("Updating the Walrus Site object on Sui");
+            } else {
+                display::action(format!(
+                    "Updating the Walrus Site object on Sui (transaction {} of {n_chunks})",
+                    index + 1
+                ));
+            }
+            let response = self.execute_sui_updates(chunk).await?;
+            display::done();
+            self.wait_for_new_site(&response).await?;
+            if let (SiteIdentifier::NewSite(_), Some(effects)) =
+                (&self.site_id, response.effects.as_ref())
+            {
+                let site_id = get_site_id_from_response(self.active_address()?, effects)?;
+                self.site_id = SiteIdentifier::ExistingSite(site_id);
+            }
+            first_response.get_or_insert(response);
+        }
+        Ok(first_response.expect("there is always at least one chunk"))
+    }
+
+    /// Splits a gas coin of the given amount from the largest coin of the wallet, and uses it to
+    /// pay for the following transactions.
+    ///
+    /// The dedicated coin isolates the gas of the command from the other coins of the address,
+    /// which can be used concurrently by other jobs.
+    async fn prepare_gas_coin(&mut self, amount: u64) -> Result<()> {
+        let gas_budget = self.config.gas_budget();
+        ensure!(
+            amount >= gas_budget,
+            "the prepared gas coin of {amount} MIST cannot pay for the gas budget of {gas_budget} \
+            MIST"
+        );
+        let address = self.active_address()?;
+        let (balance, largest_coin) = self
+            .wallet
+            .gas_objects(address)
+            .await?
+            .into_iter()
+            .max_by_key(|(balance, _)| *balance)
+            .ok_or_else(|| anyhow!("the address {address} does not own any gas coin"))?;
+        ensure!(
+            balance >= amount + gas_budget,
+            "the largest coin of {address} holds {balance} MIST, which is not enough to split a \
+            gas coin of {amount} MIST and pay for the split"
+        );
+        display::action(format!("Preparing a gas coin of {amount} MIST"));
+        let mut ptb = ProgrammableTransactionBuilder::new();
+        ptb.pay_sui(vec![address], vec![amount])?;
+        let response = self
+            .sign_and_send_ptb(ptb.finish(), largest_coin.object_ref())
+            .await?;
+        let coin = response
+            .effects
+            .as_ref()
+            .and_then(|effects| {
+                effects
+                    .created()
+                    .iter()
+                    .find(|created| created.owner == Owner::AddressOwner(address))
+                    .map(|created| created.reference.to_object_ref())
+            })
+            .ok_or_else(|| anyhow!("the new gas coin was not found in the effects"))?;
+        tracing::debug!(?coin, "prepared the gas coin");
+        self.gas_coin = Some(coin);
+        display::done();
+        Ok(())
+    }
+
+    /// Waits until the newly created site can be read from the full node.
+    ///
+    /// Does nothing if the site already existed. The site has been created at this point, so a
+    /// site that is not visible yet only results in a warning.
+    async fn wait_for_new_site(&self, response: &SuiTransactionBlockResponse) -> Result<()> {
+        let (SiteIdentifier::NewSite(_), Some(effects)) =
+            (&self.site_id, response.effects.as_ref())
+        else {
+            return Ok(());
+        };
+        let site_id = get_site_id_from_response(self.active_address()?, effects)?;
+        if let Err(error) = wait_for_object(&self.sui_client().await?, site_id).await {
+            display::warning(format!("the new site cannot be read yet: {error}"));
+        }
+        Ok(())
+    }
+
     /// Publishes the resources to Walrus.
+    ///
+    /// Unless the upload is forced, resources whose blob is already certified on Walrus for the
+    /// requested number of epochs are skipped, as the blob ID is derived from the content.
     async fn publish_to_walrus<'b>(&mut self, updates: &[&ResourceOp<'b>]) -> Result<()> {
+        self.confirm_on_mainnet().await?;
+        let info = self.walrus.info(false).await?;
+        // A resource references a single blob on chain, so files above the maximum blob size
+        // cannot be published.
+        if let Some(resource) = updates
+            .iter()
+            .map(|update| update.inner())
+            .find(|resource| resource.unencoded_size as u64 > info.max_blob_size)
+        {
+            bail!(
+                "the resource {} is {} bytes, above the maximum blob size of {} bytes; resources \
+                cannot be split across multiple blobs",
+                resource.info.path,
+                resource.unencoded_size,
+                info.max_blob_size
+            );
+        }
+        let required_end_epoch = if self.when_upload.is_always() {
+            None
+        } else {
+            Some(info.current_epoch + self.epochs)
+        };
+
+        let mut to_store = vec![];
+        // Resources with the same content map to the same blob, which only needs to be stored once.
+        let mut content_hashes = BTreeSet::new();
         for update in updates.iter() {
             let resource = update.inner();
+            if !content_hashes.insert(resource.info.blob_hash) {
+                tracing::debug!(
+                    resource=?resource.full_path,
+                    blob_id=%resource.info.blob_id,
+                    "blob with the same content already scheduled for upload, skipping upload"
+                );
+                continue;
+            }
+            if let Some(required_end_epoch) = required_end_epoch {
+                match self
+                    .blob_state(&resource.info.blob_id, required_end_epoch)
+                    .await
+                {
+                    BlobState::Stored => {
+                        tracing::debug!(
+                            resource=?resource.full_path,
+                            blob_id=%resource.info.blob_id,
+                            "blob already stored on Walrus, skipping upload"
+                        );
+                        continue;
+                    }
+                    BlobState::Registered => {
+                        tracing::info!(
+                            resource=?resource.full_path,
+                            blob_id=%resource.info.blob_id,
+                            "blob registered but not certified, resuming the upload"
+                        );
+                    }
+                    BlobState::Missing => (),
+                }
+            }
+            to_store.push(resource);
+        }
+        check_total_size(&to_store, self.options.max_total_size)?;
+
+        if let Some(threshold) = self.config.general.batch_upload_threshold {
+            let (small, large): (Vec<_>, Vec<_>) = to_store
+                .into_iter()
+                .partition(|resource| resource.unencoded_size as u64 <= threshold);
+            to_store = large;
+            self.store_batch(small).await?;
+        }
+        self.store_individually(to_store).await
+    }
+
+    /// Stores the resources on Walrus one by one, with [`Self::store_with_retries`].
+    async fn store_individually(&mut self, to_store: Vec<&Resource>) -> Result<()> {
+        let mut retried = vec![];
+        for resource in to_store {
             tracing::debug!(
                 resource=?resource.full_path,
                 blob_id=%resource.info.blob_id,
@@ -119,33 +894,359 @@ impl SiteManager {
                 "Storing resource on Walrus: {}",
                 &resource.info.path
             ));
-            let _output = self
+            let (output, retries) = self.store_with_retries(resource).await?;
+            self.blobs_uploaded += 1;
+            if retries > 0 {
+                retried.push(format!("  - {} ({retries} retries)", resource.info.path));
+            }
+            if let BlobStoreResult::NewlyCreated {
+                resource_operation: RegisterBlobOp::ReuseRegistration { .. },
+                ..
+            } = output.0
+            {
+                tracing::debug!(
+                    blob_id=%resource.info.blob_id,
+                    "reused the existing registration of the blob"
+                );
+            }
+            display::done();
+        }
+        if !retried.is_empty() {
+            display::warning(format!(
+                "the uploads of {} resources succeeded after retrying:\n{}",
+                retried.len(),
+                retried.join("\n")
+            ));
+        }
+        Ok(())
+    }
+
+    /// Stores the resource on Walrus, retrying the upload up to `upload_retries` times.
+    ///
+    /// The delay between the attempts doubles at every retry. Returns the output of the successful
+    /// attempt, and the number of retries it took.
+    async fn store_with_retries(&mut self, resource: &Resource) -> Result<(StoreOutput, u32)> {
+        let mut delay = UPLOAD_RETRY_INITIAL_DELAY;
+        let mut retries = 0;
+        loop {
+            match self
                 .walrus
                 .store(resource.full_path.clone(), self.epochs, false)
+                .await
+            {
+                Ok(output) => return Ok((output, retries)),
+                Err(error) if retries < self.options.upload_retries => {
+                    retries += 1;
+                    tracing::info!(
+                        resource=%resource.info.path,
+                        retries,
+                        ?error,
+                        "the upload of the blob failed, retrying in {} seconds",
+                        delay.as_secs()
+                    );
+                    tokio::time::sleep(delay).await;
+                    delay *= 2;
+                }
+                Err(error) => {
+                    return Err(error.context(format!(
+                        "the upload of {} failed after {retries} retries",
+                        resource.info.path
+                    )))
+                }
+            }
+        }
+    }
+
+    /// Stores the resources on Walrus with a single batched command.
+    ///
+    /// The resources that are not stored by the batch, because the batched command failed or
+    /// because the Walrus CLI reported no valid result for them, are then stored individually, and
+    /// the error of their upload is returned, if any.
+    async fn store_batch(&mut self, resources: Vec<&Resource>) -> Result<()> {
+        if resources.len() < 2 {
+            return self.store_individually(resources).await;
+        }
+        display::action(format!(
+            "Storing {} small resources on Walrus in a single batch",
+            resources.len()
+        ));
+        let files = resources
+            .iter()
+            .map(|resource| resource.full_path.clone())
+            .collect();
+        let failed = match self.walrus.store_batch(files, self.epochs, false).await {
+            Ok(output) => {
+                let failed = unstored_resources(&resources, &output);
+                if failed.is_empty() {
+                    display::done();
+                } else {
+                    display::failed();
+                    display::warning(format!(
+                        "the batched upload did not store {} of the {} resources, storing them \
+                        individually",
+                        failed.len(),
+                        resources.len()
+                    ));
+                }
+                failed
+            }
+            Err(error) => {
+                display::failed();
+                tracing::warn!(?error, "batched upload failed");
+                display::warning("batched upload failed, storing the resources individually");
+                resources.clone()
+            }
+        };
+        self.blobs_uploaded += resources.len() - failed.len();
+        self.store_individually(failed).await
+    }
+
+    /// Deletes the blobs of the removed resources that are not used by any other resource.
+    ///
+    /// The site is already updated at this point, so a failure to delete a blob only results in a
+    /// warning.
+    async fn delete_removed_blobs<'b>(&mut self, updates: &SiteDataDiff<'b>) {
+        let removed = updates
+            .resource_ops
+            .iter()
+            .filter(|op| matches!(op, ResourceOp::Deleted(_)))
+            .map(|op| op.inner().info.blob_id)
+            .collect::<BTreeSet<_>>();
+        if removed.is_empty() {
+            return;
+        }
+        if let Err(error) = self.try_delete_removed_blobs(updates, removed).await {
+            display::warning(format!(
+                "could not delete the blobs of the removed resources: {error:#}"
+            ));
+        }
+    }
+
+    async fn try_delete_removed_blobs<'b>(
+        &mut self,
+        updates: &SiteDataDiff<'b>,
+        removed: BTreeSet<BlobId>,
+    ) -> Result<()> {
+        // The resources of the update are also kept, in case the full node does not return the
+        // latest version of the site yet.
+        let mut referenced = self.referenced_blob_ids().await?;
+        referenced.extend(
+            updates
+                .resource_ops
+                .iter()
+                .filter(|op| !matches!(op, ResourceOp::Deleted(_)))
+                .map(|op| op.inner().info.blob_id),
+        );
+        let n_referenced = removed.intersection(&referenced).count();
+        if n_referenced > 0 {
+            println!("Keeping {n_referenced} blobs still used by the sites of the wallet");
+        }
+        // Only the objects of the blobs are deleted, as several objects can store the same blob.
+        let blobs = self
+            .walrus
+            .list_blobs(false)
+            .await?
+            .0
+            .into_iter()
+            .filter(|blob| {
+                blob.deletable
+                    && removed.contains(&blob.blob_id)
+                    && !referenced.contains(&blob.blob_id)
+            })
+            .collect::<Vec<_>>();
+        if blobs.is_empty() {
+            return Ok(());
+        }
+
+        display::action(format!(
+            "Deleting the {} blobs of the removed resources",
+            blobs.len()
+        ));
+        let mut n_deleted = 0;
+        let mut reclaimed = 0;
+        for blob in blobs {
+            match self.walrus.delete(blob.id).await {
+                Ok(output) => {
+                    n_deleted += output.deleted_blobs.len();
+                    reclaimed += output
+                        .deleted_blobs
+                        .iter()
+                        .map(|blob| blob.storage.storage_size)
+                        .sum::<u64>();
+                }
+                Err(error) => display::warning(format!(
+                    "could not delete the blob object {} of the blob {}: {error}",
+                    blob.id, blob.blob_id
+                )),
+            }
+        }
+        display::done();
+        println!("Deleted {n_deleted} blobs, reclaiming {reclaimed} bytes of storage");
+        Ok(())
+    }
+
+    /// Returns the blob IDs used by the resources of the site, and of the other sites owned by the
+    /// wallet, as they are on chain.
+    async fn referenced_blob_ids(&self) -> Result<BTreeSet<BlobId>> {
+        let client = self.sui_client().await?;
+        let factory = RemoteSiteFactory::new(&client, self.config.package());
+        let mut site_ids =
+            get_owned_sites(&client, self.active_address()?, factory.site_type()?).await?;
+        if let SiteIdentifier::ExistingSite(site_id) = &self.site_id {
+            site_ids.push(*site_id);
+        }
+        site_ids.sort();
+        site_ids.dedup();
+        let mut referenced = BTreeSet::new();
+        for site_id in site_ids {
+            let site = factory.get_from_chain(site_id).await?;
+            referenced.extend(
+                site.resources()
+                    .inner
+                    .iter()
+                    .map(|resource| resource.info.blob_id),
+            );
+        }
+        Ok(referenced)
+    }
+
+    /// Extends the storage of the blobs of the site that expire before the given epoch.
+    ///
+    /// As the blob ID is derived from the content, storing the resource again for the missing
+    /// epochs extends the lifetime of the blob referenced by the site.
+    async fn extend_storage(&mut self, site_data: &SiteData, extend_to: Epoch) -> Result<()> {
+        let current_epoch = self.walrus.info(false).await?.current_epoch;
+        if extend_to <= current_epoch {
+            display::warning(format!(
+                "the target epoch {extend_to} is not after the current epoch {current_epoch}; \
+                nothing to extend"
+            ));
+            return Ok(());
+        }
+
+        let mut to_extend = vec![];
+        for resource in site_data.resources().inner.iter() {
+            if self.blob_state(&resource.info.blob_id, extend_to).await != BlobState::Stored {
+                to_extend.push(resource);
+            }
+        }
+        if to_extend.is_empty() {
+            tracing::debug!(
+                extend_to,
+                "all blobs are already stored until the target epoch"
+            );
+            return Ok(());
+        }
+
+        display::action(format!(
+            "Extending the storage of {} resources until epoch {}",
+            to_extend.len(),
+            extend_to
+        ));
+        for resource in to_extend {
+            tracing::debug!(
+                resource=?resource.full_path,
+                blob_id=%resource.info.blob_id,
+                extend_to,
+                "extending the storage of the blob"
+            );
+            let _output = self
+                .walrus
+                .store(resource.full_path.clone(), extend_to - current_epoch, false)
                 .await?;
-            display::done();
         }
+        display::done();
         Ok(())
     }
 
+    /// Returns the state of the blob on Walrus, with respect to the given end epoch.
+    ///
+    /// The state is read from chain at every run, so that an upload interrupted at any step is
+    /// resumed from that step. Failures in getting the status of the blob are logged, and the blob
+    /// is considered missing, such that it is uploaded.
+    async fn blob_state(&self, blob_id: &BlobId, end_epoch: Epoch) -> BlobState {
+        match self.walrus.blob_status(*blob_id).await {
+            Ok(output) => BlobState::from_status(&output.status, end_epoch),
+            Err(error) => {
+                tracing::warn!(%blob_id, ?error, "could not get the status of the blob");
+                BlobState::Missing
+            }
+        }
+    }
+
     /// Executes the updates on Sui.
     async fn execute_sui_updates<'b>(
-        &self,
+        &mut self,
         updates: &SiteDataDiff<'b>,
     ) -> Result<SuiTransactionBlockResponse> {
         tracing::debug!(
             address=?self.active_address()?,
             "starting to update site resources on chain",
         );
+        let ptb = self.build_ptb(updates).await?;
+        let gas_coin = self.gas_coin_ref().await?;
+        let is_shared_site = ptb
+            .inputs
+            .iter()
+            .any(|input| matches!(input, CallArg::Object(ObjectArg::SharedObject { .. })));
+        let active_address = self.active_address()?;
+        self.sign_and_send_ptb(ptb, gas_coin)
+            .await
+            .map_err(|error| {
+                if is_shared_site {
+                    error.context(format!(
+                    "the update of the shared site failed; check that the Walrus Sites contract \
+                    allows {active_address} to modify it"
+                ))
+                } else {
+                    error
+                }
+            })
+    }
+
+    /// Returns the call argument for the existing site, which is either owned or shared.
+    ///
+    /// The owner of the site is read from the full node, see [`object_call_arg`]. If the initial
+    /// shared version of the site is pinned in the config, the site is passed as shared without
+    /// querying the full node.
+    async fn site_call_arg(&self, site_id: ObjectID) -> Result<CallArg> {
+        if let Some(initial_shared_version) = self.config.shared_object_versions.get(&site_id) {
+            tracing::debug!(%site_id, %initial_shared_version, "using the pinned shared version");
+            return Ok(CallArg::Object(ObjectArg::SharedObject {
+                id: site_id,
+                initial_shared_version: *initial_shared_version,
+                mutable: true,
+            }));
+        }
+        object_call_arg(
+            &self.sui_client().await?,
+            site_id,
+            self.active_address()?,
+            OBJECT_READ_ATTEMPTS,
+        )
+        .await
+    }
+
+    /// Estimates the gas cost of the updates on Sui, through a dry run.
+    async fn estimate_sui_updates<'b>(&self, updates: &SiteDataDiff<'b>) -> Result<u64> {
+        let ptb = self.build_ptb(updates).await?;
+        let transaction = self
+            .transaction_data(ptb, self.gas_coin_ref().await?)
+            .await?;
+        self.estimate_gas(&transaction).await
+    }
+
+    /// Builds the PTB to perform the updates on Sui.
+    async fn build_ptb<'b>(&self, updates: &SiteDataDiff<'b>) -> Result<ProgrammableTransaction> {
         let ptb = SitePtb::new(
-            self.config.package,
+            self.config.package(),
             Identifier::from_str(SITE_MODULE).expect("the str provided is valid"),
         )?;
 
         // Add the call arg if we are updating a site, or add the command to create a new site.
         let mut ptb = match &self.site_id {
             SiteIdentifier::ExistingSite(site_id) => {
-                ptb.with_call_arg(&self.wallet.get_object_ref(*site_id).await?.into())?
+                ptb.with_call_arg(&self.site_call_arg(*site_id).await?)?
             }
             SiteIdentifier::NewSite(site_name) => ptb.with_create_site(site_name)?,
         };
@@ -157,25 +1258,345 @@ impl SiteManager {
             ptb.transfer_site(self.active_address()?);
         }
 
-        self.sign_and_send_ptb(ptb.finish(), self.gas_coin_ref().await?)
-            .await
+        Ok(ptb.finish())
     }
 
-    async fn sign_and_send_ptb(
+    /// Creates the transaction data for the PTB, paying gas with the given coin.
+    async fn transaction_data(
         &self,
         programmable_transaction: ProgrammableTransaction,
         gas_coin: ObjectRef,
-    ) -> Result<SuiTransactionBlockResponse> {
+    ) -> Result<TransactionData> {
         let gas_price = self.wallet.get_reference_gas_price().await?;
-        let transaction = TransactionData::new_programmable(
+        Ok(TransactionData::new_programmable(
             self.active_address()?,
             vec![gas_coin],
             programmable_transaction,
             self.config.gas_budget(),
             gas_price,
+        ))
+    }
+
+    async fn sign_and_send_ptb(
+        &mut self,
+        programmable_transaction: ProgrammableTransaction,
+        gas_coin: ObjectRef,
+    ) -> Result<SuiTransactionBlockResponse> {
+        self.confirm_on_mainnet().await?;
+        let mut gas_coin = gas_coin;
+        let mut attempt = 1;
+        // The transaction signed in the previous attempt, to be resubmitted as is.
+        let mut signed = None;
+        let (result, estimate) = loop {
+            let (transaction, estimate) = match signed.take() {
+                Some(signed) => signed,
+                None => {
+                    let transaction = self
+                        .transaction_data(programmable_transaction.clone(), gas_coin)
+                        .await?;
+                    let estimate = self.check_max_budget(&transaction).await?;
+                    let transaction = self.sign_transaction(transaction, self.signing_intent()?)?;
+                    (transaction, estimate)
+                }
+            };
+            let digest = *transaction.digest();
+            let result = if self.config.general.no_confirm_local_execution {
+                execute_without_local_confirmation(&self.sui_client().await?, transaction.clone())
+                    .await
+            } else {
+                self.wallet
+                    .execute_transaction_may_fail(transaction.clone())
+                    .await
+            };
+            let result = match result {
+                Ok(response) => self.with_effects(response).await,
+                Err(error) => Err(error),
+            };
+            self.audit_transaction(
+                digest,
+                result
+                    .as_ref()
+                    .is_ok_and(|response| response.status_ok() == Some(true)),
+            );
+            match &result {
+                Err(error)
+                    if attempt < STALE_GAS_ATTEMPTS
+                        && is_stale_object_version(&format!("{error:#}")) =>
+                {
+                    // The gas coin was used by another transaction in the meantime. The same coin
+                    // is used again at its latest version, instead of selecting another coin.
+                    gas_coin = self.wallet.get_object_ref(gas_coin.0).await?;
+                    tracing::info!(
+                        attempt,
+                        ?gas_coin,
+                        "the version of the gas coin was stale, retrying with the latest version"
+                    );
+                    attempt += 1;
+                }
+                Err(error)
+                    if attempt < STALE_GAS_ATTEMPTS
+                        && is_configured_transient(
+                            &format!("{error:#}"),
+                            self.config
+                                .general
+                                .retryable_errors
+                                .as_deref()
+                                .unwrap_or_default(),
+                        ) =>
+                {
+                    // The signed transaction is resubmitted as is, with the same digest: if the
+                    // first submission was executed after all, the resubmission returns its
+                    // effects instead of executing it again.
+                    display::warning(format!(
+                        "the transaction failed with a transient error, retrying (attempt {} of \
+                        {STALE_GAS_ATTEMPTS}): {error:#}",
+                        attempt + 1
+                    ));
+                    tokio::time::sleep(TRANSIENT_ERROR_RETRY_DELAY).await;
+                    signed = Some((transaction, estimate));
+                    attempt += 1;
+                }
+                _ => break (result, estimate),
+            }
+        };
+        self.gas_coin = result
+            .as_ref()
+            .ok()
+            .and_then(|r| r.effects.as_ref())
+            .map(|effects| effects.gas_object().reference.to_object_ref());
+        tracing::debug!(gas_coin=?self.gas_coin, "updated the gas coin from the effects");
+        if let Some(effects) = result.as_ref().ok().and_then(|r| r.effects.as_ref()) {
+            let net_gas = effects.gas_cost_summary().net_gas_usage();
+            self.gas_spent += u64::try_from(net_gas).unwrap_or_default();
+            if let Some(estimate) = estimate {
+                *self.gas_estimated.get_or_insert(0) += estimate;
+            }
+        }
+        let gas_budget = self.config.gas_budget();
+        let response = result.map_err(|error| {
+            if is_insufficient_gas(&format!("{error:#}")) {
+                error.context(insufficient_gas_message(gas_budget, None))
+            } else {
+                error
+            }
+        })?;
+        check_execution_status(&response, gas_budget)?;
+        Ok(response)
+    }
+
+    /// Asks the user to confirm the operation, if the wallet is connected to Mainnet.
+    ///
+    /// The confirmation is asked at most once per command, before anything is paid for, and is
+    /// skipped with `--yes`.
+    async fn confirm_on_mainnet(&mut self) -> Result<()> {
+        if self.confirmed || self.config.general.yes {
+            return Ok(());
+        }
+        let chain_id = self
+            .sui_client()
+            .await?
+            .read_api()
+            .get_chain_identifier()
+            .await?;
+        tracing::debug!(%chain_id, "checking the network before the operation");
+        if self.config.general.network == Some(Network::Mainnet)
+            || Network::from_chain_id(&chain_id) == Some(Network::Mainnet)
+        {
+            confirm_interactively(&format!(
+                "About to run `{}` on Sui Mainnet (chain {chain_id}), signing as {}.",
+                self.operation_name(),
+                self.active_address()?
+            ))?;
+        }
+        self.confirmed = true;
+        Ok(())
+    }
+
+    /// Checks that the transaction does not bring the total gas spent over the maximum budget.
+    ///
+    /// The gas cost of the transaction is estimated through a dry run, and returned. Does nothing
+    /// if no maximum budget is set.
+    async fn check_max_budget(&self, transaction: &TransactionData) -> Result<Option<u64>> {
+        let Some(max_budget) = self.config.general.max_budget else {
+            return Ok(None);
+        };
+        let estimate = self.estimate_gas(transaction).await?;
+        tracing::debug!(estimate, gas_spent = self.gas_spent, "estimated gas cost");
+
+        if self.gas_spent + estimate > max_budget {
+            let site = match &self.site_id {
+                SiteIdentifier::ExistingSite(site_id) => format!("site object ID: {site_id}"),
+                SiteIdentifier::NewSite(_) => "the site was not created".to_owned(),
+            };
+            bail!(
+                "the estimated gas cost of the next transaction ({estimate} MIST) would exceed \
+                the maximum budget of {max_budget} MIST; gas spent so far: {} MIST; {site}",
+                self.gas_spent
+            );
+        }
+        Ok(Some(estimate))
+    }
+
+    /// Estimates the gas cost of the transaction through a dry run, ignoring storage rebates.
+    async fn estimate_gas(&self, transaction: &TransactionData) -> Result<u64> {
+        let effects = self
+            .sui_client()
+            .await?
+            .read_api()
+            .dry_run_transaction_block(transaction.clone())
+            .await?
+            .effects;
+        let gas_cost = effects.gas_cost_summary();
+        Ok(gas_cost.computation_cost + gas_cost.storage_cost)
+    }
+
+    /// Records the submission of a transaction in the audit log, if configured.
+    ///
+    /// The transaction was already submitted, so a failure to record it is only reported.
+    fn audit_transaction(&self, digest: TransactionDigest, success: bool) {
+        if let Err(error) = self.try_audit_transaction(digest, success) {
+            display::warning(format!(
+                "could not record the transaction {digest} in the audit log: {error:#}"
+            ));
+        }
+    }
+
+    fn try_audit_transaction(&self, digest: TransactionDigest, success: bool) -> Result<()> {
+        let entry = AuditLogEntry::new(
+            self.config.general.label.clone(),
+            digest,
+            self.active_address()?,
+            self.operation_name().to_owned(),
+            success,
+        );
+        tracing::info!(?entry, "transaction submitted");
+        if let Some(path) = self.config.general.audit_log.as_ref() {
+            entry.append_to(path)?;
+        }
+        Ok(())
+    }
+
+    /// The name of the operation performed on the site, as recorded in the audit log.
+    fn operation_name(&self) -> &'static str {
+        match self.site_id {
+            SiteIdentifier::ExistingSite(_) => "update-site",
+            SiteIdentifier::NewSite(_) => "create-site",
+        }
+    }
+
+    /// Signs the transaction with the active address, using the given intent.
+    ///
+    /// If a signer command is configured, the signature is delegated to it.
+    fn sign_transaction(
+        &self,
+        transaction: TransactionData,
+        intent: Intent,
+    ) -> Result<Transaction> {
+        let general = &self.config.general;
+        let signature = match (
+            general.signer_command.as_ref(),
+            general.keyring_service.as_ref(),
+        ) {
+            (Some(command), _) => CommandSigner::new(command.clone()).sign(
+                &transaction,
+                intent,
+                self.active_address()?,
+            )?,
+            (None, Some(service)) => {
+                let account = general
+                    .keyring_account
+                    .clone()
+                    .unwrap_or(self.active_address()?.to_string());
+                KeyringSigner::new(service.clone(), account).sign(
+                    &transaction,
+                    intent,
+                    self.active_address()?,
+                )?
+            }
+            (None, None) => self.wallet.config.keystore.sign_secure(
+                &self.active_address()?,
+                &transaction,
+                intent,
+            )?,
+        };
+        Ok(Transaction::from_data(transaction, vec![signature]))
+    }
+
+    /// Checks that the transactions of the active address can be signed, before any upload.
+    ///
+    /// Signs a transaction that is never submitted, so that a missing key, an unsupported key
+    /// scheme, or a failing signer command are reported before the blobs are stored.
+    fn check_signer(&self) -> Result<()> {
+        let sender = self.active_address()?;
+        let gas = (ObjectID::ZERO, SequenceNumber::new(), ObjectDigest::MIN);
+        let transaction = TransactionData::new_programmable(
+            sender,
+            vec![gas],
+            ProgrammableTransactionBuilder::new().finish(),
+            0,
+            0,
         );
-        let transaction = self.wallet.sign_transaction(&transaction);
-        self.wallet.execute_transaction_may_fail(transaction).await
+        self.sign_transaction(transaction, self.signing_intent()?)
+            .context(format!("cannot sign the transactions of {sender}"))?;
+        tracing::debug!(%sender, "checked that the transactions can be signed");
+        Ok(())
+    }
+
+    /// Returns the intent with which transactions are signed.
+    ///
+    /// Defaults to [`Intent::sui_transaction`]; the scope can be overridden in the config.
+    fn signing_intent(&self) -> Result<Intent> {
+        let Some(scope) = self.config.general.intent_scope else {
+            return Ok(Intent::sui_transaction());
+        };
+        let scope: IntentScope =
+            bcs::from_bytes(&[scope]).map_err(|_| anyhow!("invalid intent scope: {scope}"))?;
+        Ok(Intent {
+            scope,
+            ..Intent::sui_transaction()
+        })
+    }
+
+    /// Returns the response with its effects, reading the transaction by digest if the response
+    /// of the execution has none.
+    ///
+    /// Some RPC providers truncate or drop the content of the responses for large transactions,
+    /// even if the transaction succeeded. The effects are then read with narrower options, for a
+    /// bounded number of attempts, as the transaction may not be visible on the full node yet.
+    async fn with_effects(
+        &self,
+        response: SuiTransactionBlockResponse,
+    ) -> Result<SuiTransactionBlockResponse> {
+        if response.effects.is_some() {
+            return Ok(response);
+        }
+        let digest = response.digest;
+        tracing::info!(%digest, "the response has no effects, reading the transaction");
+        let client = self.sui_client().await?;
+        for attempt in 1..=OBJECT_READ_ATTEMPTS {
+            match client
+                .read_api()
+                .get_transaction_with_options(
+                    digest,
+                    SuiTransactionBlockResponseOptions::new()
+                        .with_effects()
+                        .with_object_changes(),
+                )
+                .await
+            {
+                Ok(response) if response.effects.is_some() => return Ok(response),
+                Ok(_) => tracing::debug!(%digest, attempt, "transaction effects not available yet"),
+                Err(error) => {
+                    tracing::debug!(%digest, attempt, ?error, "could not read transaction")
+                }
+            }
+            tokio::time::sleep(OBJECT_READ_DELAY).await;
+        }
+        bail!(
+            "the transaction {digest} was submitted, but its effects could not be read after {} \
+            attempts; check its status with `sui client tx-block {digest}`",
+            OBJECT_READ_ATTEMPTS
+        )
     }
 
     async fn sui_client(&self) -> Result<SuiClient> {
@@ -202,7 +1623,13 @@ impl SiteManager {
 
     /// Returns the [`ObjectRef`] of an arbitrary gas coin owned by the active wallet
     /// with a sufficient balance for the gas budget specified in the config.
+    ///
+    /// If a transaction was executed before, the gas coin it used is returned, at the version
+    /// found in its effects.
     async fn gas_coin_ref(&self) -> Result<ObjectRef> {
+        if let Some(gas_coin) = self.gas_coin {
+            return Ok(gas_coin);
+        }
         Ok(self
             .wallet
             .gas_for_owner_budget(
@@ -222,3 +1649,459 @@ impl SiteManager {
         matches!(self.site_id, SiteIdentifier::NewSite(_))
     }
 }
+
+/// Returns `true` if removing `n_removed` of the `n_existing` resources is more than
+/// `max_percent` of them.
+fn is_large_deletion(n_removed: usize, n_existing: usize, max_percent: u8) -> bool {
+    n_removed * 100 > n_existing * usize::from(max_percent)
+}
+
+/// Returns the resources for which the output of a batched `store` command has no valid result.
+///
+/// The results are matched to the resources by blob ID; a blob marked as invalid is not stored.
+fn unstored_resources<'r>(
+    resources: &[&'r Resource],
+    output: &StoreBatchOutput,
+) -> Vec<&'r Resource> {
+    let stored = output
+        .0
+        .iter()
+        .map(|result| &result.blob_store_result)
+        .filter(|result| !matches!(result, BlobStoreResult::MarkedInvalid { .. }))
+        .map(BlobStoreResult::blob_id)
+        .collect::<BTreeSet<_>>();
+    resources
+        .iter()
+        .filter(|resource| !stored.contains(&resource.info.blob_id))
+        .copied()
+        .collect()
+}
+
+/// Fails if the blobs of the resources total more than the maximum size, in bytes.
+///
+/// The resources are the ones actually stored, after skipping the duplicate contents and the blobs
+/// that are already stored.
+fn check_total_size(resources: &[&Resource], max_total_size: Option<u64>) -> Result<()> {
+    let Some(max_total_size) = max_total_size else {
+        return Ok(());
+    };
+    let total_size: u64 = resources
+        .iter()
+        .map(|resource| resource.unencoded_size as u64)
+        .sum();
+    tracing::debug!(
+        total_size,
+        max_total_size,
+        "checking the total size of the upload"
+    );
+    ensure!(
+        total_size <= max_total_size,
+        "the {} blobs to store total {total_size} bytes, above the maximum of {max_total_size} \
+        bytes set with `--max-total-size`; nothing was uploaded",
+        resources.len()
+    );
+    Ok(())
+}
+
+/// Returns the description of the changes to the immutable resources of the existing site.
+///
+/// An immutable resource changes if it is removed, or if its content or headers differ. The
+/// immutable paths that are not in the existing site can be freely created.
+fn immutable_changes(
+    existing_site: &SiteData,
+    local_site_data: &SiteData,
+    immutable: &BTreeSet<String>,
+) -> Vec<String> {
+    let find = |site: &SiteData, path: &str| {
+        site.resources()
+            .inner
+            .iter()
+            .find(|resource| resource.info.path == path)
+            .map(|resource| resource.info.clone())
+    };
+    immutable
+        .iter()
+        .filter_map(|path| {
+            let existing = find(existing_site, path)?;
+            match find(local_site_data, path) {
+                None => Some(format!("{path} would be removed")),
+                Some(local) if local != existing => Some(format!("{path} would be updated")),
+                Some(_) => None,
+            }
+        })
+        .collect()
+}
+
+/// Computes the moves of resources from the old path to the new path.
+///
+/// Fails if no resource matches the old path, or if any of the new paths is already in use.
+fn resource_moves(
+    existing_paths: &BTreeSet<&str>,
+    old_path: &str,
+    new_path: &str,
+    glob: bool,
+) -> Result<Vec<(String, String)>> {
+    let moves = if glob {
+        let (Some(old_prefix), Some(new_prefix)) =
+            (old_path.strip_suffix('*'), new_path.strip_suffix('*'))
+        else {
+            bail!("with `--glob`, both paths must end with `*`");
+        };
+        existing_paths
+            .iter()
+            .filter_map(|path| {
+                path.strip_prefix(old_prefix)
+                    .map(|rest| (path.to_string(), format!("{new_prefix}{rest}")))
+            })
+            .collect::<Vec<_>>()
+    } else {
+        ensure!(
+            existing_paths.contains(old_path),
+            "the resource {old_path} does not exist in the site"
+        );
+        vec![(old_path.to_owned(), new_path.to_owned())]
+    };
+
+    ensure!(!moves.is_empty(), "no resource matches the path {old_path}");
+    for (_, new) in moves.iter() {
+        ensure!(
+            !existing_paths.contains(new.as_str()),
+            "the resource {new} already exists in the site"
+        );
+    }
+    Ok(moves)
+}
+
+/// Parses an integer value of the protocol config, serialized either as a number, as a string, or
+/// as an object with a single field named after its type, e.g., `{"u32": "1024"}`.
+fn protocol_config_value(value: &serde_json::Value) -> Option<usize> {
+    match value {
+        serde_json::Value::Number(number) => number.as_u64()?.try_into().ok(),
+        serde_json::Value::String(string) => string.parse().ok(),
+        serde_json::Value::Object(fields) if fields.len() == 1 => {
+            protocol_config_value(fields.values().next()?)
+        }
+        _ => None,
+    }
+}
+
+/// Fails if the execution of the transaction failed.
+///
+/// A transaction that ran out of gas results in a dedicated error, suggesting a higher budget.
+fn check_execution_status(response: &SuiTransactionBlockResponse, gas_budget: u64) -> Result<()> {
+    let Some(effects) = response.effects.as_ref() else {
+        return Ok(());
+    };
+    if let SuiExecutionStatus::Failure { error } = effects.status() {
+        if is_insufficient_gas(error) {
+            bail!(insufficient_gas_message(
+                gas_budget,
+                Some(effects.gas_cost_summary().gas_used())
+            ));
+        }
+        bail!("error while processing the Sui transaction: {error}");
+    }
+    Ok(())
+}
+
+/// Returns `true` if the error of a transaction is due to a gas budget that is too low.
+fn is_insufficient_gas(error: &str) -> bool {
+    [
+        "InsufficientGas",
+        "Insufficient Gas",
+        "GasBudgetTooLow",
+        "Gas budget: ",
+    ]
+    .iter()
+    .any(|pattern| error.contains(pattern))
+}
+
+/// Returns `true` if the transaction was rejected because an owned input, e.g., the gas coin, was
+/// not at its latest version.
+fn is_stale_object_version(error: &str) -> bool {
+    [
+        "ObjectVersionUnavailableForConsumption",
+        "is not available for consumption",
+        "Could not find the referenced object",
+    ]
+    .iter()
+    .any(|pattern| error.contains(pattern))
+}
+
+/// Returns `true` if the error contains one of the substrings configured as transient with
+/// `--retryable-error`.
+///
+/// The configured substrings are layered on top of the stale gas coin versions, which are always
+/// retried; the errors caused by the transaction itself, e.g., running out of gas, are never
+/// retried unless configured.
+fn is_configured_transient(error: &str, retryable_errors: &[String]) -> bool {
+    retryable_errors
+        .iter()
+        .any(|pattern| !pattern.is_empty() && error.contains(pattern.as_str()))
+}
+
+/// Returns the error message for a transaction that failed for lack of gas.
+fn insufficient_gas_message(gas_budget: u64, gas_used: Option<u64>) -> String {
+    let used = gas_used
+        .map(|gas_used| format!(", after using {gas_used} MIST"))
+        .unwrap_or_default();
+    format!(
+        "the transaction ran out of gas with a budget of {gas_budget} MIST{used}; retry with a \
+        higher `--gas-budget`"
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use move_core_types::u256::U256;
+    use sui_types::{digests::TransactionDigest, event::EventID};
+
+    use super::*;
+    use crate::{
+        site::resource::ResourceSet,
+        types::HttpHeaders,
+        walrus::output::{BlobStoreResultWithPath, EventOrObjectId},
+    };
+
+    #[test]
+    fn test_blob_state_from_status() {
+        let certified = |end_epoch| BlobStatus::Permanent {
+            end_epoch,
+            is_certified: true,
+        };
+        assert_eq!(
+            BlobState::from_status(&certified(12), 11),
+            BlobState::Stored
+        );
+        assert_eq!(
+            BlobState::from_status(&certified(11), 11),
+            BlobState::Stored
+        );
+        // The blob expires at the end of the current epoch 10, so it is no longer stored in the
+        // epoch 11 required by a publish for one epoch.
+        assert_eq!(
+            BlobState::from_status(&certified(10), 11),
+            BlobState::Missing
+        );
+        assert_eq!(
+            BlobState::from_status(
+                &BlobStatus::Permanent {
+                    end_epoch: 12,
+                    is_certified: false,
+                },
+                11
+            ),
+            BlobState::Registered
+        );
+        assert_eq!(
+            BlobState::from_status(&BlobStatus::Nonexistent, 11),
+            BlobState::Missing
+        );
+    }
+
+    #[test]
+    fn test_is_insufficient_gas() {
+        assert!(is_insufficient_gas("InsufficientGas in command 3"));
+        assert!(is_insufficient_gas(
+            "Gas budget: 1000 is lower than min: 2000000."
+        ));
+        assert!(!is_insufficient_gas(
+            "MoveAbort(MoveLocation { module: site }, 1) in command 2"
+        ));
+        assert_eq!(
+            insufficient_gas_message(100, Some(100)),
+            "the transaction ran out of gas with a budget of 100 MIST, after using 100 MIST; \
+            retry with a higher `--gas-budget`"
+        );
+    }
+
+    #[test]
+    fn test_is_stale_object_version() {
+        assert!(is_stale_object_version(
+            "Transaction validator signing failed due to issues with transaction inputs: \
+            ObjectVersionUnavailableForConsumption { provided_obj_ref: (0x1, SequenceNumber(3)) }"
+        ));
+        assert!(!is_stale_object_version("InsufficientGas in command 3"));
+    }
+
+    #[test]
+    fn test_protocol_config_value() {
+        assert_eq!(
+            protocol_config_value(&serde_json::json!({"u32": "1024"})),
+            Some(1024)
+        );
+        assert_eq!(
+            protocol_config_value(&serde_json::json!("2048")),
+            Some(2048)
+        );
+        assert_eq!(protocol_config_value(&serde_json::json!(16)), Some(16));
+        assert_eq!(
+            protocol_config_value(&serde_json::json!({"f64": "0.5"})),
+            None
+        );
+        assert_eq!(
+            protocol_config_value(&serde_json::json!({"bool": true})),
+            None
+        );
+    }
+
+    #[test]
+    fn test_is_configured_transient() {
+        let retryable_errors = vec!["connection reset".to_owned(), String::new()];
+        assert!(is_configured_transient(
+            "error sending request: connection reset by peer",
+            &retryable_errors
+        ));
+        // The empty substring does not make every error transient.
+        assert!(!is_configured_transient(
+            "InsufficientGas",
+            &retryable_errors
+        ));
+        assert!(!is_configured_transient("connection reset", &[]));
+    }
+
+    #[test]
+    fn test_resource_moves() {
+        let existing_paths = BTreeSet::from(["/index.html", "/old/a.html", "/old/b/c.html"]);
+
+        let moves = resource_moves(&existing_paths, "/index.html", "/home.html", false).unwrap();
+        assert_eq!(
+            moves,
+            vec![("/index.html".to_owned(), "/home.html".to_owned())]
+        );
+
+        let moves = resource_moves(&existing_paths, "/old/*", "/new/*", true).unwrap();
+        assert_eq!(
+            moves,
+            vec![
+                ("/old/a.html".to_owned(), "/new/a.html".to_owned()),
+                ("/old/b/c.html".to_owned(), "/new/b/c.html".to_owned()),
+            ]
+        );
+
+        // The old path does not exist.
+        assert!(resource_moves(&existing_paths, "/missing.html", "/new.html", false).is_err());
+        assert!(resource_moves(&existing_paths, "/missing/*", "/new/*", true).is_err());
+        // The new path collides with an existing resource.
+        assert!(resource_moves(&existing_paths, "/old/a.html", "/index.html", false).is_err());
+        // Glob paths must end with `*`.
+        assert!(resource_moves(&existing_paths, "/old/", "/new/", true).is_err());
+    }
+
+    #[test]
+    fn test_is_large_deletion() {
+        assert!(!is_large_deletion(0, 0, 50));
+        assert!(!is_large_deletion(5, 10, 50));
+        assert!(is_large_deletion(6, 10, 50));
+        assert!(is_large_deletion(1, 10, 0));
+        assert!(!is_large_deletion(10, 10, 100));
+    }
+
+    #[test]
+    fn test_check_total_size() {
+        let resources: Vec<_> = [100, 250]
+            .into_iter()
+            .map(|size| {
+                Resource::new(
+                    format!("/{size}.bin"),
+                    PathBuf::from(format!("{size}.bin")),
+                    HttpHeaders(Default::default()),
+                    BlobId([0; BlobId::LENGTH]),
+                    U256::from(size as u8),
+                    size,
+                )
+            })
+            .collect();
+        let resources: Vec<_> = resources.iter().collect();
+        assert!(check_total_size(&resources, None).is_ok());
+        assert!(check_total_size(&resources, Some(350)).is_ok());
+        let error = check_total_size(&resources, Some(349)).unwrap_err();
+        assert!(error
+            .to_string()
+            .starts_with("the 2 blobs to store total 350 bytes, above the maximum of 349 bytes"));
+    }
+
+    #[test]
+    fn test_unstored_resources() {
+        let resources: Vec<_> = (1..=3)
+            .map(|content| {
+                Resource::new(
+                    format!("/{content}.html"),
+                    PathBuf::from(format!("{content}.html")),
+                    HttpHeaders(Default::default()),
+                    BlobId([content; BlobId::LENGTH]),
+                    U256::from(content),
+                    0,
+                )
+            })
+            .collect();
+        let resources: Vec<_> = resources.iter().collect();
+        let result = |blob_store_result, content| BlobStoreResultWithPath {
+            blob_store_result,
+            path: PathBuf::from(format!("{content}.html")),
+        };
+        // The first blob is stored, the second is marked as invalid, the third has no result.
+        let output = StoreBatchOutput(vec![
+            result(
+                BlobStoreResult::AlreadyCertified {
+                    blob_id: BlobId([1; BlobId::LENGTH]),
+                    event_or_object: EventOrObjectId::Object(ObjectID::ZERO),
+                    end_epoch: 10,
+                },
+                1,
+            ),
+            result(
+                BlobStoreResult::MarkedInvalid {
+                    blob_id: BlobId([2; BlobId::LENGTH]),
+                    event: EventID {
+                        tx_digest: TransactionDigest::random(),
+                        event_seq: 0,
+                    },
+                },
+                2,
+            ),
+        ]);
+        let unstored: Vec<_> = unstored_resources(&resources, &output)
+            .into_iter()
+            .map(|resource| resource.info.path.as_str())
+            .collect();
+        assert_eq!(unstored, ["/2.html", "/3.html"]);
+    }
+
+    fn site(resources: &[(&str, u8)]) -> SiteData {
+        SiteData::new(
+            ResourceSet::from_iter(resources.iter().map(|(path, content)| {
+                Resource::new(
+                    path.to_string(),
+                    PathBuf::from(path),
+                    HttpHeaders(Default::default()),
+                    BlobId([*content; BlobId::LENGTH]),
+                    U256::from(*content),
+                    0,
+                )
+            })),
+            None,
+        )
+    }
+
+    #[test]
+    fn test_immutable_changes() {
+        let existing = site(&[("/legal.pdf", 1), ("/terms.html", 2), ("/index.html", 3)]);
+        let immutable = BTreeSet::from([
+            "/legal.pdf".to_owned(),
+            "/terms.html".to_owned(),
+            "/new.pdf".to_owned(),
+        ]);
+        let local = site(&[("/legal.pdf", 1), ("/index.html", 4), ("/new.pdf", 5)]);
+        assert_eq!(
+            immutable_changes(&existing, &local, &immutable),
+            vec!["/terms.html would be removed"]
+        );
+        let local = site(&[("/legal.pdf", 6), ("/terms.html", 2)]);
+        assert_eq!(
+            immutable_changes(&existing, &local, &immutable),
+            vec!["/legal.pdf would be updated"]
+        );
+        assert!(immutable_changes(&existing, &existing, &immutable).is_empty());
+    }
+}
@@ -0,0 +1,243 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Plans of site updates, which can be reviewed before being applied.
+
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use move_core_types::u256::U256;
+use serde::{Deserialize, Serialize};
+use serde_with::{serde_as, DisplayFromStr};
+
+use super::{
+    manager::SiteIdentifier,
+    resource::{Resource, ResourceSet},
+    SiteData,
+    SiteDataDiff,
+};
+use crate::{
+    publish::WhenWalrusUpload,
+    summary::Summarizable,
+    types::{HttpHeaders, Range, Routes, SuiResource},
+    walrus::types::BlobId,
+};
+
+/// The planned operations to update a site, and the states of the site they were computed from.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct SitePlan {
+    /// The site to be updated.
+    pub site_id: SiteIdentifier,
+    /// The number of epochs for which to store the resources on Walrus.
+    pub epochs: u64,
+    /// Whether all the resources are replaced and uploaded to Walrus.
+    pub force: bool,
+    /// The human-readable list of the operations on the resources.
+    pub resource_operations: Vec<String>,
+    /// The human-readable description of the operations on the routes.
+    pub route_operations: String,
+    /// The number of blobs to be uploaded to Walrus.
+    pub estimated_uploads: usize,
+    /// The number of resource operations in each of the transactions of the update on Sui.
+    #[serde(default)]
+    pub transaction_sizes: Vec<usize>,
+    /// The estimated gas cost of the update on Sui, in MIST.
+    pub estimated_gas: Option<u64>,
+    /// The site on chain at the time of planning.
+    pub existing_site: PlannedSite,
+    /// The site to be published.
+    pub local_site: PlannedSite,
+}
+
+impl SitePlan {
+    pub fn new(
+        site_id: SiteIdentifier,
+        epochs: u64,
+        force: bool,
+        existing_site: &SiteData,
+        local_site: &SiteData,
+        site_updates: &SiteDataDiff,
+        chunks: &[SiteDataDiff],
+        estimated_gas: Option<u64>,
+    ) -> Self {
+        let when_upload = WhenWalrusUpload::from_force_flag(force);
+        let summary = site_updates.summary(&when_upload);
+        Self {
+            site_id,
+            epochs,
+            force,
+            resource_operations: summary
+                .resource_ops
+                .iter()
+                .map(|op| op.to_summary())
+                .collect(),
+            route_operations: summary.route_ops.to_summary(),
+            estimated_uploads: site_updates.get_walrus_updates(&when_upload).len(),
+            transaction_sizes: chunks
+                .iter()
+                .map(|chunk| chunk.resource_ops.len())
+                .collect(),
+            estimated_gas,
+            existing_site: existing_site.into(),
+            local_site: local_site.into(),
+        }
+    }
+
+    /// Reads the plan from a JSON file.
+    pub fn read(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .context(format!("unable to read the plan file {}", path.display()))?;
+        serde_json::from_str(&contents)
+            .context(format!("unable to parse the plan file {}", path.display()))
+    }
+
+    /// Writes the plan to a JSON file.
+    pub fn write(&self, path: &Path) -> Result<()> {
+        std::fs::write(path, serde_json::to_string_pretty(self)?)
+            .context(format!("unable to write the plan file {}", path.display()))
+    }
+}
+
+impl Summarizable for SitePlan {
+    fn to_summary(&self) -> String {
+        let resource_str = if self.resource_operations.is_empty() {
+            "No resource operation needs to be performed\n".to_owned()
+        } else {
+            format!(
+                "Resource operations planned:\n{}\n",
+                self.resource_operations
+                    .iter()
+                    .map(|op| format!("  - {op}"))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            )
+        };
+        let gas_str = self
+            .estimated_gas
+            .map(|gas| format!("{gas} MIST"))
+            .unwrap_or("unknown".to_owned());
+        let transactions_str = match self.transaction_sizes.as_slice() {
+            [] | [_] => format!("{}", self.transaction_sizes.len()),
+            sizes => format!(
+                "{} (resource operations per transaction: {})",
+                sizes.len(),
+                sizes
+                    .iter()
+                    .map(|size| size.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        };
+        format!(
+            "{resource_str}{}\nEstimated blob uploads: {}\nEstimated transactions on Sui: \
+            {transactions_str}\nEstimated gas cost: {gas_str}",
+            self.route_operations, self.estimated_uploads
+        )
+    }
+}
+
+/// The serializable representation of [`SiteData`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct PlannedSite {
+    resources: Vec<PlannedResource>,
+    routes: Option<Routes>,
+}
+
+impl From<&SiteData> for PlannedSite {
+    fn from(site: &SiteData) -> Self {
+        Self {
+            resources: site.resources.inner.iter().map(Into::into).collect(),
+            routes: site.routes.clone(),
+        }
+    }
+}
+
+impl From<&PlannedSite> for SiteData {
+    fn from(site: &PlannedSite) -> Self {
+        SiteData::new(
+            ResourceSet::from_iter(site.resources.iter().cloned().map(Resource::from)),
+            site.routes.clone(),
+        )
+    }
+}
+
+/// The serializable representation of a [`Resource`].
+#[serde_as]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PlannedResource {
+    path: String,
+    headers: BTreeMap<String, String>,
+    #[serde_as(as = "DisplayFromStr")]
+    blob_id: BlobId,
+    #[serde_as(as = "DisplayFromStr")]
+    blob_hash: U256,
+    range: Option<Range>,
+    full_path: PathBuf,
+    unencoded_size: usize,
+}
+
+impl From<&Resource> for PlannedResource {
+    fn from(resource: &Resource) -> Self {
+        Self {
+            path: resource.info.path.clone(),
+            headers: resource.info.headers.0.clone(),
+            blob_id: resource.info.blob_id,
+            blob_hash: resource.info.blob_hash,
+            range: resource.info.range.clone(),
+            full_path: resource.full_path.clone(),
+            unencoded_size: resource.unencoded_size,
+        }
+    }
+}
+
+impl From<PlannedResource> for Resource {
+    fn from(resource: PlannedResource) -> Self {
+        Self {
+            info: SuiResource {
+                path: resource.path,
+                headers: HttpHeaders(resource.headers),
+                blob_id: resource.blob_id,
+                blob_hash: resource.blob_hash,
+                range: resource.range,
+            },
+            unencoded_size: resource.unencoded_size,
+            full_path: resource.full_path,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_planned_site_roundtrip() {
+        let resource = Resource::new(
+            "/index.html".to_owned(),
+            PathBuf::from("/my/site/index.html"),
+            HttpHeaders(BTreeMap::from([(
+                "content-type".to_owned(),
+                "text/html".to_owned(),
+            )])),
+            BlobId([1; BlobId::LENGTH]),
+            U256::from(42u64),
+            10,
+        );
+        let site = SiteData::new(
+            ResourceSet::from_iter([resource]),
+            Some(Routes(BTreeMap::from([(
+                "/*".to_owned(),
+                "/index.html".to_owned(),
+            )]))),
+        );
+
+        let serialized = serde_json::to_string(&PlannedSite::from(&site)).unwrap();
+        let deserialized: PlannedSite = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(SiteData::from(&deserialized), site);
+    }
+}
@@ -0,0 +1,64 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Download of the resources declared with a URL in `ws-resources.json`.
+
+use std::{fs::File, io::Write, path::Path, time::Duration};
+
+use anyhow::{ensure, Context, Result};
+use reqwest::header::CONTENT_TYPE;
+
+use crate::util::http_client_builder;
+
+/// The maximum duration of the download of a remote resource, including its content.
+const DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Downloads the content at the URL to the destination file, returning the content type of the
+/// response, if any.
+///
+/// The content is written to the file as it is received, and never fully held in memory. Fails if
+/// the response status is not a success, or if the download takes longer than
+/// [`DOWNLOAD_TIMEOUT`].
+pub(crate) async fn download(
+    url: &str,
+    destination: &Path,
+    proxy: Option<&str>,
+) -> Result<Option<String>> {
+    let client = http_client_builder(proxy)?
+        .timeout(DOWNLOAD_TIMEOUT)
+        .build()?;
+    let mut response = client
+        .get(url)
+        .send()
+        .await
+        .with_context(|| format!("could not fetch {url}"))?;
+    ensure!(
+        response.status().is_success(),
+        "fetching {url} failed with status {}",
+        response.status()
+    );
+    let content_type = response
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_owned);
+    if let Some(parent) = destination.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut file = File::create(destination)
+        .with_context(|| format!("could not create {}", destination.display()))?;
+    while let Some(chunk) = response
+        .chunk()
+        .await
+        .with_context(|| format!("the download of {url} was interrupted"))?
+    {
+        file.write_all(&chunk)?;
+    }
+    tracing::debug!(
+        url,
+        ?destination,
+        ?content_type,
+        "downloaded the remote resource"
+    );
+    Ok(content_type)
+}
@@ -7,25 +7,55 @@ use std::{
     collections::{BTreeMap, BTreeSet},
     fmt::{self, Display},
     fs,
-    io::Write,
+    io::{Read, Write},
     num::NonZeroU16,
     path::{Path, PathBuf},
+    str::FromStr,
 };
 
-use anyhow::{anyhow, Context, Result};
+use anyhow::{anyhow, bail, ensure, Context, Result};
+use clap::ValueEnum;
 use fastcrypto::hash::{HashFunction, Sha256};
 use flate2::{write::GzEncoder, Compression};
-use futures::future::try_join_all;
+use futures::future::join_all;
 use move_core_types::u256::U256;
+use tempfile::TempDir;
+use tokio::sync::Semaphore;
 
-use super::SiteData;
+use super::{reserved_field_names, SiteData};
 use crate::{
+    display,
     publish::WhenWalrusUpload,
-    site::{config::WSResources, content::ContentType},
+    site::{
+        base_href::BaseHref,
+        cache::CachePolicy,
+        config::{check_remote_resource_path, WSResources},
+        content::ContentType,
+        csp::CspGenerator,
+        remote::download,
+    },
     types::{HttpHeaders, SuiResource},
+    util::ResourceSelection,
     walrus::{types::BlobId, Walrus},
 };
 
+/// The maximum length of a resource path, in bytes.
+///
+/// The paths are the names of the dynamic fields of the site, and are passed as pure arguments to
+/// the transactions; the limit stays below the 16 KiB limit on pure arguments, leaving room for the
+/// BCS length prefix.
+pub(crate) const MAX_RESOURCE_PATH_BYTES: usize = 16 * 1024 - 8;
+
+/// The size in bytes above which the files that are not HTML pages are hashed in chunks, without
+/// loading them in memory.
+const STREAMING_HASH_THRESHOLD: u64 = 16 * 1024 * 1024;
+
+/// The headers that make a document cross-origin isolated, set on the HTML pages of WASM apps.
+const CROSS_ORIGIN_ISOLATION_HEADERS: [(&str, &str); 2] = [
+    ("cross-origin-opener-policy", "same-origin"),
+    ("cross-origin-embedder-policy", "require-corp"),
+];
+
 /// The resource that is to be created or updated on Sui.
 ///
 /// This struct contains additional information that is not stored on chain, compared to
@@ -103,6 +133,7 @@ impl Display for Resource {
 /// Updates to resources are implemented as deleting the outdated
 /// resource and adding a new one. Two [`Resources`][Resource] are
 /// different if their respective [`SuiResource`] differ.
+#[derive(Clone, Copy)]
 pub enum ResourceOp<'a> {
     Deleted(&'a Resource),
     Created(&'a Resource),
@@ -136,7 +167,8 @@ impl<'a> ResourceOp<'a> {
     /// Returns if the operation needs to be uploaded to Walrus.
     pub fn is_walrus_update(&self, when_upload: &WhenWalrusUpload) -> bool {
         matches!(self, ResourceOp::Created(_))
-            || (when_upload.is_always() && matches!(self, ResourceOp::Unchanged(_)))
+            || (!matches!(when_upload, WhenWalrusUpload::Modified)
+                && matches!(self, ResourceOp::Unchanged(_)))
     }
 
     /// Returns true if the operation modifies a resource.
@@ -286,6 +318,162 @@ impl Display for ResourceSet {
     }
 }
 
+/// A directory whose contents are published under a path prefix of the site.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Mount {
+    /// The directory on disk.
+    pub directory: PathBuf,
+    /// The prefix of the resource paths, without trailing `/` (empty for the site root).
+    pub prefix: String,
+}
+
+impl Mount {
+    /// Mounts the directory at the root of the site.
+    pub fn root(directory: PathBuf) -> Self {
+        Self {
+            directory,
+            prefix: String::new(),
+        }
+    }
+
+    /// Converts the full path of a file in the mounted directory to the on-chain resource path.
+    pub fn resource_path(&self, full_path: &Path) -> Result<String> {
+        Ok(format!(
+            "{}{}",
+            self.prefix,
+            full_path_to_resource_path(full_path, &self.directory)?
+        ))
+    }
+}
+
+impl FromStr for Mount {
+    type Err = anyhow::Error;
+
+    /// Parses a mount in the `<DIR>:<PATH>` format, e.g., `docs:/docs`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (directory, prefix) = s
+            .rsplit_once(':')
+            .ok_or_else(|| anyhow!("invalid mount `{s}`: expected the format `<DIR>:<PATH>`"))?;
+        if !prefix.starts_with('/') {
+            bail!("invalid mount `{s}`: the mount path must start with `/`");
+        }
+        Ok(Self {
+            directory: PathBuf::from(directory),
+            prefix: prefix.trim_end_matches('/').replace('\\', "/"),
+        })
+    }
+}
+
+/// The content type set for the resource at an exact path, overriding the one inferred from the
+/// extension.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ContentTypeOverride {
+    /// The resource path, e.g., `/manifest`.
+    pub path: String,
+    /// The content type, e.g., `application/manifest+json`.
+    pub content_type: String,
+}
+
+impl FromStr for ContentTypeOverride {
+    type Err = anyhow::Error;
+
+    /// Parses a content type override in the `<PATH>=<MIME>` format, e.g.,
+    /// `/manifest=application/manifest+json`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (path, content_type) = s.split_once('=').ok_or_else(|| {
+            anyhow!("invalid content type `{s}`: expected the format `<PATH>=<MIME>`")
+        })?;
+        if !path.starts_with('/') {
+            bail!("invalid content type `{s}`: the resource path must start with `/`");
+        }
+        if content_type.is_empty() {
+            bail!("invalid content type `{s}`: the content type must not be empty");
+        }
+        Ok(Self {
+            path: path.to_owned(),
+            content_type: content_type.to_owned(),
+        })
+    }
+}
+
+/// How to handle the symbolic links found in the site directory.
+#[derive(Debug, ValueEnum, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SymlinkPolicy {
+    /// Follow the symbolic links, skipping the ones that create cycles.
+    Follow,
+    /// Ignore the symbolic links.
+    #[default]
+    Skip,
+    /// Fail, listing the symbolic links found.
+    Error,
+}
+
+/// The options of the reading of the resources, as set when publishing.
+#[derive(Debug, Default)]
+pub(crate) struct ResourceOptions {
+    /// Whether to include dotfiles and dot-directories (e.g., `.well-known/`) in the site.
+    pub include_dotfiles: bool,
+    /// If set, only the files whose resource path is selected are read.
+    pub only: Option<ResourceSelection>,
+    /// Whether to set the `etag` header of the resources from their content hash.
+    pub emit_etags: bool,
+    /// How to handle symbolic links in the site directory.
+    pub symlinks: SymlinkPolicy,
+    /// The content types set for exact resource paths, which take precedence over the ones in
+    /// the ws-resources.json file.
+    pub content_types: Vec<ContentTypeOverride>,
+    /// How to set the `cache-control` headers not set in the ws-resources.json file.
+    pub cache_policy: CachePolicy,
+    /// Whether to serve the HTML pages with the headers that enable cross-origin isolation, as
+    /// needed by WASM apps that use `SharedArrayBuffer`.
+    pub wasm_app: bool,
+    /// The file extensions and content types allowed in the site; if empty, all are allowed.
+    pub allowed_types: Vec<String>,
+    /// If set, the `Content-Security-Policy` header of the HTML pages is generated from the
+    /// sources they reference.
+    pub csp: Option<CspGenerator>,
+    /// Whether to allow the resource paths named like the reserved fields of the site.
+    pub allow_reserved_paths: bool,
+    /// Whether to skip the files and directories that cannot be read, instead of failing.
+    pub skip_unreadable: bool,
+    /// The proxy through which the remote resources are downloaded, if any.
+    pub proxy: Option<String>,
+    /// Whether to only store the headers set explicitly, in ws-resources.json or with the
+    /// content type overrides, and none of the ones added automatically.
+    pub no_auto_headers: bool,
+    /// The lowercase names of the automatically added headers that are not stored.
+    pub excluded_headers: Vec<String>,
+    /// If set, the `<base>` element injected in the HTML pages.
+    ///
+    /// The rewritten pages are stored from a temporary directory.
+    pub base_href: Option<BaseHref>,
+    /// Whether to read the resources without the Walrus CLI, e.g., to compare them with a
+    /// lockfile.
+    ///
+    /// The blob IDs are then not computed and left as zeros, and the remote resources are skipped.
+    pub offline: bool,
+}
+
+impl ResourceOptions {
+    /// Removes the headers added automatically that are not stored, keeping the explicit ones.
+    fn retain_stored_headers(
+        &self,
+        headers: &mut BTreeMap<String, String>,
+        explicit_headers: &BTreeSet<String>,
+    ) {
+        headers.retain(|name, _| explicit_headers.contains(name) || self.keeps_auto_header(name));
+    }
+
+    /// Whether the header, added automatically to a resource, is stored.
+    fn keeps_auto_header(&self, name: &str) -> bool {
+        !self.no_auto_headers
+            && !self
+                .excluded_headers
+                .iter()
+                .any(|excluded| excluded == name)
+    }
+}
+
 /// Loads and manages the set of resources composing the site.
 #[derive(Debug)]
 pub(crate) struct ResourceManager {
@@ -295,8 +483,18 @@ pub(crate) struct ResourceManager {
     pub ws_resources: Option<WSResources>,
     /// The ws-resource file path.
     pub ws_resources_path: Option<PathBuf>,
+    /// The paths of the `ws-resources.json` files of the subdirectories, merged in
+    /// `ws_resources`, which are not resources either.
+    pub nested_ws_resources_paths: Vec<PathBuf>,
     /// The number of shards of the Walrus system.
     pub n_shards: NonZeroU16,
+    /// How the resources are read.
+    pub options: ResourceOptions,
+    /// Bounds the number of files that are hashed at the same time.
+    hash_permits: Semaphore,
+    /// The private directory of the files written while reading the site, e.g., the downloaded
+    /// remote resources and the pages rewritten with the base href, from which they are stored.
+    local_files: TempDir,
 }
 
 impl ResourceManager {
@@ -304,28 +502,86 @@ impl ResourceManager {
         walrus: Walrus,
         ws_resources: Option<WSResources>,
         ws_resources_path: Option<PathBuf>,
+        nested_ws_resources_paths: Vec<PathBuf>,
+        options: ResourceOptions,
     ) -> Result<Self> {
-        let n_shards = walrus.info(false).await?.n_shards;
+        let n_shards = if options.offline {
+            NonZeroU16::MIN
+        } else {
+            walrus.info(false).await?.n_shards
+        };
         Ok(ResourceManager {
             walrus,
             ws_resources,
             ws_resources_path,
+            nested_ws_resources_paths,
             n_shards,
+            options,
+            hash_permits: hash_permits(),
+            local_files: local_files()?,
         })
     }
 
+    /// Returns the directory of the files written while reading the site, which must be kept until
+    /// the resources are stored.
+    pub fn into_local_files(self) -> TempDir {
+        self.local_files
+    }
+
+    /// Returns the content type set for the resource path, if any.
+    ///
+    /// The overrides given to the manager take precedence over the `content_types` of the
+    /// ws-resources.json file.
+    fn content_type_override(&self, resource_path: &str) -> Option<&str> {
+        self.options
+            .content_types
+            .iter()
+            .rev()
+            .find(|content_type| content_type.path == resource_path)
+            .map(|content_type| content_type.content_type.as_str())
+            .or_else(|| {
+                self.ws_resources
+                    .as_ref()
+                    .and_then(|config| config.content_types.as_ref())
+                    .and_then(|content_types| content_types.get(resource_path))
+                    .map(String::as_str)
+            })
+    }
+
     /// Read a resource at a path.
     ///
     /// Ignores empty files.
-    pub async fn read_resource(&self, full_path: &Path, root: &Path) -> Result<Option<Resource>> {
+    pub async fn read_resource(&self, full_path: &Path, mount: &Mount) -> Result<Option<Resource>> {
+        self.read_resource_with_content_type(full_path, mount, None)
+            .await
+    }
+
+    /// Reads a resource at a path, with the given content type instead of the one inferred from
+    /// the extension.
+    ///
+    /// The content types set in ws-resources.json or with the overrides still take precedence.
+    async fn read_resource_with_content_type(
+        &self,
+        full_path: &Path,
+        mount: &Mount,
+        default_content_type: Option<&str>,
+    ) -> Result<Option<Resource>> {
         if let Some(ws_path) = &self.ws_resources_path {
             if full_path == ws_path {
                 tracing::debug!(?full_path, "ignoring the ws-resources config file");
                 return Ok(None);
             }
         }
+        if self
+            .nested_ws_resources_paths
+            .iter()
+            .any(|path| path == full_path)
+        {
+            tracing::debug!(?full_path, "ignoring the nested ws-resources config file");
+            return Ok(None);
+        }
 
-        let resource_path = full_path_to_resource_path(full_path, root)?;
+        let resource_path = mount.resource_path(full_path)?;
         let mut http_headers: BTreeMap<String, String> = self
             .ws_resources
             .as_ref()
@@ -342,6 +598,8 @@ impl ResourceManager {
                     .collect()
             })
             .unwrap_or_default();
+        // The explicit headers are never stripped, unlike the ones added automatically.
+        let mut explicit_headers = http_headers.keys().cloned().collect::<BTreeSet<_>>();
 
         let extension = full_path
             .extension()
@@ -362,58 +620,269 @@ impl ResourceManager {
             );
 
         // Read the content type.
-        let content_type =
-            ContentType::try_from_extension(extension.ok_or_else(|| {
+        let content_type = match default_content_type {
+            Some(content_type) => content_type.to_owned(),
+            None => ContentType::try_from_extension(extension.ok_or_else(|| {
                 anyhow!("Could not read file extension for {}", full_path.display())
             })?)
-            .unwrap_or(ContentType::ApplicationOctetstream); // Default ContentType.
+            .ok()
+            .or_else(|| sniff_content_type(full_path))
+            .unwrap_or(ContentType::ApplicationOctetstream) // Default ContentType.
+            .to_string(),
+        };
+
+        // The content type set for the exact path wins over the headers and the extension.
+        if let Some(content_type) = self.content_type_override(&resource_path) {
+            http_headers.insert("content-type".to_string(), content_type.to_owned());
+            explicit_headers.insert("content-type".to_string());
+        }
+
+        // The allowed methods are declared explicitly, but an `allow` header in ws-resources.json
+        // wins.
+        if let Some(allow) = self
+            .ws_resources
+            .as_ref()
+            .and_then(|config| config.allow_header(&resource_path))
+        {
+            http_headers.entry("allow".to_string()).or_insert(allow);
+            explicit_headers.insert("allow".to_string());
+        }
 
         // If content-type not specified in ws-resources.yaml, parse it from the extension.
         http_headers
             .entry("content-type".to_string())
-            .or_insert(content_type.to_string());
+            .or_insert(content_type);
+        ensure!(
+            is_allowed_type(
+                &self.options.allowed_types,
+                extension,
+                &http_headers["content-type"]
+            ),
+            "the file {} has content type {}, which is not allowed by `--allow-types`",
+            full_path.display(),
+            http_headers["content-type"]
+        );
 
-        let plain_content: Vec<u8> = std::fs::read(full_path)?;
-        let output = self
-            .walrus
-            .blob_id(full_path.to_owned(), Some(self.n_shards))
-            .await
-            .context(format!(
-                "error while computing the blob id for path: {}",
-                full_path.to_string_lossy()
-            ))?;
+        // The headers in ws-resources.json take precedence over the cache policy.
+        if let Some(cache_control) = self
+            .options
+            .cache_policy
+            .cache_control(&resource_path, &http_headers["content-type"])
+        {
+            http_headers
+                .entry("cache-control".to_string())
+                .or_insert(cache_control.to_owned());
+        }
+
+        // Cross-origin isolation is only decided by the headers of the document.
+        if self.options.wasm_app && http_headers["content-type"].starts_with("text/html") {
+            for (name, value) in CROSS_ORIGIN_ISOLATION_HEADERS {
+                http_headers
+                    .entry(name.to_string())
+                    .or_insert(value.to_string());
+            }
+        }
 
         // Hash the contents of the file - this will be contained in the site::Resource
-        // to verify the integrity of the blob when fetched from an aggregator.
-        let mut hash_function = Sha256::default();
-        hash_function.update(&plain_content);
-        let blob_hash: [u8; 32] = hash_function.finalize().digest;
+        // to verify the integrity of the blob when fetched from an aggregator. The content of the
+        // HTML pages is kept, as they can be rewritten and scanned for the CSP.
+        let is_html = http_headers["content-type"].starts_with("text/html");
+        let (plain_content, blob_hash, size) = self.read_and_hash(full_path, is_html).await?;
+        let rewritten = match plain_content.as_deref() {
+            Some(content) => {
+                self.with_base_href(&resource_path, &http_headers["content-type"], content)?
+            }
+            None => None,
+        };
+        let (full_path, plain_content, blob_hash, size) = match rewritten {
+            Some((path, content)) => {
+                let blob_hash = resource_hash(&content);
+                let size = content.len();
+                (path, Some(content), blob_hash, size)
+            }
+            None => (full_path.to_owned(), plain_content, blob_hash, size),
+        };
+        if let (Some(csp), Some(content)) = (&self.options.csp, plain_content.as_deref()) {
+            if is_html {
+                http_headers
+                    .entry(csp.header_name().to_string())
+                    .or_insert_with(|| csp.policy(&String::from_utf8_lossy(content)));
+            }
+        }
+        if self.options.emit_etags {
+            http_headers
+                .entry("etag".to_string())
+                .or_insert(resource_etag(&blob_hash));
+        }
+
+        // Audio and video resources are served with their length, so that browsers can show their
+        // duration, unless specified otherwise in ws-resources.json. No `accept-ranges` header is
+        // added, as the portal always serves the whole resource.
+        if is_streamable_media(&http_headers["content-type"]) {
+            http_headers
+                .entry("content-length".to_string())
+                .or_insert(size.to_string());
+        }
+        // The content type is still used for the checks above, even if it is not stored.
+        self.options
+            .retain_stored_headers(&mut http_headers, &explicit_headers);
+        let blob_id = if self.options.offline {
+            BlobId([0; BlobId::LENGTH])
+        } else {
+            self.walrus
+                .blob_id(full_path.to_owned(), Some(self.n_shards))
+                .await
+                .context(format!(
+                    "error while computing the blob id for path: {}",
+                    full_path.to_string_lossy()
+                ))?
+                .blob_id
+        };
 
         Ok(Some(Resource::new(
             resource_path,
             full_path.to_owned(),
             HttpHeaders(http_headers),
-            output.blob_id,
-            U256::from_le_bytes(&blob_hash),
-            plain_content.len(),
+            blob_id,
+            blob_hash,
+            size,
         )))
     }
 
-    /// Recursively iterate a directory and load all [`Resources`][Resource] within.
-    pub async fn read_dir(&mut self, root: &Path) -> Result<SiteData> {
-        let resource_paths = Self::iter_dir(root, root)?;
-        let resources = ResourceSet::from_iter(
-            try_join_all(
-                resource_paths
-                    .iter()
-                    .map(|(full_path, root)| self.read_resource(full_path, root)),
-            )
-            .await
-            .context("error in loading one of the resources")?
-            .into_iter()
-            .flatten(),
-        );
+    /// Injects the `<base>` element in the HTML page, if set, and writes the rewritten page to the
+    /// directory of the local files, from which it is stored.
+    ///
+    /// Returns the path and the content of the rewritten page, or `None` if the page is unchanged.
+    /// The pages that are not valid UTF-8 are not rewritten, with a warning.
+    fn with_base_href(
+        &self,
+        resource_path: &str,
+        content_type: &str,
+        content: &[u8],
+    ) -> Result<Option<(PathBuf, Vec<u8>)>> {
+        let Some(base_href) = self.options.base_href.as_ref() else {
+            return Ok(None);
+        };
+        if !content_type.starts_with("text/html") {
+            return Ok(None);
+        }
+        let Ok(page) = std::str::from_utf8(content) else {
+            display::check_warning(format!(
+                "not injecting the base href in {resource_path}, which is not valid UTF-8"
+            ))?;
+            return Ok(None);
+        };
+        let Some(rewritten) = base_href.apply(page) else {
+            tracing::debug!(resource_path, "not injecting the base href");
+            return Ok(None);
+        };
+        let path = self
+            .local_files
+            .path()
+            .join("base-href")
+            .join(resource_path.trim_start_matches('/'));
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&path, &rewritten).context(format!(
+            "could not write the rewritten page {}",
+            path.display()
+        ))?;
+        Ok(Some((path, rewritten.into_bytes())))
+    }
 
+    /// Reads and hashes the file on the blocking thread pool, as hashing is CPU-bound, and returns
+    /// its content, hash, and size.
+    ///
+    /// At most as many files as the available cores are hashed at the same time. Unless
+    /// `keep_content` is set, the files above [`STREAMING_HASH_THRESHOLD`] are hashed in chunks,
+    /// and their content is not returned, so that the memory used does not grow with their size.
+    async fn read_and_hash(
+        &self,
+        full_path: &Path,
+        keep_content: bool,
+    ) -> Result<(Option<Vec<u8>>, U256, usize)> {
+        let _permit = self.hash_permits.acquire().await?;
+        let full_path = full_path.to_owned();
+        tokio::task::spawn_blocking(move || -> Result<(Option<Vec<u8>>, U256, usize)> {
+            let size = fs::metadata(&full_path)?.len();
+            if !keep_content && size > STREAMING_HASH_THRESHOLD {
+                tracing::debug!(?full_path, size, "hashing the large file in chunks");
+                let blob_hash = resource_hash_reader(fs::File::open(&full_path)?)?;
+                return Ok((None, blob_hash, size as usize));
+            }
+            let plain_content = fs::read(&full_path)?;
+            let blob_hash = resource_hash(&plain_content);
+            let size = plain_content.len();
+            Ok((Some(plain_content), blob_hash, size))
+        })
+        .await?
+    }
+
+    /// Recursively iterate the mounted directories and load all [`Resources`][Resource] within.
+    ///
+    /// The resources of each mount are placed under the mount's path prefix. Returns an error if
+    /// two files map to the same resource path.
+    pub async fn read_mounts(&mut self, mounts: &[Mount]) -> Result<SiteData> {
+        let mut resource_paths = vec![];
+        for mount in mounts {
+            for full_path in self.iter_dir(&mount.directory)? {
+                if let Some(selection) = self.options.only.as_ref() {
+                    if !selection.matches(&mount.resource_path(&full_path)?) {
+                        continue;
+                    }
+                }
+                resource_paths.push((full_path, mount));
+            }
+        }
+        let results = join_all(
+            resource_paths
+                .iter()
+                .map(|(full_path, mount)| self.read_resource(full_path, mount)),
+        )
+        .await;
+        let mut resources = vec![];
+        let mut unreadable = vec![];
+        for ((full_path, _), result) in resource_paths.iter().zip(results) {
+            match result {
+                Ok(resource) => resources.extend(resource),
+                Err(error) if self.options.skip_unreadable && is_io_error(&error) => {
+                    tracing::debug!(?full_path, ?error, "skipping the unreadable file");
+                    unreadable.push((full_path.clone(), error.root_cause().to_string()));
+                }
+                Err(error) => return Err(error.context("error in loading one of the resources")),
+            }
+        }
+        warn_unreadable(&unreadable)?;
+        resources.extend(self.read_remote_resources().await?);
+
+        if let Some(error_pages) = self
+            .ws_resources
+            .as_ref()
+            .and_then(|config| config.error_pages.as_ref())
+        {
+            let error_page_resources =
+                error_page_resources(&resources, error_pages, self.options.only.as_ref())?;
+            resources.extend(error_page_resources);
+        }
+
+        let mut seen: BTreeMap<&str, &Path> = BTreeMap::new();
+        for resource in resources.iter() {
+            check_resource_path_length(&resource.info.path)?;
+            if !self.options.allow_reserved_paths {
+                check_reserved_path(&resource.info.path)?;
+            }
+            if let Some(other) = seen.insert(&resource.info.path, &resource.full_path) {
+                bail!(
+                    "the files {} and {} are both mapped to the resource path {}",
+                    other.display(),
+                    resource.full_path.display(),
+                    resource.info.path
+                );
+            }
+        }
+
+        let resources = ResourceSet::from_iter(resources);
         Ok(SiteData::new(
             resources,
             self.ws_resources
@@ -422,19 +891,417 @@ impl ResourceManager {
         ))
     }
 
-    fn iter_dir(start: &Path, root: &Path) -> Result<Vec<(PathBuf, PathBuf)>> {
+    /// Downloads the remote resources declared in ws-resources.json, and reads them.
+    ///
+    /// The content is downloaded to the directory of the local files, from which the blobs are
+    /// stored. Offline, the remote resources are skipped.
+    async fn read_remote_resources(&self) -> Result<Vec<Resource>> {
+        let Some(remote_resources) = self
+            .ws_resources
+            .as_ref()
+            .and_then(|config| config.remote_resources.as_ref())
+            .filter(|_| !self.options.offline)
+        else {
+            return Ok(vec![]);
+        };
+        let directory = self.local_files.path().join("remote");
+        let mount = Mount::root(directory.clone());
+        let mut resources = vec![];
+        for (resource_path, remote) in remote_resources {
+            if let Some(selection) = self.options.only.as_ref() {
+                if !selection.matches(resource_path) {
+                    continue;
+                }
+            }
+            check_remote_resource_path(resource_path)?;
+            let full_path = directory.join(resource_path.trim_start_matches('/'));
+            tracing::info!(
+                url = remote.url,
+                resource_path,
+                "downloading the remote resource"
+            );
+            let content_type = download(&remote.url, &full_path, self.options.proxy.as_deref())
+                .await
+                .context(format!(
+                    "error in loading the remote resource {resource_path}"
+                ))?;
+            let content_type = remote.content_type.clone().or(content_type);
+            resources.extend(
+                self.read_resource_with_content_type(&full_path, &mount, content_type.as_deref())
+                    .await?,
+            );
+        }
+        Ok(resources)
+    }
+
+    fn iter_dir(&self, start: &Path) -> Result<Vec<PathBuf>> {
+        FileLister {
+            include_dotfiles: self.options.include_dotfiles,
+            symlinks: self.options.symlinks,
+            skip_unreadable: self.options.skip_unreadable,
+        }
+        .iter_dir(start)
+    }
+}
+
+/// Lists the files of the site directory, as they are read by the [`ResourceManager`].
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct FileLister {
+    /// Whether to include dotfiles and dot-directories (e.g., `.well-known/`) in the site.
+    pub include_dotfiles: bool,
+    /// How to handle symbolic links in the site directory.
+    pub symlinks: SymlinkPolicy,
+    /// Whether to skip the directories that cannot be read, instead of failing.
+    pub skip_unreadable: bool,
+}
+
+impl FileLister {
+    /// Recursively lists the files in the directory.
+    pub fn iter_dir(&self, start: &Path) -> Result<Vec<PathBuf>> {
+        let mut symlinks = vec![];
+        let mut unreadable = vec![];
+        let resources = self.walk_dir(start, &mut vec![], &mut symlinks, &mut unreadable)?;
+        warn_unreadable(&unreadable)?;
+        if self.symlinks == SymlinkPolicy::Error && !symlinks.is_empty() {
+            bail!(
+                "the directory {} contains symbolic links:\n{}",
+                start.display(),
+                symlinks
+                    .iter()
+                    .map(|path| format!("  - {}", path.display()))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            );
+        }
+        Ok(resources)
+    }
+
+    /// Recursively lists the files in the directory, applying the symbolic link policy.
+    ///
+    /// `ancestors` contains the canonical paths of the directories being visited, to detect the
+    /// cycles created by symbolic links. The symbolic links found are added to `symlinks`, and the
+    /// entries that cannot be read, if skipped, to `unreadable`.
+    fn walk_dir(
+        &self,
+        dir: &Path,
+        ancestors: &mut Vec<PathBuf>,
+        symlinks: &mut Vec<PathBuf>,
+        unreadable: &mut Vec<(PathBuf, String)>,
+    ) -> Result<Vec<PathBuf>> {
+        let canonical = fs::canonicalize(dir)?;
+        if ancestors.contains(&canonical) {
+            display::check_warning(format!(
+                "skipping {}, as the symbolic link creates a cycle",
+                dir.display()
+            ))?;
+            return Ok(vec![]);
+        }
+        ancestors.push(canonical);
+
         let mut resources = vec![];
-        let entries = fs::read_dir(start)?;
+        let entries = match fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(error) if self.skip_unreadable => {
+                unreadable.push((dir.to_owned(), error.to_string()));
+                ancestors.pop();
+                return Ok(vec![]);
+            }
+            Err(error) => {
+                return Err(error)
+                    .context(format!("unable to read the directory {}", dir.display()))
+            }
+        };
         for entry in entries.flatten() {
             let path = entry.path();
+            if self.is_ignored(&path) {
+                tracing::debug!(?path, "ignoring path");
+                continue;
+            }
+            let file_type = match entry.file_type() {
+                Ok(file_type) => file_type,
+                Err(error) if self.skip_unreadable => {
+                    unreadable.push((path, error.to_string()));
+                    continue;
+                }
+                Err(error) => return Err(error.into()),
+            };
+            if file_type.is_symlink() {
+                match self.symlinks {
+                    SymlinkPolicy::Follow => (),
+                    SymlinkPolicy::Skip => {
+                        tracing::debug!(?path, "skipping symbolic link");
+                        continue;
+                    }
+                    SymlinkPolicy::Error => {
+                        symlinks.push(path);
+                        continue;
+                    }
+                }
+            }
             if path.is_dir() {
-                resources.extend(Self::iter_dir(&path, root)?);
+                resources.extend(self.walk_dir(&path, ancestors, symlinks, unreadable)?);
+            } else if path.is_file() {
+                resources.push(path.to_owned());
             } else {
-                resources.push((path.to_owned(), root.to_owned()));
+                display::check_warning(format!(
+                    "skipping {}, which is not a file",
+                    path.display()
+                ))?;
             }
         }
+
+        ancestors.pop();
         Ok(resources)
     }
+
+    /// Returns `true` if the file or directory at the path should not be part of the site.
+    ///
+    /// Dotfiles and dot-directories are excluded, unless `include_dotfiles` is set. With
+    /// `skip_unreadable`, the entries that cannot be read are also skipped, during the enumeration
+    /// and by [`ResourceManager::read_mounts`], which also excludes the files outside the
+    /// selection of a partial update.
+    fn is_ignored(&self, path: &Path) -> bool {
+        !self.include_dotfiles && is_dotfile(path)
+    }
+}
+
+/// Returns `true` if the error is caused by a failure to read from the file system.
+fn is_io_error(error: &anyhow::Error) -> bool {
+    error
+        .root_cause()
+        .downcast_ref::<std::io::Error>()
+        .is_some()
+}
+
+/// Displays a warning listing the files and directories skipped because they cannot be read.
+fn warn_unreadable(unreadable: &[(PathBuf, String)]) -> Result<()> {
+    if unreadable.is_empty() {
+        return Ok(());
+    }
+    display::check_warning(format!(
+        "skipped {} files and directories that cannot be read:\n{}",
+        unreadable.len(),
+        unreadable
+            .iter()
+            .map(|(path, error)| format!("  - {}: {error}", path.display()))
+            .collect::<Vec<_>>()
+            .join("\n")
+    ))
+}
+
+/// Returns the path of the resource that the portal serves for the HTTP error status.
+fn error_page_path(status: u16) -> String {
+    format!("/{status}.html")
+}
+
+/// Creates the resources that serve the error pages declared in `ws-resources.json`.
+///
+/// Each error page is a copy of the declared resource, with the same blob, at the path that the
+/// portal serves for the status (e.g., `/404.html`). Therefore, error pages are regular resources
+/// of the site, and are never re-uploaded.
+fn error_page_resources(
+    resources: &[Resource],
+    error_pages: &BTreeMap<u16, String>,
+    selection: Option<&ResourceSelection>,
+) -> Result<Vec<Resource>> {
+    let mut error_page_resources = vec![];
+    for (status, path) in error_pages {
+        let page_path = error_page_path(*status);
+        if *path == page_path {
+            continue;
+        }
+        // The pages that are not selected are not read, and are left unchanged on chain.
+        if selection.is_some_and(|selection| !selection.matches(path)) {
+            continue;
+        }
+        if *status != 404 {
+            display::check_warning(format!(
+                "the portal currently only serves custom error pages for status 404; the page \
+                for status {status} is stored at {page_path}"
+            ))?;
+        }
+        let mut page = resources
+            .iter()
+            .find(|resource| resource.info.path == *path)
+            .ok_or_else(|| anyhow!("the error page {path} for status {status} does not exist"))?
+            .clone();
+        page.info.path = page_path;
+        error_page_resources.push(page);
+    }
+    Ok(error_page_resources)
+}
+
+/// Returns `true` if the content type is audio or video, which browsers stream.
+fn is_streamable_media(content_type: &str) -> bool {
+    content_type.starts_with("audio/") || content_type.starts_with("video/")
+}
+
+/// Checks if the file extension or the content type is in the allowed types.
+///
+/// The comparison is case-insensitive, and ignores the parameters of the content type and a
+/// leading `.` of the extensions. All types are allowed if the list is empty.
+fn is_allowed_type(allowed_types: &[String], extension: Option<&str>, content_type: &str) -> bool {
+    let essence = content_type.split(';').next().unwrap_or_default().trim();
+    allowed_types.is_empty()
+        || allowed_types.iter().any(|allowed| {
+            let allowed = allowed.trim();
+            if allowed.contains('/') {
+                allowed.eq_ignore_ascii_case(essence)
+            } else {
+                extension.is_some_and(|extension| {
+                    allowed
+                        .trim_start_matches('.')
+                        .eq_ignore_ascii_case(extension)
+                })
+            }
+        })
+}
+
+/// The number of bytes read from the start of a file to detect its content type.
+const SNIFF_BYTES: u64 = 512;
+
+/// Infers the content type of the file from its extension, then from its content, like the
+/// resources published without a content type set in `ws-resources.json`.
+pub(crate) fn infer_content_type(full_path: &Path) -> ContentType {
+    full_path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .and_then(|extension| ContentType::try_from_extension(extension).ok())
+        .or_else(|| sniff_content_type(full_path))
+        .unwrap_or(ContentType::ApplicationOctetstream)
+}
+
+/// Detects the content type of the file from its first bytes, see
+/// [`ContentType::try_from_magic_bytes`].
+fn sniff_content_type(full_path: &Path) -> Option<ContentType> {
+    let mut head = vec![];
+    fs::File::open(full_path)
+        .and_then(|file| file.take(SNIFF_BYTES).read_to_end(&mut head))
+        .ok()?;
+    let content_type = ContentType::try_from_magic_bytes(&head);
+    tracing::debug!(?full_path, ?content_type, "sniffed the content type");
+    content_type
+}
+
+/// Returns `true` if the file name of the path starts with a `.`.
+fn is_dotfile(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| name.starts_with('.'))
+}
+
+/// Computes the hash of the contents of a resource, as stored on chain in the `blob_hash` field.
+///
+/// The portal verifies the integrity of the blobs fetched from the aggregators by comparing the
+/// SHA-256 digest of the contents to the BCS bytes of the `blob_hash`. Therefore, the hash function
+/// must be SHA-256, and the digest is interpreted as a little-endian `u256`.
+pub(crate) fn resource_hash(content: &[u8]) -> U256 {
+    let mut hash_function = Sha256::default();
+    hash_function.update(content);
+    let digest: [u8; 32] = hash_function.finalize().digest;
+    U256::from_le_bytes(&digest)
+}
+
+/// Computes the same hash as [`resource_hash`], reading the content in chunks of
+/// [`HASH_CHUNK_SIZE`] bytes instead of whole.
+pub(crate) fn resource_hash_reader(mut reader: impl Read) -> std::io::Result<U256> {
+    let mut hash_function = Sha256::default();
+    let mut buffer = vec![0; HASH_CHUNK_SIZE];
+    loop {
+        let n_read = reader.read(&mut buffer)?;
+        if n_read == 0 {
+            break;
+        }
+        hash_function.update(&buffer[..n_read]);
+    }
+    let digest: [u8; 32] = hash_function.finalize().digest;
+    Ok(U256::from_le_bytes(&digest))
+}
+
+/// Checks that the content downloaded for the resource matches the hash stored on chain.
+///
+/// As the resource on chain commits to the hash of its content, this detects the blobs corrupted
+/// or substituted by an aggregator.
+pub(crate) fn check_content_hash(resource: &SuiResource, content: &[u8]) -> Result<()> {
+    check_hash(resource, resource_hash(content))
+}
+
+/// The size of the chunks in which the large files are hashed.
+const HASH_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Checks that the content of the file downloaded for the resource matches the hash stored on
+/// chain, reading the file in chunks instead of whole.
+pub(crate) fn check_file_hash(resource: &SuiResource, path: &Path) -> Result<()> {
+    let file =
+        fs::File::open(path).with_context(|| format!("could not open {}", path.display()))?;
+    check_hash(resource, resource_hash_reader(file)?)
+}
+
+/// Checks the hash of the content of the resource against the one stored on chain.
+fn check_hash(resource: &SuiResource, hash: U256) -> Result<()> {
+    ensure!(
+        hash == resource.blob_hash,
+        "the content of the resource {} (blob {}) does not match its hash: expected {}, got {}",
+        resource.path,
+        resource.blob_id,
+        hash_to_hex(&resource.blob_hash),
+        hash_to_hex(&hash)
+    );
+    Ok(())
+}
+
+/// Returns the semaphore bounding the files hashed in parallel to the available cores.
+fn hash_permits() -> Semaphore {
+    Semaphore::new(std::thread::available_parallelism().map_or(1, |cores| cores.get()))
+}
+
+/// Creates the private directory of the local files, which is removed when dropped.
+fn local_files() -> Result<TempDir> {
+    tempfile::Builder::new()
+        .prefix("walrus-sites-")
+        .tempdir()
+        .context("could not create the temporary directory of the site")
+}
+
+/// Returns the `etag` header value of a resource: the quoted hex SHA-256 digest of the content.
+///
+/// The tag only depends on the content, so it is stable across republishes of the same content.
+fn resource_etag(blob_hash: &U256) -> String {
+    format!("\"{}\"", hash_to_hex(blob_hash))
+}
+
+/// Returns the hex encoding of a hash computed with [`resource_hash`].
+pub(crate) fn hash_to_hex(hash: &U256) -> String {
+    hash.to_le_bytes()
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+/// Fails if the resource path is named like a reserved field of the site, e.g., `/routes`.
+///
+/// Resources and reserved fields have names of different types on chain, but the tools that
+/// address the fields by name, like the portals and the `fields` command, cannot tell them apart.
+fn check_reserved_path(path: &str) -> Result<()> {
+    let name = path.trim_start_matches('/');
+    ensure!(
+        !reserved_field_names().any(|reserved| reserved == name),
+        "the resource path {path} is named like the reserved field `{name}` of the site; rename \
+        the file, or pass `--allow-reserved-paths`"
+    );
+    Ok(())
+}
+
+/// Fails if the resource path is too long to be the name of a dynamic field of the site.
+fn check_resource_path_length(path: &str) -> Result<()> {
+    ensure!(
+        path.len() <= MAX_RESOURCE_PATH_BYTES,
+        "the resource path {path} is {} bytes long, {} bytes over the limit of {} bytes",
+        path.len(),
+        path.len() - MAX_RESOURCE_PATH_BYTES,
+        MAX_RESOURCE_PATH_BYTES
+    );
+    Ok(())
 }
 
 #[allow(dead_code)]
@@ -449,6 +1316,8 @@ fn compress(content: &[u8]) -> Result<Vec<u8>> {
 }
 
 /// Converts the full path of the resource to the on-chain resource path.
+///
+/// The resulting path always uses `/` as separator, regardless of the host OS.
 pub(crate) fn full_path_to_resource_path(full_path: &Path, root: &Path) -> Result<String> {
     let rel_path = full_path.strip_prefix(root)?;
     Ok(format!(
@@ -456,5 +1325,296 @@ pub(crate) fn full_path_to_resource_path(full_path: &Path, root: &Path) -> Resul
         rel_path
             .to_str()
             .ok_or(anyhow!("could not process the path string: {:?}", rel_path))?
+            .replace('\\', "/")
     ))
 }
+
+#[cfg(test)]
+mod tests {
+    use base64::Engine;
+
+    use super::*;
+
+    #[test]
+    fn test_full_path_to_resource_path() {
+        let cases = vec![
+            ("/my/site/index.html", "/index.html"),
+            ("/my/site/assets/app.js", "/assets/app.js"),
+            // Windows-style separators are normalized.
+            ("/my/site/assets\\app.js", "/assets/app.js"),
+            ("/my/site/assets\\js\\app.js", "/assets/js/app.js"),
+        ];
+        for (full_path, expected) in cases {
+            let resource_path =
+                full_path_to_resource_path(Path::new(full_path), Path::new("/my/site")).unwrap();
+            assert_eq!(resource_path, expected);
+        }
+    }
+
+    #[test]
+    fn test_resource_hash() {
+        // The hash of the content, as stored on chain and checked by the portal.
+        const CONTENT: &[u8] = b"<html>Hello Walrus Sites!</html>";
+        const ON_CHAIN_HASH: &str =
+            "55069151210579718243577967369237785683918255919068552231505396708706440306004";
+        const PORTAL_HASH: &str = "VN1RFbu8cm1/lLSP4KO43pkqOKhv5qOjWsmM1fcIwHk=";
+
+        let hash = resource_hash(CONTENT);
+        assert_eq!(hash, U256::from_str(ON_CHAIN_HASH).unwrap());
+        assert_eq!(
+            base64::engine::general_purpose::STANDARD.encode(bcs::to_bytes(&hash).unwrap()),
+            PORTAL_HASH
+        );
+    }
+
+    #[test]
+    fn test_resource_hash_reader() {
+        let content: Vec<u8> = (0..3 * HASH_CHUNK_SIZE + 17)
+            .map(|index| index as u8)
+            .collect();
+        assert_eq!(
+            resource_hash_reader(content.as_slice()).unwrap(),
+            resource_hash(&content)
+        );
+        assert_eq!(resource_hash_reader(&[][..]).unwrap(), resource_hash(b""));
+    }
+
+    #[test]
+    fn test_check_content_hash() {
+        let resource = Resource::new(
+            "/index.html".to_owned(),
+            PathBuf::from("index.html"),
+            HttpHeaders(BTreeMap::new()),
+            BlobId([0; BlobId::LENGTH]),
+            resource_hash(b"<html></html>"),
+            13,
+        );
+        assert!(check_content_hash(&resource.info, b"<html></html>").is_ok());
+        assert!(check_content_hash(&resource.info, b"<html>substituted</html>").is_err());
+
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("index.html");
+        fs::write(&path, b"<html></html>").unwrap();
+        assert!(check_file_hash(&resource.info, &path).is_ok());
+    }
+
+    #[test]
+    fn test_resource_etag() {
+        let hash = resource_hash(b"<html>Hello Walrus Sites!</html>");
+        assert_eq!(
+            resource_etag(&hash),
+            "\"54dd5115bbbc726d7f94b48fe0a3b8de992a38a86fe6a3a35ac98cd5f708c079\""
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlink_policy() {
+        use std::os::unix::fs::symlink;
+
+        let directory = tempfile::tempdir().unwrap();
+        let root = directory.path().to_owned();
+        fs::create_dir_all(root.join("v2")).unwrap();
+        fs::write(root.join("v2").join("index.html"), "<html></html>").unwrap();
+        symlink(root.join("v2"), root.join("latest")).unwrap();
+        // A self-referential link must not hang the enumeration.
+        symlink(&root, root.join("v2").join("loop")).unwrap();
+
+        let mut manager = ResourceManager {
+            walrus: Walrus::new("walrus".to_owned(), 0, None, None, None),
+            ws_resources: None,
+            ws_resources_path: None,
+            nested_ws_resources_paths: vec![],
+            n_shards: NonZeroU16::new(1).unwrap(),
+            options: ResourceOptions::default(),
+            hash_permits: hash_permits(),
+            local_files: local_files().unwrap(),
+        };
+        let files = |manager: &ResourceManager| {
+            let mut files = manager.iter_dir(&root).unwrap();
+            files.sort();
+            files
+        };
+        assert_eq!(files(&manager), vec![root.join("v2").join("index.html")]);
+
+        manager.options.symlinks = SymlinkPolicy::Follow;
+        assert_eq!(
+            files(&manager),
+            vec![
+                root.join("latest").join("index.html"),
+                root.join("v2").join("index.html"),
+            ]
+        );
+
+        manager.options.symlinks = SymlinkPolicy::Error;
+        assert!(manager.iter_dir(&root).is_err());
+    }
+
+    #[test]
+    fn test_mount_from_str() {
+        let cases = vec![
+            ("web:/", "web", "", "/index.html"),
+            ("docs:/docs", "docs", "/docs", "/docs/index.html"),
+            ("docs:/docs/", "docs", "/docs", "/docs/index.html"),
+        ];
+        for (mount, directory, prefix, resource_path) in cases {
+            let mount = Mount::from_str(mount).unwrap();
+            assert_eq!(mount.directory, PathBuf::from(directory));
+            assert_eq!(mount.prefix, prefix);
+            assert_eq!(
+                mount
+                    .resource_path(&mount.directory.join("index.html"))
+                    .unwrap(),
+                resource_path
+            );
+        }
+        assert!(Mount::from_str("docs").is_err());
+        assert!(Mount::from_str("docs:docs").is_err());
+    }
+
+    #[test]
+    fn test_content_type_override_from_str() {
+        let content_type =
+            ContentTypeOverride::from_str("/manifest=application/manifest+json").unwrap();
+        assert_eq!(content_type.path, "/manifest");
+        assert_eq!(content_type.content_type, "application/manifest+json");
+        assert!(ContentTypeOverride::from_str("/manifest").is_err());
+        assert!(ContentTypeOverride::from_str("manifest=text/html").is_err());
+        assert!(ContentTypeOverride::from_str("/manifest=").is_err());
+    }
+
+    #[test]
+    fn test_error_page_resources() {
+        let resource = |path: &str| {
+            Resource::new(
+                path.to_owned(),
+                PathBuf::from(format!("/my/site{path}")),
+                HttpHeaders(BTreeMap::new()),
+                BlobId([0; BlobId::LENGTH]),
+                U256::zero(),
+                0,
+            )
+        };
+        let resources = vec![resource("/index.html"), resource("/not-found.html")];
+
+        let pages = BTreeMap::from([(404, "/not-found.html".to_owned())]);
+        let error_pages = error_page_resources(&resources, &pages, None).unwrap();
+        assert_eq!(error_pages.len(), 1);
+        assert_eq!(error_pages[0].info.path, "/404.html");
+        assert_eq!(
+            error_pages[0].full_path,
+            PathBuf::from("/my/site/not-found.html")
+        );
+
+        let pages = BTreeMap::from([(404, "/404.html".to_owned())]);
+        assert!(error_page_resources(&resources, &pages, None)
+            .unwrap()
+            .is_empty());
+
+        let pages = BTreeMap::from([(404, "/missing.html".to_owned())]);
+        assert!(error_page_resources(&resources, &pages, None).is_err());
+        // The pages outside the selection are skipped, as they are not read.
+        let selection = ResourceSelection::Glob("/css/**".to_owned());
+        assert!(error_page_resources(&resources, &pages, Some(&selection))
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_check_reserved_path() {
+        assert!(check_reserved_path("/routes").is_err());
+        assert!(check_reserved_path("/routes.html").is_ok());
+        assert!(check_reserved_path("/docs/routes").is_ok());
+    }
+
+    #[test]
+    fn test_check_resource_path_length() {
+        let path = format!("/{}", "a".repeat(MAX_RESOURCE_PATH_BYTES - 1));
+        assert!(check_resource_path_length(&path).is_ok());
+        let error = check_resource_path_length(&format!("{path}bc")).unwrap_err();
+        assert!(error.to_string().contains("2 bytes over the limit"));
+    }
+
+    #[test]
+    fn test_retain_stored_headers() {
+        let headers = BTreeMap::from([
+            ("cache-control".to_owned(), "max-age=3600".to_owned()),
+            ("content-encoding".to_owned(), "identity".to_owned()),
+            ("content-type".to_owned(), "text/html".to_owned()),
+            ("x-frame-options".to_owned(), "DENY".to_owned()),
+        ]);
+        let explicit_headers = BTreeSet::from(["x-frame-options".to_owned()]);
+        let stored = |options: ResourceOptions| {
+            let mut headers = headers.clone();
+            options.retain_stored_headers(&mut headers, &explicit_headers);
+            headers.into_keys().collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            stored(ResourceOptions::default()),
+            vec![
+                "cache-control",
+                "content-encoding",
+                "content-type",
+                "x-frame-options"
+            ]
+        );
+        assert_eq!(
+            stored(ResourceOptions {
+                no_auto_headers: true,
+                ..Default::default()
+            }),
+            vec!["x-frame-options"]
+        );
+        assert_eq!(
+            stored(ResourceOptions {
+                excluded_headers: vec!["content-encoding".to_owned(), "x-frame-options".to_owned()],
+                ..Default::default()
+            }),
+            vec!["cache-control", "content-type", "x-frame-options"]
+        );
+    }
+
+    #[test]
+    fn test_is_streamable_media() {
+        assert!(is_streamable_media("video/mp4"));
+        assert!(is_streamable_media("audio/mpeg"));
+        assert!(!is_streamable_media("text/html"));
+        assert!(!is_streamable_media("application/ogg"));
+    }
+
+    #[test]
+    fn test_is_allowed_type() {
+        let allowed = vec![
+            "html".to_owned(),
+            ".CSS".to_owned(),
+            "image/svg+xml".to_owned(),
+        ];
+        assert!(is_allowed_type(&allowed, Some("html"), "text/html"));
+        assert!(is_allowed_type(&allowed, Some("css"), "text/css"));
+        assert!(is_allowed_type(&allowed, Some("icon"), "image/svg+xml"));
+        assert!(!is_allowed_type(
+            &allowed,
+            Some("env"),
+            "application/octet-stream"
+        ));
+        assert!(!is_allowed_type(
+            &allowed,
+            None,
+            "text/plain; charset=utf-8"
+        ));
+        assert!(is_allowed_type(
+            &[],
+            Some("key"),
+            "application/octet-stream"
+        ));
+    }
+
+    #[test]
+    fn test_is_dotfile() {
+        assert!(is_dotfile(Path::new("/my/site/.well-known")));
+        assert!(is_dotfile(Path::new("/my/site/.env")));
+        assert!(!is_dotfile(Path::new("/my/site/index.html")));
+        assert!(!is_dotfile(Path::new("/my/.site/index.html")));
+    }
+}
@@ -0,0 +1,107 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Local snapshots of the state of a site before an update, to roll back the update.
+
+use std::{
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use sui_types::base_types::ObjectID;
+
+use super::{plan::PlannedSite, SiteData};
+
+/// The default directory in which the snapshots are stored.
+pub const DEFAULT_SNAPSHOT_DIR: &str = ".site-snapshots";
+
+/// The state of a site on chain at a point in time.
+///
+/// Snapshots are stored as `<dir>/<site object ID>/<timestamp in ms>.json`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct SiteSnapshot {
+    pub site_object: ObjectID,
+    pub timestamp_ms: u64,
+    pub site: PlannedSite,
+}
+
+impl SiteSnapshot {
+    /// Creates a snapshot of the site at the current time.
+    pub fn new(site_object: ObjectID, site: &SiteData) -> Self {
+        Self {
+            site_object,
+            timestamp_ms: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |duration| duration.as_millis() as u64),
+            site: site.into(),
+        }
+    }
+
+    /// Writes the snapshot in the directory, returning the path of the file.
+    pub fn write(&self, dir: &Path) -> Result<PathBuf> {
+        let site_dir = dir.join(self.site_object.to_string());
+        std::fs::create_dir_all(&site_dir).context(format!(
+            "unable to create the snapshot directory {}",
+            site_dir.display()
+        ))?;
+        let path = site_dir.join(format!("{}.json", self.timestamp_ms));
+        std::fs::write(&path, serde_json::to_string_pretty(self)?)
+            .context(format!("unable to write the snapshot {}", path.display()))?;
+        Ok(path)
+    }
+
+    /// Reads the most recent snapshot of the site in the directory.
+    pub fn read_latest(dir: &Path, site_object: ObjectID) -> Result<Self> {
+        let site_dir = dir.join(site_object.to_string());
+        let latest = std::fs::read_dir(&site_dir)
+            .context(format!(
+                "no snapshots of the site {site_object} in {}",
+                dir.display()
+            ))?
+            .flatten()
+            .filter_map(|entry| {
+                let path = entry.path();
+                let timestamp = path.file_stem()?.to_str()?.parse::<u64>().ok()?;
+                Some((timestamp, path))
+            })
+            .max_by_key(|(timestamp, _)| *timestamp)
+            .map(|(_, path)| path)
+            .ok_or_else(|| {
+                anyhow!(
+                    "no snapshots of the site {site_object} in {}",
+                    dir.display()
+                )
+            })?;
+        let contents = std::fs::read_to_string(&latest)
+            .context(format!("unable to read the snapshot {}", latest.display()))?;
+        serde_json::from_str(&contents)
+            .context(format!("unable to parse the snapshot {}", latest.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_latest() {
+        let directory = tempfile::tempdir().unwrap();
+        let dir = directory.path().join("snapshots");
+        let site_object = ObjectID::ZERO;
+        for timestamp_ms in [2, 10, 3] {
+            SiteSnapshot {
+                site_object,
+                timestamp_ms,
+                site: (&SiteData::empty()).into(),
+            }
+            .write(&dir)
+            .unwrap();
+        }
+        let latest = SiteSnapshot::read_latest(&dir, site_object).unwrap();
+        assert_eq!(latest.timestamp_ms, 10);
+        assert!(SiteSnapshot::read_latest(&dir, ObjectID::from_single_byte(1)).is_err());
+    }
+}
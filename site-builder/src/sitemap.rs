@@ -0,0 +1,96 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Sitemaps of the resources of a site.
+
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use clap::ValueEnum;
+
+use crate::site::SiteData;
+
+/// The format of the sitemap.
+#[derive(Debug, ValueEnum, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SitemapFormat {
+    /// A human-readable list of the resources and their object IDs.
+    #[default]
+    List,
+    /// An XML sitemap of the pages of the site, to be submitted to search engines.
+    Xml,
+    /// A JSON map from the path of every resource to its blob ID.
+    Json,
+}
+
+/// Returns the XML sitemap of the pages of the site.
+///
+/// Only the HTML resources are included; the paths are prefixed with the given site URL.
+pub(crate) fn to_xml(site: &SiteData, site_url: &str) -> String {
+    let site_url = site_url.trim_end_matches('/');
+    let mut xml = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+        <urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n",
+    );
+    for resource in site.resources().inner.iter() {
+        let is_page = resource
+            .info
+            .headers
+            .0
+            .get("content-type")
+            .is_some_and(|content_type| is_page_content_type(content_type));
+        if is_page {
+            xml.push_str(&format!(
+                "  <url><loc>{}</loc></url>\n",
+                escape_xml(&format!("{}{}", site_url, resource.info.path))
+            ));
+        }
+    }
+    xml.push_str("</urlset>\n");
+    xml
+}
+
+/// Returns the JSON map from the path of every resource of the site to its blob ID.
+pub(crate) fn to_json(site: &SiteData) -> Result<String> {
+    let map: BTreeMap<_, _> = site
+        .resources()
+        .inner
+        .iter()
+        .map(|resource| (&resource.info.path, resource.info.blob_id.to_string()))
+        .collect();
+    Ok(serde_json::to_string_pretty(&map)?)
+}
+
+fn is_page_content_type(content_type: &str) -> bool {
+    let mime = content_type.split(';').next().unwrap_or_default().trim();
+    mime == "text/html" || mime == "application/xhtml+xml"
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_page_content_type() {
+        assert!(is_page_content_type("text/html"));
+        assert!(is_page_content_type("text/html; charset=utf-8"));
+        assert!(is_page_content_type("application/xhtml+xml"));
+        assert!(!is_page_content_type("image/png"));
+        assert!(!is_page_content_type("font/woff2"));
+    }
+
+    #[test]
+    fn test_escape_xml() {
+        assert_eq!(
+            escape_xml("https://site.walrus.site/a?b=1&c=<d>"),
+            "https://site.walrus.site/a?b=1&amp;c=&lt;d&gt;"
+        );
+    }
+}
@@ -3,6 +3,24 @@
 
 //! Summaries of the run results.
 
+use std::{
+    fs::OpenOptions,
+    io::Write,
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use sui_sdk::rpc_types::{
+    SuiExecutionStatus,
+    SuiTransactionBlockDataAPI,
+    SuiTransactionBlockEffects,
+    SuiTransactionBlockEffectsAPI,
+    SuiTransactionBlockResponse,
+};
+use sui_types::{base_types::SuiAddress, digests::TransactionDigest};
+
 use crate::{
     site::{resource::ResourceOp, SiteDataDiff},
     types::RouteOps,
@@ -63,6 +81,115 @@ impl Summarizable for Vec<ResourceOpSummary> {
     }
 }
 
+impl Summarizable for SuiTransactionBlockEffects {
+    fn to_summary(&self) -> String {
+        let status = match self.status() {
+            SuiExecutionStatus::Success => "success".to_owned(),
+            SuiExecutionStatus::Failure { error } => format!("failure ({error})"),
+        };
+        let gas = self.gas_cost_summary();
+        let mut lines = vec![
+            format!("Transaction digest: {}", self.transaction_digest()),
+            format!("Status: {status}"),
+            format!(
+                "Gas: {} MIST (computation {}, storage {}, rebate {})",
+                gas.net_gas_usage(),
+                gas.computation_cost,
+                gas.storage_cost,
+                gas.storage_rebate
+            ),
+        ];
+        for (operation, objects) in [
+            ("Created", self.created()),
+            ("Mutated", self.mutated()),
+            ("Unwrapped", self.unwrapped()),
+        ] {
+            lines.push(format!("{operation} objects: {}", objects.len()));
+            lines.extend(
+                objects
+                    .iter()
+                    .map(|object| format!("  - {} (owner: {})", object.object_id(), object.owner)),
+            );
+        }
+        for (operation, objects) in [("Deleted", self.deleted()), ("Wrapped", self.wrapped())] {
+            lines.push(format!("{operation} objects: {}", objects.len()));
+            lines.extend(
+                objects
+                    .iter()
+                    .map(|object| format!("  - {}", object.object_id)),
+            );
+        }
+        lines.join("\n")
+    }
+}
+
+/// The margin over the gas used, in percent, for the suggested gas budget.
+const SUGGESTED_BUDGET_MARGIN_PERCENT: u64 = 20;
+
+/// The comparison of the gas budget and the estimated gas with the gas actually used.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GasReport {
+    /// The gas budget set for the transaction, in MIST.
+    pub budget: u64,
+    /// The gas used by the transaction, computation and storage, in MIST.
+    pub gas_used: u64,
+    /// The ratio between the gas used and the budget.
+    pub budget_ratio: f64,
+    /// The gas estimated with a dry run before the execution, if any, in MIST.
+    pub estimated_gas: Option<u64>,
+    /// The relative error of the estimate with respect to the gas used.
+    pub estimate_error: Option<f64>,
+    /// A budget that leaves some margin over the gas used, in MIST.
+    pub suggested_budget: u64,
+}
+
+impl GasReport {
+    /// Creates the report for a transaction with the given effects.
+    pub fn new(
+        budget: u64,
+        effects: &SuiTransactionBlockEffects,
+        estimated_gas: Option<u64>,
+    ) -> Self {
+        let gas_cost = effects.gas_cost_summary();
+        let gas_used = gas_cost.computation_cost + gas_cost.storage_cost;
+        let estimate_error = estimated_gas
+            .filter(|_| gas_used > 0)
+            .map(|estimate| (estimate as f64 - gas_used as f64) / gas_used as f64);
+        GasReport {
+            budget,
+            gas_used,
+            budget_ratio: gas_used as f64 / budget as f64,
+            estimated_gas,
+            estimate_error,
+            suggested_budget: gas_used + gas_used * SUGGESTED_BUDGET_MARGIN_PERCENT / 100,
+        }
+    }
+}
+
+impl Summarizable for GasReport {
+    fn to_summary(&self) -> String {
+        let mut summary = format!(
+            "Gas used: {} MIST of a budget of {} MIST ({:.1}%)",
+            self.gas_used,
+            self.budget,
+            self.budget_ratio * 100.0
+        );
+        if let (Some(estimated_gas), Some(error)) = (self.estimated_gas, self.estimate_error) {
+            summary.push_str(&format!(
+                "\nEstimated gas: {} MIST ({:+.1}% of the gas used)",
+                estimated_gas,
+                error * 100.0
+            ));
+        }
+        summary.push_str(&format!(
+            "\nSuggested gas budget: {} MIST",
+            self.suggested_budget
+        ));
+        summary
+    }
+}
+
 pub struct SiteDataDiffSummary {
     pub resource_ops: Vec<ResourceOpSummary>,
     pub route_ops: RouteOps,
@@ -102,3 +229,109 @@ impl Summarizable for SiteDataDiffSummary {
         format!("{}{}", resource_str, route_str)
     }
 }
+
+/// A transaction that changed a site, as recorded on chain.
+#[derive(Debug)]
+pub struct DeployRecord {
+    /// The digest of the transaction.
+    pub digest: TransactionDigest,
+    /// The time of the checkpoint of the transaction, in milliseconds since the Unix epoch.
+    pub timestamp_ms: Option<u64>,
+    /// The address that sent the transaction.
+    pub sender: Option<SuiAddress>,
+    /// The net gas used by the transaction, in MIST.
+    pub gas_used: Option<i64>,
+    /// Whether the transaction was executed successfully.
+    pub success: Option<bool>,
+}
+
+impl DeployRecord {
+    /// Extracts the record from a transaction queried with its input and effects.
+    pub fn from_response(response: &SuiTransactionBlockResponse) -> Self {
+        Self {
+            digest: response.digest,
+            timestamp_ms: response.timestamp_ms,
+            sender: response
+                .transaction
+                .as_ref()
+                .map(|transaction| *transaction.data.sender()),
+            gas_used: response
+                .effects
+                .as_ref()
+                .map(|effects| effects.gas_cost_summary().net_gas_usage()),
+            success: response.status_ok(),
+        }
+    }
+}
+
+impl Summarizable for DeployRecord {
+    fn to_summary(&self) -> String {
+        let unknown = || "unknown".to_owned();
+        format!(
+            "{}  {}  {}  {}  {}",
+            self.digest,
+            self.timestamp_ms.map_or_else(unknown, |ms| ms.to_string()),
+            self.sender
+                .map_or_else(unknown, |sender| sender.to_string()),
+            self.gas_used
+                .map_or_else(unknown, |gas| format!("{gas} MIST")),
+            match self.success {
+                Some(true) => "success",
+                Some(false) => "failure",
+                None => "unknown",
+            }
+        )
+    }
+}
+
+/// An entry of the audit log, recording a transaction submitted by the site builder.
+#[derive(Debug, Serialize)]
+pub struct AuditLogEntry {
+    /// The user-supplied label for the transaction.
+    pub label: Option<String>,
+    /// The digest of the transaction.
+    pub digest: TransactionDigest,
+    /// The time of the submission, in milliseconds since the Unix epoch.
+    pub timestamp_ms: u64,
+    /// The address that signed the transaction.
+    pub signer: SuiAddress,
+    /// The operation performed by the transaction.
+    pub operation: String,
+    /// Whether the transaction was executed successfully.
+    pub success: bool,
+}
+
+impl AuditLogEntry {
+    /// Creates a new entry, timestamped with the current time.
+    pub fn new(
+        label: Option<String>,
+        digest: TransactionDigest,
+        signer: SuiAddress,
+        operation: String,
+        success: bool,
+    ) -> Self {
+        let timestamp_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_millis() as u64)
+            .unwrap_or_default();
+        Self {
+            label,
+            digest,
+            timestamp_ms,
+            signer,
+            operation,
+            success,
+        }
+    }
+
+    /// Appends the entry as a JSON line to the audit log at the given path.
+    pub fn append_to(&self, path: &Path) -> Result<()> {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .context(format!("unable to open the audit log {}", path.display()))?;
+        writeln!(file, "{}", serde_json::to_string(self)?)?;
+        Ok(())
+    }
+}
@@ -29,6 +29,17 @@ where
     const CONTRACT_STRUCT: StructTag<'static> = contracts::dynamic_field::Field;
 }
 
+/// The site object, without its dynamic fields.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SuiSite {
+    pub id: ObjectID,
+    pub name: String,
+}
+
+impl AssociatedContractStruct for SuiSite {
+    const CONTRACT_STRUCT: StructTag<'static> = contracts::site::Site;
+}
+
 pub type ResourceDynamicField = SuiDynamicField<SuiResourcePath, SuiResource>;
 
 /// The name of a path.
@@ -1,15 +1,42 @@
 // Copyright (c) Mysten Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use std::{path::PathBuf, str};
+use std::{
+    collections::BTreeSet,
+    io::{self, IsTerminal},
+    path::{Path, PathBuf},
+    str,
+    time::{Duration, Instant},
+};
 
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, bail, ensure, Context, Result};
 use futures::Future;
+use move_core_types::language_storage::StructTag;
 use sui_sdk::{
-    rpc_types::{Page, SuiTransactionBlockEffects, SuiTransactionBlockEffectsAPI},
+    rpc_types::{
+        DynamicFieldPage,
+        Page,
+        SuiObjectData,
+        SuiObjectDataFilter,
+        SuiObjectDataOptions,
+        SuiObjectResponse,
+        SuiObjectResponseError,
+        SuiObjectResponseQuery,
+        SuiTransactionBlockEffects,
+        SuiTransactionBlockEffectsAPI,
+        SuiTransactionBlockResponse,
+        SuiTransactionBlockResponseOptions,
+    },
     wallet_context::WalletContext,
+    SuiClient,
+};
+use sui_types::{
+    base_types::{ObjectID, SuiAddress},
+    dynamic_field::DynamicFieldInfo,
+    object::Owner,
+    quorum_driver_types::ExecuteTransactionRequestType,
+    transaction::{CallArg, ObjectArg, Transaction},
 };
-use sui_types::base_types::{ObjectID, SuiAddress};
 
 pub async fn handle_pagination<F, T, C, Fut>(
     closure: F,
@@ -42,15 +69,61 @@ where
     Ok(iterators.into_iter().flatten())
 }
 
+/// Returns the objects of the Move type owned by the address.
+///
+/// The objects are filtered by type on the full node, and fetched in pages, so that the unrelated
+/// objects of the address are never transferred.
+pub(crate) async fn get_owned_objects_of_type(
+    client: &SuiClient,
+    address: SuiAddress,
+    struct_type: StructTag,
+    options: SuiObjectDataOptions,
+) -> Result<Vec<SuiObjectData>> {
+    let query = SuiObjectResponseQuery::new(
+        Some(SuiObjectDataFilter::StructType(struct_type)),
+        Some(options),
+    );
+    Ok(handle_pagination(|cursor| {
+        client
+            .read_api()
+            .get_owned_objects(address, Some(query.clone()), cursor, None)
+    })
+    .await?
+    .filter_map(|response| response.data)
+    .collect())
+}
+
+/// Returns the IDs of the sites owned by the address, given the type of the site.
+pub(crate) async fn get_owned_sites(
+    client: &SuiClient,
+    address: SuiAddress,
+    site_type: StructTag,
+) -> Result<Vec<ObjectID>> {
+    Ok(
+        get_owned_objects_of_type(client, address, site_type, SuiObjectDataOptions::new())
+            .await?
+            .into_iter()
+            .map(|data| data.object_id)
+            .collect(),
+    )
+}
+
 /// Convert the hex representation of an object id to base36.
 pub fn id_to_base36(id: &ObjectID) -> Result<String> {
+    Ok(bytes_to_base36(&id.into_bytes()))
+}
+
+/// Convert a big-endian sequence of bytes to base36, without leading zeros.
+fn bytes_to_base36(source: &[u8]) -> String {
     const BASE36: &[u8] = "0123456789abcdefghijklmnopqrstuvwxyz".as_bytes();
-    let source = id.into_bytes();
     let base = BASE36.len();
-    let size = source.len() * 2;
+    // A number of `n` bytes is smaller than `256^n = 36^(8n / log2(36))`, so it has at most
+    // `ceil(8n / log2(36))` base36 digits. The additional digit guarantees that the most
+    // significant position is never written, so that the index below never underflows.
+    let size = (source.len() as f64 * 8.0 / (base as f64).log2()).ceil() as usize + 1;
     let mut encoding = vec![0; size];
     let mut high = size - 1;
-    for digit in &source {
+    for digit in source {
         let mut carry = *digit as usize;
         let mut it = size - 1;
         while it > high || carry != 0 {
@@ -62,15 +135,138 @@ pub fn id_to_base36(id: &ObjectID) -> Result<String> {
         high = it;
     }
     let skip = encoding.iter().take_while(|v| **v == 0).count();
-    let string = str::from_utf8(
+    str::from_utf8(
         &(encoding[skip..]
             .iter()
             .map(|&c| BASE36[c])
             .collect::<Vec<_>>()),
     )
     .unwrap()
-    .to_owned();
-    Ok(string)
+    .to_owned()
+}
+
+/// Convert the base36 representation of an object id back to the object id.
+pub fn base36_to_id(base36: &str) -> Result<ObjectID> {
+    let mut bytes = [0u8; ObjectID::LENGTH];
+    for character in base36.chars() {
+        let mut carry = character
+            .to_digit(36)
+            .filter(|_| !character.is_ascii_uppercase())
+            .ok_or(anyhow!("invalid base36 character: {character}"))?
+            as usize;
+        for byte in bytes.iter_mut().rev() {
+            carry += 36 * *byte as usize;
+            *byte = (carry % 256) as u8;
+            carry /= 256;
+        }
+        ensure!(carry == 0, "the base36 string does not fit in an object id");
+    }
+    Ok(ObjectID::new(bytes))
+}
+
+/// The time spent encoding object ids to base36, and decoding them back.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Base36Bench {
+    pub n_ids: usize,
+    pub encoding: Duration,
+    pub decoding: Duration,
+}
+
+impl Base36Bench {
+    /// Returns the number of ids encoded per second.
+    pub fn encoded_per_second(&self) -> f64 {
+        self.n_ids as f64 / self.encoding.as_secs_f64()
+    }
+
+    /// Returns the number of ids decoded per second.
+    pub fn decoded_per_second(&self) -> f64 {
+        self.n_ids as f64 / self.decoding.as_secs_f64()
+    }
+}
+
+/// Encodes the object ids to base36 and decodes them back, timing both.
+///
+/// Fails at the first id that does not decode to itself.
+pub(crate) fn bench_base36(ids: &[ObjectID]) -> Result<Base36Bench> {
+    let start = Instant::now();
+    let encoded = ids.iter().map(id_to_base36).collect::<Result<Vec<_>>>()?;
+    let encoding = start.elapsed();
+    let start = Instant::now();
+    let decoded = encoded
+        .iter()
+        .map(|base36| base36_to_id(base36))
+        .collect::<Result<Vec<_>>>()?;
+    let decoding = start.elapsed();
+    for ((id, base36), decoded) in ids.iter().zip(encoded.iter()).zip(decoded) {
+        ensure!(
+            decoded == *id,
+            "the object id {id} is encoded as {base36}, which decodes to {decoded}"
+        );
+    }
+    Ok(Base36Bench {
+        n_ids: ids.len(),
+        encoding,
+        decoding,
+    })
+}
+
+/// Checks that the base36 subdomain corresponds to the object id.
+///
+/// The portal resolves the site from the subdomain, so the two must always agree.
+pub fn verify_base36(id: &ObjectID, base36: &str) -> Result<()> {
+    let computed = id_to_base36(id)?;
+    ensure!(
+        computed == base36,
+        "the subdomain {base36} does not match the object id {id} (expected {computed})"
+    );
+    ensure!(
+        base36_to_id(base36)? == *id,
+        "the subdomain {base36} does not decode to the object id {id}"
+    );
+    Ok(())
+}
+
+/// The placeholders of the portal URL templates: the Base36 subdomain, and the hex object ID.
+const PORTAL_URL_PLACEHOLDERS: [&str; 2] = ["{b36}", "{id}"];
+
+/// Checks that the portal URL template only contains known placeholders, and at least one.
+pub fn validate_portal_url_template(template: &str) -> Result<()> {
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let end = rest[start..]
+            .find('}')
+            .ok_or_else(|| anyhow!("unclosed placeholder in the portal URL template {template}"))?;
+        let placeholder = &rest[start..start + end + 1];
+        ensure!(
+            PORTAL_URL_PLACEHOLDERS.contains(&placeholder),
+            "unknown placeholder {placeholder} in the portal URL template {template}; the \
+            supported placeholders are {}",
+            PORTAL_URL_PLACEHOLDERS.join(", ")
+        );
+        rest = &rest[start + end + 1..];
+    }
+    ensure!(
+        PORTAL_URL_PLACEHOLDERS
+            .iter()
+            .any(|placeholder| template.contains(placeholder)),
+        "the portal URL template {template} contains no placeholder; use {}",
+        PORTAL_URL_PLACEHOLDERS.join(" or ")
+    );
+    Ok(())
+}
+
+/// Renders the URL of the site on the portal.
+///
+/// With a template, `{b36}` is replaced by the Base36 subdomain of the site and `{id}` by its
+/// object ID; otherwise, the site is served at the Base36 subdomain of the portal domain.
+pub fn portal_url(portal: &str, template: Option<&str>, site_id: &ObjectID) -> Result<String> {
+    let base36 = id_to_base36(site_id)?;
+    Ok(match template {
+        Some(template) => template
+            .replace("{b36}", &base36)
+            .replace("{id}", &site_id.to_string()),
+        None => format!("https://{base36}.{portal}"),
+    })
 }
 
 /// Get the object id of the site that was published in the transaction.
@@ -88,6 +284,285 @@ pub fn get_site_id_from_response(
         .object_id)
 }
 
+/// Returns the builder of an HTTP client, going through the proxy if one is given.
+///
+/// Without a proxy, the proxies set in the environment are used; `NO_PROXY` is always honored.
+pub(crate) fn http_client_builder(proxy: Option<&str>) -> Result<reqwest::ClientBuilder> {
+    let builder = reqwest::Client::builder();
+    let Some(proxy) = proxy else {
+        return Ok(builder);
+    };
+    Ok(builder.proxy(
+        reqwest::Proxy::all(proxy)
+            .with_context(|| format!("invalid proxy URL: {proxy}"))?
+            .no_proxy(reqwest::NoProxy::from_env()),
+    ))
+}
+
+/// Returns `true` if the path matches the glob pattern.
+///
+/// In the pattern, `?` matches any character except `/`, `*` matches any sequence of characters
+/// except `/`, and `**` matches any sequence of characters. As usual, `**/` matches zero or more
+/// directories, so `/**/*.css` also matches `/style.css`.
+pub(crate) fn glob_match(pattern: &str, path: &str) -> bool {
+    fn matches(pattern: &[u8], path: &[u8]) -> bool {
+        match pattern {
+            [] => path.is_empty(),
+            [b'*', b'*', b'/', rest @ ..] => {
+                matches(rest, path)
+                    || (0..path.len())
+                        .any(|end| path[end] == b'/' && matches(rest, &path[end + 1..]))
+            }
+            [b'*', b'*', rest @ ..] => (0..=path.len()).any(|start| matches(rest, &path[start..])),
+            [b'*', rest @ ..] => {
+                for start in 0..=path.len() {
+                    if matches(rest, &path[start..]) {
+                        return true;
+                    }
+                    if path.get(start) == Some(&b'/') {
+                        break;
+                    }
+                }
+                false
+            }
+            [b'?', rest @ ..] => {
+                matches!(path.first(), Some(c) if *c != b'/') && matches(rest, &path[1..])
+            }
+            [c, rest @ ..] => path.first() == Some(c) && matches(rest, &path[1..]),
+        }
+    }
+    matches(pattern.as_bytes(), path.as_bytes())
+}
+
+/// The resources selected for a partial update of a site.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum ResourceSelection {
+    /// The resources whose path matches the glob pattern, with `--only`.
+    Glob(String),
+    /// The resources with exactly these paths, with `--files-from`.
+    Paths(BTreeSet<String>),
+    /// The resources whose path matches any of the glob patterns, with `--tag`.
+    Globs(Vec<String>),
+}
+
+impl ResourceSelection {
+    /// Returns `true` if the resource path is selected.
+    pub fn matches(&self, path: &str) -> bool {
+        match self {
+            Self::Glob(pattern) => glob_match(pattern, path),
+            Self::Paths(paths) => paths.contains(path),
+            Self::Globs(patterns) => patterns.iter().any(|pattern| glob_match(pattern, path)),
+        }
+    }
+}
+
+/// The reads from the full node used to prepare the transactions.
+///
+/// Implemented by [`SuiClient`], and by an in-memory mock in the tests.
+pub(crate) trait SuiReadOps {
+    /// Reads the object with the given options.
+    async fn get_object_with_options(
+        &self,
+        object_id: ObjectID,
+        options: SuiObjectDataOptions,
+    ) -> Result<SuiObjectResponse>;
+
+    /// Reads a page of the dynamic fields of the object, starting after the cursor.
+    async fn get_dynamic_fields(
+        &self,
+        object_id: ObjectID,
+        cursor: Option<ObjectID>,
+        limit: Option<usize>,
+    ) -> Result<DynamicFieldPage>;
+}
+
+/// The submission of the transactions to the network.
+///
+/// Implemented by [`SuiClient`], and by an in-memory mock in the tests.
+pub(crate) trait SuiWriteOps {
+    /// Executes the signed transaction, returning the response with the given options.
+    async fn execute_transaction_block(
+        &self,
+        transaction: Transaction,
+        options: SuiTransactionBlockResponseOptions,
+        request_type: Option<ExecuteTransactionRequestType>,
+    ) -> Result<SuiTransactionBlockResponse>;
+}
+
+impl SuiReadOps for SuiClient {
+    async fn get_object_with_options(
+        &self,
+        object_id: ObjectID,
+        options: SuiObjectDataOptions,
+    ) -> Result<SuiObjectResponse> {
+        Ok(self
+            .read_api()
+            .get_object_with_options(object_id, options)
+            .await?)
+    }
+
+    async fn get_dynamic_fields(
+        &self,
+        object_id: ObjectID,
+        cursor: Option<ObjectID>,
+        limit: Option<usize>,
+    ) -> Result<DynamicFieldPage> {
+        Ok(self
+            .read_api()
+            .get_dynamic_fields(object_id, cursor, limit)
+            .await?)
+    }
+}
+
+impl SuiWriteOps for SuiClient {
+    async fn execute_transaction_block(
+        &self,
+        transaction: Transaction,
+        options: SuiTransactionBlockResponseOptions,
+        request_type: Option<ExecuteTransactionRequestType>,
+    ) -> Result<SuiTransactionBlockResponse> {
+        Ok(self
+            .quorum_driver_api()
+            .execute_transaction_block(transaction, options, request_type)
+            .await?)
+    }
+}
+
+/// Returns all the dynamic fields of the object, reading all the pages.
+///
+/// The names are returned as read from the full node, even if they are not valid resource
+/// paths, e.g., `vector<u8>` names that are not UTF-8; the callers classify them.
+pub(crate) async fn get_dynamic_field_names(
+    sui_client: &impl SuiReadOps,
+    object_id: ObjectID,
+) -> Result<Vec<DynamicFieldInfo>> {
+    let mut fields = vec![];
+    let mut cursor = None;
+    loop {
+        let page = sui_client
+            .get_dynamic_fields(object_id, cursor, None)
+            .await
+            .context(format!("could not read the dynamic fields of {object_id}"))?;
+        fields.extend(page.data);
+        if !page.has_next_page {
+            return Ok(fields);
+        }
+        cursor = page.next_cursor;
+    }
+}
+
+/// Executes the transaction, only waiting for the effects certificate.
+///
+/// Unlike [`WalletContext::execute_transaction_may_fail`], this does not require the full node
+/// to confirm the local execution of the transaction.
+pub(crate) async fn execute_without_local_confirmation(
+    sui_client: &impl SuiWriteOps,
+    transaction: Transaction,
+) -> Result<SuiTransactionBlockResponse> {
+    sui_client
+        .execute_transaction_block(
+            transaction,
+            SuiTransactionBlockResponseOptions::new()
+                .with_effects()
+                .with_input()
+                .with_events()
+                .with_object_changes()
+                .with_balance_changes(),
+            Some(ExecuteTransactionRequestType::WaitForEffectsCert),
+        )
+        .await
+}
+
+/// Returns the argument to pass the object as mutable to a Move call.
+///
+/// An owned object must be owned by the active address; a shared object is passed as mutable, and
+/// the contract decides whether the active address can modify it. An object that does not exist
+/// is read again up to `read_attempts` times in total, as it may just not be visible yet on the
+/// full node.
+pub(crate) async fn object_call_arg(
+    sui_client: &impl SuiReadOps,
+    object_id: ObjectID,
+    active_address: SuiAddress,
+    read_attempts: u32,
+) -> Result<CallArg> {
+    let mut attempt = 1;
+    let object = loop {
+        let response = sui_client
+            .get_object_with_options(object_id, SuiObjectDataOptions::new().with_owner())
+            .await?;
+        match response.error {
+            Some(SuiObjectResponseError::NotExists { .. }) if attempt < read_attempts => {
+                tracing::debug!(%object_id, attempt, "object not visible yet");
+                attempt += 1;
+                tokio::time::sleep(OBJECT_READ_DELAY).await;
+            }
+            Some(SuiObjectResponseError::NotExists { .. }) => bail!(
+                "the site object {object_id} does not exist, or is not visible yet on the full \
+                node after {attempt} attempts; check the object ID and the network"
+            ),
+            _ => {
+                break response
+                    .into_object()
+                    .context(format!("could not get the site object {object_id}"))?
+            }
+        }
+    };
+    let owner = object.owner.ok_or(anyhow!(
+        "the owner of the site object {object_id} is unknown"
+    ))?;
+    tracing::debug!(%object_id, %owner, "fetched the owner of the site");
+    match owner {
+        Owner::Shared {
+            initial_shared_version,
+        } => Ok(CallArg::Object(ObjectArg::SharedObject {
+            id: object_id,
+            initial_shared_version,
+            mutable: true,
+        })),
+        Owner::AddressOwner(address) => {
+            ensure!(
+                address == active_address,
+                "the site object {object_id} is owned by {address}, not by the active address \
+                {active_address}"
+            );
+            Ok(CallArg::Object(ObjectArg::ImmOrOwnedObject(
+                object.object_ref(),
+            )))
+        }
+        _ => bail!("the site object {object_id} is {owner}, and cannot be modified"),
+    }
+}
+
+/// The number of attempts to read an object that was just created.
+pub(crate) const OBJECT_READ_ATTEMPTS: u32 = 10;
+/// The delay between the attempts to read an object that was just created.
+pub(crate) const OBJECT_READ_DELAY: Duration = Duration::from_millis(500);
+
+/// Waits until the object can be read from the full node, for a bounded number of attempts.
+///
+/// Right after the execution of a transaction, the objects it created may not be visible yet on
+/// a full node other than the one that executed it.
+pub(crate) async fn wait_for_object(
+    sui_client: &impl SuiReadOps,
+    object_id: ObjectID,
+) -> Result<()> {
+    for attempt in 1..=OBJECT_READ_ATTEMPTS {
+        match sui_client
+            .get_object_with_options(object_id, SuiObjectDataOptions::new())
+            .await
+        {
+            Ok(response) if response.data.is_some() => return Ok(()),
+            Ok(_) => tracing::debug!(%object_id, attempt, "object not visible yet"),
+            Err(error) => tracing::debug!(%object_id, attempt, ?error, "could not read object"),
+        }
+        tokio::time::sleep(OBJECT_READ_DELAY).await;
+    }
+    bail!(
+        "the object {object_id} is not visible on the full node after {} attempts",
+        OBJECT_READ_ATTEMPTS
+    )
+}
+
 /// Returns the path if it is `Some` or any of the default paths if they exist (attempt in order).
 pub fn path_or_defaults_if_exist(path: &Option<PathBuf>, defaults: &[PathBuf]) -> Option<PathBuf> {
     let mut path = path.clone();
@@ -102,28 +577,417 @@ pub fn path_or_defaults_if_exist(path: &Option<PathBuf>, defaults: &[PathBuf]) -
 
 /// Loads the wallet context from the given path.
 ///
-/// If no path is provided, tries to load the configuration first from the local folder, and then
-/// from the standard Sui configuration directory.
+/// If no path is provided, tries to load the configuration first from the directory set in the
+/// `SUI_CONFIG_DIR` environment variable, then from the local folder, and then from the standard
+/// Sui configuration directory. As with the `sui` CLI, the keystore, the active address, and the
+/// active environment are the ones set in the loaded configuration.
 // NB: When making changes to the logic, make sure to update the argument docs in
 // `crates/walrus-service/bin/client.rs`.
 #[allow(dead_code)]
 pub fn load_wallet_context(path: &Option<PathBuf>) -> Result<WalletContext> {
-    let mut default_paths = vec!["./client.yaml".into(), "./sui_config.yaml".into()];
-    if let Some(home_dir) = home::home_dir() {
+    let path = wallet_config_path(path)?;
+    tracing::info!("Using wallet configuration from {}", path.display());
+    let wallet = WalletContext::new(&path, None, None).map_err(|error| {
+        let context = keystore_problem(&path).unwrap_or_else(|| {
+            format!(
+                "unable to load the wallet config {}; make sure that the keystore it references \
+                exists",
+                path.display()
+            )
+        });
+        error.context(context)
+    })?;
+    tracing::info!(
+        active_address=?wallet.config.active_address,
+        "wallet configuration loaded",
+    );
+    Ok(wallet)
+}
+
+/// Returns a description of the problem with the keystore referenced by the wallet config, if
+/// the keystore is missing or cannot be parsed.
+fn keystore_problem(config_path: &Path) -> Option<String> {
+    let config = std::fs::read_to_string(config_path).ok()?;
+    let keystore = keystore_path(&config)?;
+    let fix = format!(
+        "fix the `keystore` path in {}, or point to another wallet config with `--wallet`",
+        config_path.display()
+    );
+    if !keystore.exists() {
+        return Some(format!(
+            "the keystore {} referenced by the wallet config does not exist; {fix}",
+            keystore.display()
+        ));
+    }
+    let parsed = std::fs::read_to_string(&keystore)
+        .map_err(anyhow::Error::from)
+        .and_then(|contents| Ok(serde_json::from_str::<Vec<String>>(&contents)?));
+    parsed.err().map(|error| {
+        format!(
+            "the keystore {} referenced by the wallet config cannot be read: {error}; it must be \
+            the JSON list of keys written by `sui keytool`, e.g., after `sui keytool import`; {fix}",
+            keystore.display()
+        )
+    })
+}
+
+/// Returns the path of the file keystore set in the wallet config, if any.
+fn keystore_path(config: &str) -> Option<PathBuf> {
+    let config = serde_yaml::from_str::<serde_yaml::Value>(config).ok()?;
+    config
+        .get("keystore")?
+        .get("File")?
+        .as_str()
+        .map(PathBuf::from)
+}
+
+/// Returns the path of the wallet configuration that is loaded by [`load_wallet_context`].
+pub fn wallet_config_path(path: &Option<PathBuf>) -> Result<PathBuf> {
+    let default_paths = default_wallet_config_paths(
+        std::env::var_os(SUI_CONFIG_DIR_ENV).map(PathBuf::from),
+        home::home_dir(),
+    );
+    path_or_defaults_if_exist(path, &default_paths).ok_or(anyhow!(
+        "Could not find a valid wallet config file. Set up the Sui CLI with `sui client`, or \
+        point to a wallet config with `--wallet`."
+    ))
+}
+
+/// Asks the user to confirm the operation on the terminal, failing if it is not confirmed.
+pub(crate) fn confirm_interactively(message: &str) -> Result<()> {
+    ensure!(
+        io::stdin().is_terminal(),
+        "{message}\nthe operation must be confirmed, but stdin is not a terminal; pass `--yes` to \
+        proceed without confirmation"
+    );
+    eprint!("{message}\nContinue? [y/N] ");
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    ensure!(
+        matches!(answer.trim(), "y" | "Y" | "yes"),
+        "the operation was aborted"
+    );
+    Ok(())
+}
+
+/// The environment variable that sets the Sui configuration directory, as for the `sui` CLI.
+pub(crate) const SUI_CONFIG_DIR_ENV: &str = "SUI_CONFIG_DIR";
+
+/// Returns the paths at which the wallet configuration is looked for, in order of precedence.
+///
+/// The directory set with `SUI_CONFIG_DIR` comes first, then the local folder, and finally the
+/// standard Sui configuration directory in the home directory.
+fn default_wallet_config_paths(
+    sui_config_dir: Option<PathBuf>,
+    home_dir: Option<PathBuf>,
+) -> Vec<PathBuf> {
+    let mut default_paths = vec![];
+    if let Some(sui_config_dir) = sui_config_dir {
+        default_paths.push(sui_config_dir.join("client.yaml"));
+    }
+    default_paths.extend(["./client.yaml".into(), "./sui_config.yaml".into()]);
+    if let Some(home_dir) = home_dir {
         default_paths.push(home_dir.join(".sui").join("sui_config").join("client.yaml"))
     }
-    let path = path_or_defaults_if_exist(path, &default_paths)
-        .ok_or(anyhow!("Could not find a valid wallet config file."))?;
-    tracing::info!("Using wallet configuration from {}", path.display());
-    WalletContext::new(&path, None, None)
+    default_paths
+}
+
+/// Returns the SUI balance of the active address of the wallet, in MIST.
+pub(crate) async fn sui_balance(wallet: &mut WalletContext) -> Result<u128> {
+    let address = wallet.active_address()?;
+    let balance = wallet
+        .get_client()
+        .await?
+        .coin_read_api()
+        .get_balance(address, None)
+        .await?;
+    tracing::debug!(%address, balance=%balance.total_balance, "checked the SUI balance");
+    Ok(balance.total_balance)
+}
+
+/// Fails if the active address of the wallet has no SUI to pay for gas.
+pub(crate) async fn ensure_sui_balance(wallet: &mut WalletContext) -> Result<()> {
+    let address = wallet.active_address()?;
+    ensure!(
+        sui_balance(wallet).await? > 0,
+        "address {address} has 0 SUI, fund it before publishing"
+    );
+    Ok(())
 }
 
 #[cfg(test)]
 mod test_util {
-    use sui_types::base_types::ObjectID;
+    use std::{
+        cell::{Cell, RefCell},
+        collections::BTreeMap,
+    };
+
+    use fastcrypto::encoding::{Base58, Encoding};
+    use sui_sdk::rpc_types::{SuiObjectData, SuiObjectResponseError};
+    use sui_types::{
+        base_types::{ObjectDigest, ObjectID, SequenceNumber},
+        digests::TransactionDigest,
+        object::Owner,
+        programmable_transaction_builder::ProgrammableTransactionBuilder,
+        transaction::TransactionData,
+    };
 
     use super::*;
 
+    /// The number of dynamic fields in a page of the mock, if no limit is given.
+    const MOCK_PAGE_SIZE: usize = 2;
+
+    /// An in-memory full node, holding the objects with their owners.
+    #[derive(Default)]
+    struct MockSuiClient {
+        objects: BTreeMap<ObjectID, Option<Owner>>,
+        /// The dynamic fields of the objects; the other objects cannot be read.
+        dynamic_fields: BTreeMap<ObjectID, Vec<DynamicFieldInfo>>,
+        /// The number of reads that return nothing before the objects become visible.
+        hidden_reads: Cell<usize>,
+        /// The transactions executed, with their request types.
+        executed: RefCell<Vec<(TransactionDigest, Option<ExecuteTransactionRequestType>)>>,
+    }
+
+    impl MockSuiClient {
+        fn with_object(mut self, object_id: ObjectID, owner: Option<Owner>) -> Self {
+            self.objects.insert(object_id, owner);
+            self
+        }
+
+        fn with_dynamic_fields(
+            mut self,
+            object_id: ObjectID,
+            fields: Vec<DynamicFieldInfo>,
+        ) -> Self {
+            self.dynamic_fields.insert(object_id, fields);
+            self
+        }
+    }
+
+    /// Returns a dynamic field named by the bytes, as a `vector<u8>`.
+    fn bytes_dynamic_field(name: &[u8], byte: u8) -> DynamicFieldInfo {
+        let bcs_name = bcs::to_bytes(name).unwrap();
+        serde_json::from_value(serde_json::json!({
+            "name": { "type": "vector<u8>", "value": name },
+            "bcsName": Base58::encode(bcs_name),
+            "type": "DynamicField",
+            "objectType": "vector<u8>",
+            "objectId": ObjectID::new([byte; ObjectID::LENGTH]),
+            "version": 1,
+            "digest": ObjectDigest::random(),
+        }))
+        .unwrap()
+    }
+
+    impl SuiReadOps for MockSuiClient {
+        async fn get_object_with_options(
+            &self,
+            object_id: ObjectID,
+            _options: SuiObjectDataOptions,
+        ) -> Result<SuiObjectResponse> {
+            let not_exists =
+                SuiObjectResponse::new_with_error(SuiObjectResponseError::NotExists { object_id });
+            if self.hidden_reads.get() > 0 {
+                self.hidden_reads.set(self.hidden_reads.get() - 1);
+                return Ok(not_exists);
+            }
+            let Some(owner) = self.objects.get(&object_id) else {
+                return Ok(not_exists);
+            };
+            Ok(SuiObjectResponse::new_with_data(SuiObjectData {
+                object_id,
+                version: SequenceNumber::from_u64(3),
+                digest: ObjectDigest::random(),
+                type_: None,
+                owner: *owner,
+                previous_transaction: None,
+                storage_rebate: None,
+                display: None,
+                content: None,
+                bcs: None,
+            }))
+        }
+
+        async fn get_dynamic_fields(
+            &self,
+            object_id: ObjectID,
+            cursor: Option<ObjectID>,
+            limit: Option<usize>,
+        ) -> Result<DynamicFieldPage> {
+            let fields = self
+                .dynamic_fields
+                .get(&object_id)
+                .ok_or(anyhow!("the object {object_id} does not exist"))?;
+            let start = cursor.map_or(0, |cursor| {
+                fields
+                    .iter()
+                    .position(|field| field.object_id == cursor)
+                    .expect("the cursor is one of the fields")
+                    + 1
+            });
+            let end = fields.len().min(start + limit.unwrap_or(MOCK_PAGE_SIZE));
+            let data = fields[start..end].to_vec();
+            Ok(Page {
+                next_cursor: data.last().map(|field| field.object_id),
+                has_next_page: end < fields.len(),
+                data,
+            })
+        }
+    }
+
+    impl SuiWriteOps for MockSuiClient {
+        async fn execute_transaction_block(
+            &self,
+            transaction: Transaction,
+            _options: SuiTransactionBlockResponseOptions,
+            request_type: Option<ExecuteTransactionRequestType>,
+        ) -> Result<SuiTransactionBlockResponse> {
+            let digest = *transaction.digest();
+            self.executed.borrow_mut().push((digest, request_type));
+            Ok(SuiTransactionBlockResponse::new(digest))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_object_call_arg() {
+        let active = SuiAddress::random_for_testing_only();
+        let other = SuiAddress::random_for_testing_only();
+        let [owned, foreign, shared, immutable, unknown, missing] =
+            [0u8, 1, 2, 3, 4, 5].map(|byte| ObjectID::new([byte; ObjectID::LENGTH]));
+        let client = MockSuiClient::default()
+            .with_object(owned, Some(Owner::AddressOwner(active)))
+            .with_object(foreign, Some(Owner::AddressOwner(other)))
+            .with_object(
+                shared,
+                Some(Owner::Shared {
+                    initial_shared_version: SequenceNumber::from_u64(1),
+                }),
+            )
+            .with_object(immutable, Some(Owner::Immutable))
+            .with_object(unknown, None);
+
+        assert!(matches!(
+            object_call_arg(&client, owned, active, 1).await.unwrap(),
+            CallArg::Object(ObjectArg::ImmOrOwnedObject((id, version, _)))
+                if id == owned && version == SequenceNumber::from_u64(3)
+        ));
+        assert!(matches!(
+            object_call_arg(&client, shared, active, 1).await.unwrap(),
+            CallArg::Object(ObjectArg::SharedObject { id, initial_shared_version, mutable: true })
+                if id == shared && initial_shared_version == SequenceNumber::from_u64(1)
+        ));
+        let error = object_call_arg(&client, foreign, active, 1)
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("not by the active address"));
+        let error = object_call_arg(&client, immutable, active, 1)
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("cannot be modified"));
+        let error = object_call_arg(&client, unknown, active, 1)
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("is unknown"));
+        let error = object_call_arg(&client, missing, active, 1)
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("does not exist"));
+    }
+
+    #[tokio::test]
+    async fn test_object_call_arg_read_lag() {
+        let shared = ObjectID::new([6; ObjectID::LENGTH]);
+        let client = MockSuiClient {
+            hidden_reads: Cell::new(1),
+            ..Default::default()
+        }
+        .with_object(
+            shared,
+            Some(Owner::Shared {
+                initial_shared_version: SequenceNumber::from_u64(1),
+            }),
+        );
+        let active = SuiAddress::random_for_testing_only();
+        assert!(object_call_arg(&client, shared, active, 2).await.is_ok());
+        assert_eq!(client.hidden_reads.get(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_object() {
+        let object_id = ObjectID::new([7; ObjectID::LENGTH]);
+        let client = MockSuiClient {
+            hidden_reads: Cell::new(2),
+            ..Default::default()
+        }
+        .with_object(object_id, Some(Owner::Immutable));
+        wait_for_object(&client, object_id).await.unwrap();
+        assert_eq!(client.hidden_reads.get(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_get_dynamic_field_names() {
+        let site = ObjectID::new([8; ObjectID::LENGTH]);
+        let fields = vec![
+            bytes_dynamic_field(b"routes", 9),
+            // Names that are not UTF-8 are returned, and classified as unknown by the callers.
+            bytes_dynamic_field(&[0xff, 0xfe], 10),
+            bytes_dynamic_field(b"index.html", 11),
+        ];
+        let client = MockSuiClient::default().with_dynamic_fields(site, fields.clone());
+
+        let names = get_dynamic_field_names(&client, site).await.unwrap();
+        assert_eq!(
+            names
+                .iter()
+                .map(|field| field.object_id)
+                .collect::<Vec<_>>(),
+            fields
+                .iter()
+                .map(|field| field.object_id)
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(names[1].name.value, serde_json::json!([0xff, 0xfe]));
+
+        let missing = ObjectID::new([12; ObjectID::LENGTH]);
+        let error = get_dynamic_field_names(&client, missing).await.unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("could not read the dynamic fields"));
+    }
+
+    #[tokio::test]
+    async fn test_execute_without_local_confirmation() {
+        let sender = SuiAddress::random_for_testing_only();
+        let gas = (
+            ObjectID::new([13; ObjectID::LENGTH]),
+            SequenceNumber::from_u64(1),
+            ObjectDigest::random(),
+        );
+        let transaction = Transaction::from_data(
+            TransactionData::new_programmable(
+                sender,
+                vec![gas],
+                ProgrammableTransactionBuilder::new().finish(),
+                1_000_000,
+                1_000,
+            ),
+            vec![],
+        );
+        let digest = *transaction.digest();
+        let client = MockSuiClient::default();
+
+        let response = execute_without_local_confirmation(&client, transaction)
+            .await
+            .unwrap();
+        assert_eq!(response.digest, digest);
+        assert!(matches!(
+            client.executed.borrow().as_slice(),
+            [(executed, Some(ExecuteTransactionRequestType::WaitForEffectsCert))]
+                if *executed == digest
+        ));
+    }
+
     #[test]
     fn test_id_to_base36() {
         let id = ObjectID::from_hex_literal(
@@ -136,4 +1000,148 @@ mod test_util {
             "5d8t4gd5q8x4xcfyctpygyr5pnk85x54o7ndeq2j4pg9l7rmw"
         );
     }
+
+    #[test]
+    fn test_bytes_to_base36_lengths() {
+        for len in 0..=64 {
+            let max = vec![u8::MAX; len];
+            let digits = bytes_to_base36(&max).len();
+            assert!(digits <= (len as f64 * 8.0 / 36f64.log2()).ceil() as usize);
+            if len <= 16 {
+                let mut value = u128::from_be_bytes({
+                    let mut padded = [0u8; 16];
+                    padded[16 - len..].copy_from_slice(&max);
+                    padded
+                });
+                let mut expected = vec![];
+                while value > 0 {
+                    expected.push(char::from_digit((value % 36) as u32, 36).unwrap());
+                    value /= 36;
+                }
+                let expected: String = expected.into_iter().rev().collect();
+                assert_eq!(bytes_to_base36(&max), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn test_default_wallet_config_paths() {
+        let paths =
+            default_wallet_config_paths(Some("/etc/sui".into()), Some("/home/walrus".into()));
+        assert_eq!(
+            paths,
+            vec![
+                PathBuf::from("/etc/sui/client.yaml"),
+                PathBuf::from("./client.yaml"),
+                PathBuf::from("./sui_config.yaml"),
+                PathBuf::from("/home/walrus/.sui/sui_config/client.yaml"),
+            ]
+        );
+        assert_eq!(
+            default_wallet_config_paths(None, None),
+            vec![
+                PathBuf::from("./client.yaml"),
+                PathBuf::from("./sui_config.yaml")
+            ]
+        );
+
+        // An explicit path always takes precedence.
+        let explicit = Some(PathBuf::from("/does/not/exist.yaml"));
+        assert_eq!(path_or_defaults_if_exist(&explicit, &paths), explicit);
+    }
+
+    #[test]
+    fn test_glob_match() {
+        let cases = vec![
+            ("/index.html", "/index.html", true),
+            ("/index.html", "/about.html", false),
+            ("/css/*.css", "/css/style.css", true),
+            ("/css/*.css", "/css/vendor/style.css", false),
+            ("/css/**", "/css/vendor/style.css", true),
+            ("/**/*.css", "/css/vendor/style.css", true),
+            ("/**/*.css", "/css/vendor/style.js", false),
+            ("/**/*.css", "/style.css", true),
+            ("/css/**/*.css", "/css/style.css", true),
+            ("/css/**/*.css", "/assets/style.css", false),
+            ("/img/logo.???", "/img/logo.png", true),
+            ("/img/logo.???", "/img/logo.jpeg", false),
+        ];
+        for (pattern, path, expected) in cases {
+            assert_eq!(glob_match(pattern, path), expected, "{pattern} {path}");
+        }
+    }
+
+    #[test]
+    fn test_keystore_path() {
+        let config = "keystore:\n  File: /home/me/.sui/sui_config/sui.keystore\nenvs: []\n";
+        assert_eq!(
+            keystore_path(config),
+            Some(PathBuf::from("/home/me/.sui/sui_config/sui.keystore"))
+        );
+        assert_eq!(keystore_path("keystore:\n  InMem: {}\n"), None);
+        assert_eq!(keystore_path("not: [valid"), None);
+    }
+
+    #[test]
+    fn test_resource_selection() {
+        let glob = ResourceSelection::Glob("/css/*".to_owned());
+        assert!(glob.matches("/css/style.css"));
+        assert!(!glob.matches("/index.html"));
+        let paths = ResourceSelection::Paths(BTreeSet::from(["/css/style.css".to_owned()]));
+        assert!(paths.matches("/css/style.css"));
+        // The paths are not patterns.
+        assert!(!paths.matches("/css/style.css.map"));
+        let globs = ResourceSelection::Globs(vec!["/css/*".to_owned(), "/*.html".to_owned()]);
+        assert!(globs.matches("/css/style.css"));
+        assert!(globs.matches("/index.html"));
+        assert!(!globs.matches("/img/logo.png"));
+    }
+
+    #[test]
+    fn test_base36_to_id() {
+        let id = ObjectID::from_hex_literal(
+            "0x05fb8843a23017cbf1c907bd559a2d6191b77bc595d4c83853cca14cc784c0a8",
+        )
+        .unwrap();
+        let base36 = "5d8t4gd5q8x4xcfyctpygyr5pnk85x54o7ndeq2j4pg9l7rmw";
+        assert_eq!(base36_to_id(base36).unwrap(), id);
+        assert!(verify_base36(&id, base36).is_ok());
+        assert!(verify_base36(&id, "5d8t4gd5q8x4xcfyctpygyr5pnk85x54o7ndeq2j4pg9l7rmx").is_err());
+        assert!(base36_to_id("5D8T").is_err());
+        assert!(base36_to_id(&"z".repeat(60)).is_err());
+    }
+
+    #[test]
+    fn test_bench_base36() {
+        // Every byte position with the edge values, so that all the carries are exercised.
+        let mut ids = vec![ObjectID::ZERO, ObjectID::new([u8::MAX; ObjectID::LENGTH])];
+        for position in 0..ObjectID::LENGTH {
+            for value in [1, 35, 36, 128, u8::MAX] {
+                let mut bytes = [0; ObjectID::LENGTH];
+                bytes[position] = value;
+                ids.push(ObjectID::new(bytes));
+            }
+        }
+        ids.extend((0..100).map(|_| ObjectID::random()));
+        let bench = bench_base36(&ids).unwrap();
+        assert_eq!(bench.n_ids, ids.len());
+    }
+
+    #[test]
+    fn test_portal_url() {
+        let id = ObjectID::from_hex_literal("0x5").unwrap();
+        assert_eq!(
+            portal_url("walrus.site", None, &id).unwrap(),
+            "https://5.walrus.site"
+        );
+        assert_eq!(
+            portal_url("walrus.site", Some("https://gw.example/{b36}?id={id}"), &id).unwrap(),
+            format!("https://gw.example/5?id={id}")
+        );
+        assert!(validate_portal_url_template("https://{b36}.walrus.site").is_ok());
+        assert!(validate_portal_url_template("https://gw.example/{id}").is_ok());
+        assert!(validate_portal_url_template("https://walrus.site").is_err());
+        assert!(validate_portal_url_template("https://{base36}.walrus.site").is_err());
+        assert!(validate_portal_url_template("https://{b36.walrus.site").is_err());
+    }
 }
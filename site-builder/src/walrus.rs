@@ -3,11 +3,22 @@
 
 //! High-level controller for the Walrus binary through the JSON interface.
 
-use std::{num::NonZeroU16, path::PathBuf};
+use std::{num::NonZeroU16, path::PathBuf, process::Stdio};
 
-use anyhow::{Context, Result};
+use anyhow::{ensure, Context, Result};
 use command::RpcArg;
-use output::{try_from_output, BlobIdOutput, InfoOutput, ReadOutput, StoreOutput};
+use output::{
+    try_from_output,
+    BlobIdOutput,
+    BlobStatusOutput,
+    DeleteOutput,
+    InfoOutput,
+    ListBlobsOutput,
+    ReadOutput,
+    StoreBatchOutput,
+    StoreOutput,
+};
+use sui_types::base_types::ObjectID;
 use tokio::process::Command as CliCommand;
 
 use self::types::BlobId;
@@ -17,6 +28,16 @@ pub mod command;
 pub mod output;
 pub mod types;
 
+/// The environment variables that set the proxy of the HTTP clients of the Walrus CLI.
+const PROXY_ENV_VARS: [&str; 6] = [
+    "HTTP_PROXY",
+    "HTTPS_PROXY",
+    "ALL_PROXY",
+    "http_proxy",
+    "https_proxy",
+    "all_proxy",
+];
+
 /// Controller to execute actions on Walrus.
 #[derive(Debug, Clone)]
 pub struct Walrus {
@@ -30,6 +51,8 @@ pub struct Walrus {
     config: Option<PathBuf>,
     /// The path to the Sui Wallet config.
     wallet: Option<PathBuf>,
+    /// The proxy through which the Walrus CLI connects, if any.
+    proxy: Option<String>,
 }
 
 macro_rules! create_command {
@@ -67,9 +90,18 @@ impl Walrus {
             rpc_url,
             config,
             wallet,
+            proxy: None,
         }
     }
 
+    /// Sets the proxy through which the Walrus CLI connects to the network.
+    ///
+    /// The proxy is passed to the CLI through the standard proxy environment variables.
+    pub fn with_proxy(mut self, proxy: Option<String>) -> Self {
+        self.proxy = proxy;
+        self
+    }
+
     /// Issues a `store` JSON command to the Walrus CLI, returning the parsed output.
     // NOTE: takes a mutable reference to ensure that only one store command is executed at every
     // time. The issue is that the inner wallet may lock coins if called in parallel.
@@ -77,12 +109,53 @@ impl Walrus {
         create_command!(self, store, file, epochs, force)
     }
 
+    /// Issues a `store` JSON command for multiple files to the Walrus CLI, returning the parsed
+    /// output.
+    // NOTE: takes a mutable reference for the same reason as `store`.
+    pub async fn store_batch(
+        &mut self,
+        files: Vec<PathBuf>,
+        epochs: u64,
+        force: bool,
+    ) -> Result<StoreBatchOutput> {
+        create_command!(self, store_batch, files, epochs, force)
+    }
+
     /// Issues a `read` JSON command to the Walrus CLI, returning the parsed output.
     #[allow(dead_code)]
     pub async fn read(&self, blob_id: BlobId, out: Option<PathBuf>) -> Result<ReadOutput> {
         create_command!(self, read, blob_id, out, self.rpc_arg())
     }
 
+    /// Issues a `read` JSON command to the Walrus CLI, writing the blob to the file.
+    ///
+    /// The JSON output of the CLI contains the whole blob, so it is discarded instead of parsed,
+    /// and the blob is never held in memory.
+    pub async fn read_to_file(&self, blob_id: BlobId, out: PathBuf) -> Result<()> {
+        let json_input = self
+            .builder()
+            .read(blob_id, Some(out), self.rpc_arg())
+            .build()
+            .to_json()?;
+        let output = self
+            .base_command()
+            .arg(&json_input)
+            .stdout(Stdio::null())
+            .output()
+            .await
+            .context(format!(
+                "error while executing the call to the Walrus binary; is it available and \
+                executable? you are using: `{}`",
+                self.bin
+            ))?;
+        ensure!(
+            output.status.success(),
+            "running the command exited with error: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        Ok(())
+    }
+
     // TODO(giac): maybe preconfigure the `n_shards` to avid repeating `None`.
     /// Issues a `blob_id` JSON command to the Walrus CLI, returning the parsed output.
     pub async fn blob_id(
@@ -93,6 +166,22 @@ impl Walrus {
         create_command!(self, blob_id, file, n_shards, self.rpc_arg())
     }
 
+    /// Issues a `blob_status` JSON command to the Walrus CLI, returning the parsed output.
+    pub async fn blob_status(&self, blob_id: BlobId) -> Result<BlobStatusOutput> {
+        create_command!(self, blob_status, blob_id, self.rpc_arg())
+    }
+
+    /// Issues a `delete` JSON command to the Walrus CLI, returning the parsed output.
+    // NOTE: takes a mutable reference for the same reason as `store`.
+    pub async fn delete(&mut self, object_id: ObjectID) -> Result<DeleteOutput> {
+        create_command!(self, delete, object_id)
+    }
+
+    /// Issues a `list-blobs` JSON command to the Walrus CLI, returning the parsed output.
+    pub async fn list_blobs(&self, include_expired: bool) -> Result<ListBlobsOutput> {
+        create_command!(self, list_blobs, include_expired)
+    }
+
     /// Issues a `info` JSON command to the Walrus CLI, returning the parsed output.
     pub async fn info(&self, dev: bool) -> Result<InfoOutput> {
         create_command!(self, info, self.rpc_arg(), dev)
@@ -101,6 +190,11 @@ impl Walrus {
     fn base_command(&self) -> CliCommand {
         let mut cmd = CliCommand::new(&self.bin);
         cmd.arg("json");
+        if let Some(proxy) = &self.proxy {
+            for variable in PROXY_ENV_VARS {
+                cmd.env(variable, proxy);
+            }
+        }
         cmd
     }
 
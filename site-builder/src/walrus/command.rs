@@ -8,6 +8,7 @@ use std::{num::NonZeroU16, path::PathBuf};
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use serde_with::{serde_as, DisplayFromStr};
+use sui_types::base_types::ObjectID;
 
 use super::types::BlobId;
 
@@ -56,6 +57,18 @@ pub enum Command {
         #[serde(default)]
         force: bool,
     },
+    /// Stores multiple blobs to Walrus with a single command.
+    ///
+    /// Only supported by the versions of the Walrus CLI that accept multiple files.
+    #[serde(rename = "store", skip_deserializing)]
+    StoreBatch {
+        /// The paths to the files to be stored.
+        files: Vec<PathBuf>,
+        /// The number of epochs for which to store the files.
+        epochs: u64,
+        /// Do not check for the blob status before storing it.
+        force: bool,
+    },
     /// Reads a blob from Walrus.
     Read {
         /// The blob ID of the blob to be read.
@@ -82,6 +95,27 @@ pub enum Command {
         #[serde(skip_serializing_if = "RpcArg::is_none")]
         rpc_arg: RpcArg,
     },
+    /// Gets the status of a blob.
+    BlobStatus {
+        /// The blob ID of the blob for which to get the status.
+        #[serde_as(as = "DisplayFromStr")]
+        blob_id: BlobId,
+        /// The RPC endpoint to which the Walrus CLI should connect to.
+        #[serde(default)]
+        rpc_arg: RpcArg,
+    },
+    /// Deletes the deletable blob object with the given object ID owned by the wallet.
+    Delete {
+        /// The object ID of the blob object to be deleted.
+        object_id: ObjectID,
+        /// Do not ask for confirmation before deleting the blob.
+        yes: bool,
+    },
+    /// Lists the blob objects owned by the wallet.
+    ListBlobs {
+        /// Also list the blobs that are expired.
+        include_expired: bool,
+    },
     Info {
         /// The URL of the Sui RPC node to use.
         #[serde(default)]
@@ -164,6 +198,21 @@ impl WalrusCmdBuilder {
         self.with_command(command)
     }
 
+    /// Adds a [`Command::StoreBatch`] command to the builder.
+    pub fn store_batch(
+        self,
+        files: Vec<PathBuf>,
+        epochs: u64,
+        force: bool,
+    ) -> WalrusCmdBuilder<Command> {
+        let command = Command::StoreBatch {
+            files,
+            epochs,
+            force,
+        };
+        self.with_command(command)
+    }
+
     /// Adds a [`Command::Read`] command to the builder.
     #[allow(dead_code)]
     pub fn read(
@@ -195,6 +244,27 @@ impl WalrusCmdBuilder {
         self.with_command(command)
     }
 
+    /// Adds a [`Command::BlobStatus`] command to the builder.
+    pub fn blob_status(self, blob_id: BlobId, rpc_arg: RpcArg) -> WalrusCmdBuilder<Command> {
+        let command = Command::BlobStatus { blob_id, rpc_arg };
+        self.with_command(command)
+    }
+
+    /// Adds a [`Command::Delete`] command to the builder.
+    pub fn delete(self, object_id: ObjectID) -> WalrusCmdBuilder<Command> {
+        let command = Command::Delete {
+            object_id,
+            yes: true,
+        };
+        self.with_command(command)
+    }
+
+    /// Adds a [`Command::ListBlobs`] command to the builder.
+    pub fn list_blobs(self, include_expired: bool) -> WalrusCmdBuilder<Command> {
+        let command = Command::ListBlobs { include_expired };
+        self.with_command(command)
+    }
+
     /// Adds a [`Command::Info`] command to the builder.
     pub fn info(self, rpc_arg: RpcArg, dev: bool) -> WalrusCmdBuilder<Command> {
         let command = Command::Info { rpc_arg, dev };
@@ -79,7 +79,6 @@ pub enum BlobStoreResult {
 
 impl BlobStoreResult {
     /// Returns the blob ID.
-    #[allow(dead_code)]
     pub fn blob_id(&self) -> &BlobId {
         match self {
             Self::AlreadyCertified { blob_id, .. } => blob_id,
@@ -143,6 +142,20 @@ pub struct Blob {
 #[derive(Debug, Clone, Deserialize)]
 pub struct StoreOutput(pub BlobStoreResult);
 
+/// The result of storing one of the files of a batched `store` command.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlobStoreResultWithPath {
+    /// The result of the store operation.
+    pub blob_store_result: BlobStoreResult,
+    /// The path of the stored file.
+    pub path: PathBuf,
+}
+
+/// The output of the batched `store` command.
+#[derive(Debug, Clone, Deserialize)]
+pub struct StoreBatchOutput(pub Vec<BlobStoreResultWithPath>);
+
 /// The output of the `read` command.
 #[serde_as]
 #[derive(Debug, Clone, Deserialize, Eq, PartialEq)]
@@ -167,6 +180,74 @@ pub struct BlobIdOutput {
     pub unencoded_length: u64,
 }
 
+/// The status of a blob on Walrus.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum BlobStatus {
+    /// The blob does not exist (anymore) within Walrus.
+    Nonexistent,
+    /// The blob exists within Walrus in a permanent state.
+    Permanent {
+        /// The latest epoch at which the blob expires (non-inclusive).
+        end_epoch: Epoch,
+        /// Whether the blob is certified (true) or only registered (false).
+        is_certified: bool,
+    },
+    /// The blob exists within Walrus, but only as deletable blobs.
+    Deletable {},
+    /// The blob exists within Walrus; but it was marked as invalid.
+    Invalid {},
+}
+
+impl BlobStatus {
+    /// Returns the epoch until which the blob is certified and stored (exclusive), if any.
+    ///
+    /// Deletable blobs may be deleted at any time, and are therefore not considered.
+    pub fn certified_until(&self) -> Option<Epoch> {
+        match self {
+            Self::Permanent {
+                end_epoch,
+                is_certified: true,
+            } => Some(*end_epoch),
+            _ => None,
+        }
+    }
+
+    /// Returns `true` if the blob is registered, but not certified.
+    ///
+    /// This happens if an upload was interrupted after the registration of the blob; storing the
+    /// blob again reuses the registration.
+    pub fn is_registered_only(&self) -> bool {
+        matches!(
+            self,
+            Self::Permanent {
+                is_certified: false,
+                ..
+            }
+        )
+    }
+}
+
+/// The output of the `blob-status` command.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlobStatusOutput {
+    pub status: BlobStatus,
+}
+
+/// The output of the `delete` command.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeleteOutput {
+    /// The blobs that were deleted.
+    #[serde(default)]
+    pub deleted_blobs: Vec<Blob>,
+}
+
+/// The output of the `list-blobs` command.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ListBlobsOutput(pub Vec<Blob>);
+
 /// The output of the `info` command.
 #[derive(Debug, Clone, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -0,0 +1,136 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Notifications of the result of a publish to a webhook.
+
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use sui_types::base_types::ObjectID;
+
+use crate::{git::GitMetadata, util::http_client_builder};
+
+/// The number of attempts made to deliver a notification.
+const WEBHOOK_ATTEMPTS: usize = 3;
+
+/// The delay between the attempts to deliver a notification.
+const WEBHOOK_RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// The payload posted to the webhook, as JSON.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "status", rename_all = "camelCase")]
+pub(crate) enum WebhookPayload {
+    /// The site was published successfully.
+    #[serde(rename_all = "camelCase")]
+    Success {
+        /// The object ID of the site.
+        site_object: ObjectID,
+        /// The Base36 subdomain of the site on the portal.
+        base36: String,
+        /// The URL of the site on the portal.
+        url: String,
+        /// The digest of the transaction that updated the site, if any was needed.
+        digest: Option<String>,
+        /// The gas used by the transactions, in MIST.
+        gas_used: u64,
+        /// The number of resources of the published site.
+        resource_count: usize,
+        /// The Merkle root of the paths and contents of the resources of the published site.
+        resource_root: String,
+        /// The revision of the sources, if published with `--git-metadata`.
+        #[serde(flatten)]
+        git: Option<GitMetadata>,
+    },
+    /// The publish failed.
+    Failure {
+        /// The error, with its causes.
+        error: String,
+    },
+}
+
+/// A webhook notified of the result of a publish.
+#[derive(Debug, Clone)]
+pub(crate) struct Webhook {
+    url: String,
+    timeout: Duration,
+    /// The proxy used instead of the one set in the environment, if any.
+    proxy: Option<String>,
+}
+
+impl Webhook {
+    pub fn new(url: String, timeout: Duration) -> Self {
+        Self {
+            url,
+            timeout,
+            proxy: None,
+        }
+    }
+
+    /// Sets the proxy through which the webhook is notified.
+    pub fn with_proxy(mut self, proxy: Option<String>) -> Self {
+        self.proxy = proxy;
+        self
+    }
+
+    /// Posts the payload to the webhook, retrying a few times on failure.
+    pub async fn notify(&self, payload: &WebhookPayload) -> Result<()> {
+        let client = http_client_builder(self.proxy.as_deref())?
+            .timeout(self.timeout)
+            .build()?;
+        let mut last_error = None;
+        for attempt in 1..=WEBHOOK_ATTEMPTS {
+            match client
+                .post(&self.url)
+                .json(payload)
+                .send()
+                .await
+                .and_then(|response| response.error_for_status())
+            {
+                Ok(_) => return Ok(()),
+                Err(error) => {
+                    tracing::debug!(attempt, ?error, "failed to notify the webhook");
+                    last_error = Some(error);
+                }
+            }
+            if attempt < WEBHOOK_ATTEMPTS {
+                tokio::time::sleep(WEBHOOK_RETRY_DELAY).await;
+            }
+        }
+        Err(anyhow!(
+            "could not notify the webhook {} after {WEBHOOK_ATTEMPTS} attempts: {}",
+            self.url,
+            last_error.expect("at least one attempt was made")
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_payload_serialization() {
+        let payload = WebhookPayload::Failure {
+            error: "the site was not found".to_owned(),
+        };
+        assert_eq!(
+            serde_json::to_value(&payload).unwrap(),
+            serde_json::json!({"status": "failure", "error": "the site was not found"})
+        );
+        let payload = WebhookPayload::Success {
+            site_object: ObjectID::ZERO,
+            base36: "0".to_owned(),
+            url: "https://0.walrus.site".to_owned(),
+            digest: None,
+            gas_used: 10,
+            resource_count: 2,
+            resource_root: "00".repeat(32),
+            git: None,
+        };
+        let value = serde_json::to_value(&payload).unwrap();
+        assert_eq!(value["status"], "success");
+        assert_eq!(value["resourceCount"], 2);
+        assert!(value.get("gitCommit").is_none());
+    }
+}